@@ -0,0 +1,179 @@
+//! The same scenarios run against every backend, to compare layouts. Set
+//! `QUADTREE_BENCH_POINTS` to a file with one `x,y` pair of integers per line to run them on
+//! your own points as well as the built in uniform and clustered ones.
+
+mod common;
+
+use common::{distributions, get_rng};
+use criterion::measurement::WallTime;
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
+use quadtree::naive::Naive;
+use quadtree::{Boundary, CompressedQuadTree, FrozenQuadTree, Point, QuadTree, SpatialIndex};
+
+/// A way of storing points that the scenarios can build and search.
+trait Backend: Sized {
+    const NAME: &'static str;
+    fn build(boundary: Boundary<u64>, points: &[Point<u64>]) -> Self;
+    fn search(&self, boundary: &Boundary<u64>) -> Vec<Point<u64>>;
+}
+
+fn fill<I: SpatialIndex<u64>>(mut index: I, points: &[Point<u64>]) -> I {
+    for p in points {
+        index.insert(*p);
+    }
+    index
+}
+
+impl Backend for QuadTree<u64> {
+    const NAME: &'static str = "QuadTree";
+    fn build(boundary: Boundary<u64>, points: &[Point<u64>]) -> Self {
+        fill(QuadTree::new(boundary), points)
+    }
+    fn search(&self, boundary: &Boundary<u64>) -> Vec<Point<u64>> {
+        SpatialIndex::search(self, boundary)
+    }
+}
+
+impl Backend for Naive<u64> {
+    const NAME: &'static str = "Naive";
+    fn build(boundary: Boundary<u64>, points: &[Point<u64>]) -> Self {
+        fill(Naive::new(boundary), points)
+    }
+    fn search(&self, boundary: &Boundary<u64>) -> Vec<Point<u64>> {
+        SpatialIndex::search(self, boundary)
+    }
+}
+
+/// Read only, so it's built as a `QuadTree` and then frozen.
+impl Backend for FrozenQuadTree<u64> {
+    const NAME: &'static str = "FrozenQuadTree";
+    fn build(boundary: Boundary<u64>, points: &[Point<u64>]) -> Self {
+        fill(QuadTree::new(boundary), points).freeze()
+    }
+    fn search(&self, boundary: &Boundary<u64>) -> Vec<Point<u64>> {
+        FrozenQuadTree::search(self, boundary)
+    }
+}
+
+impl Backend for CompressedQuadTree<u64> {
+    const NAME: &'static str = "CompressedQuadTree";
+    fn build(boundary: Boundary<u64>, points: &[Point<u64>]) -> Self {
+        let mut qt = CompressedQuadTree::new(boundary);
+        for p in points {
+            qt.insert(*p);
+        }
+        qt
+    }
+    fn search(&self, boundary: &Boundary<u64>) -> Vec<Point<u64>> {
+        CompressedQuadTree::search(self, boundary)
+    }
+}
+
+/// A data set to run the scenarios on, with the boundary around it and some windows to search.
+struct Data {
+    name: String,
+    boundary: Boundary<u64>,
+    points: Vec<Point<u64>>,
+    /// Windows a 200th of the way across, and a fifth of the way across.
+    small: Vec<Boundary<u64>>,
+    large: Vec<Boundary<u64>>,
+}
+
+impl Data {
+    fn new(name: String, points: Vec<Point<u64>>) -> Self {
+        let width = points.iter().map(|p| p.0.max(p.1)).max().unwrap_or(0) + 1;
+        let mut rng = get_rng(width);
+        let mut windows = |size: u64| -> Vec<_> {
+            (0..100)
+                .map(|_| {
+                    let (x, y) = (rng.next(), rng.next());
+                    (x, x + size, y, y + size)
+                })
+                .collect()
+        };
+        Data {
+            name,
+            boundary: (0, width, 0, width),
+            small: windows(width / 200 + 1),
+            large: windows(width / 5 + 1),
+            points,
+        }
+    }
+}
+
+fn data_sets() -> Vec<Data> {
+    let mut sets: Vec<_> = distributions(20_000)
+        .into_iter()
+        .map(|(name, points)| Data::new(name.to_string(), points))
+        .collect();
+    if let Ok(path) = std::env::var("QUADTREE_BENCH_POINTS") {
+        let text = std::fs::read_to_string(&path).expect("QUADTREE_BENCH_POINTS can't be read");
+        let points = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let mut xy = line.split(',').map(|n| n.trim().parse::<u64>());
+                match (xy.next(), xy.next()) {
+                    (Some(Ok(x)), Some(Ok(y))) => (x, y),
+                    _ => panic!("not an x,y pair of integers: {}", line),
+                }
+            })
+            .collect();
+        let name = std::path::Path::new(&path)
+            .file_stem()
+            .map_or("custom".into(), |stem| stem.to_string_lossy().into_owned());
+        sets.push(Data::new(name, points));
+    }
+    sets
+}
+
+fn build<B: Backend>(group: &mut BenchmarkGroup<WallTime>, data: &Data) {
+    group.bench_function(BenchmarkId::new(B::NAME, &data.name), |b| {
+        b.iter(|| B::build(data.boundary, &data.points))
+    });
+}
+
+fn search<B: Backend>(
+    group: &mut BenchmarkGroup<WallTime>,
+    data: &Data,
+    windows: &[Boundary<u64>],
+) {
+    let index = B::build(data.boundary, &data.points);
+    group.bench_function(BenchmarkId::new(B::NAME, &data.name), |b| {
+        b.iter(|| windows.iter().map(|w| index.search(w).len()).sum::<usize>())
+    });
+}
+
+/// Runs `scenario` for every backend and data set, in one group so they end up side by side.
+macro_rules! for_every_backend {
+    ($c:expr, $group:expr, $scenario:ident $(, $arg:ident)*) => {{
+        let mut group = $c.benchmark_group($group);
+        for data in data_sets().iter() {
+            $scenario::<QuadTree<u64>>(&mut group, data $(, &data.$arg)*);
+            $scenario::<Naive<u64>>(&mut group, data $(, &data.$arg)*);
+            $scenario::<FrozenQuadTree<u64>>(&mut group, data $(, &data.$arg)*);
+            $scenario::<CompressedQuadTree<u64>>(&mut group, data $(, &data.$arg)*);
+        }
+        group.finish();
+    }};
+}
+
+pub fn build_benchmark(c: &mut Criterion) {
+    for_every_backend!(c, "Backends/build", build);
+}
+
+pub fn small_search_benchmark(c: &mut Criterion) {
+    for_every_backend!(c, "Backends/100 small searches", search, small);
+}
+
+pub fn large_search_benchmark(c: &mut Criterion) {
+    for_every_backend!(c, "Backends/100 large searches", search, large);
+}
+
+criterion_group!(
+    benches,
+    build_benchmark,
+    small_search_benchmark,
+    large_search_benchmark
+);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+// Not every bench uses everything in here.
+#![allow(dead_code)]
+
+/// Points all over the map.
+pub fn uniform(n: usize) -> Vec<(u64, u64)> {
+    let mut rng = get_rng(10000);
+    (0..n).map(|_| (rng.next(), rng.next())).collect()
+}
+
+/// Points in 20 tight clusters, like towns on a map, which makes the tree a lot deeper in
+/// places.
+pub fn clustered(n: usize) -> Vec<(u64, u64)> {
+    let mut rng = get_rng(10000);
+    let centres: Vec<_> = (0..20)
+        .map(|_| (rng.next() % 9600 + 200, rng.next() % 9600 + 200))
+        .collect();
+    (0..n)
+        .map(|i| {
+            let (x, y) = centres[i % centres.len()];
+            (x + rng.next() % 400 - 200, y + rng.next() % 400 - 200)
+        })
+        .collect()
+}
+
+/// Both distributions with their names, to run the same bench over each.
+pub fn distributions(n: usize) -> Vec<(&'static str, Vec<(u64, u64)>)> {
+    vec![("uniform", uniform(n)), ("clustered", clustered(n))]
+}
+
+pub struct XorShift64 {
+    a: u64,
+    upper: u64,
+}
+
+impl XorShift64 {
+    pub fn next(&mut self) -> u64 {
+        let mut x = self.a;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.a = x;
+        x % self.upper
+    }
+}
+
+pub fn get_rng(upper: u64) -> XorShift64 {
+    XorShift64 {
+        a: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs(),
+        upper,
+    }
+}
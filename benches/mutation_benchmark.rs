@@ -0,0 +1,99 @@
+mod common;
+
+use common::{distributions, get_rng};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use quadtree::{DynamicIndex, QuadTree};
+
+const POINTS: usize = 20_000;
+
+fn build(capacity: usize, points: &[(u64, u64)]) -> QuadTree<u64> {
+    let mut qt = QuadTree::with_node_capacity(capacity, (0, 10000, 0, 10000));
+    for p in points {
+        qt.insert(*p);
+    }
+    qt
+}
+
+/// Taking every point out again, which merges leaves on the way.
+pub fn remove_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Remove");
+    group.throughput(Throughput::Elements(POINTS as u64));
+    for (name, points) in distributions(POINTS) {
+        for capacity in [4, 16, 64].iter() {
+            let qt = build(*capacity, &points);
+            group.bench_with_input(BenchmarkId::new(name, capacity), capacity, |b, _| {
+                b.iter_batched(
+                    || qt.clone(),
+                    |mut qt| {
+                        for p in points.iter() {
+                            qt.remove(p);
+                        }
+                        qt
+                    },
+                    BatchSize::LargeInput,
+                );
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Moving a tenth of the points a little way, as a remove and an insert on a plain tree and as a
+/// tick on a `DynamicIndex`.
+pub fn relocate_benchmark(c: &mut Criterion) {
+    let mut rng = get_rng(20);
+    let mut group = c.benchmark_group("Relocate");
+    group.throughput(Throughput::Elements(POINTS as u64 / 10));
+    for (name, points) in distributions(POINTS) {
+        let moves: Vec<_> = points
+            .iter()
+            .step_by(10)
+            .map(|(x, y)| {
+                (
+                    *x,
+                    *y,
+                    ((x + rng.next()).min(9999), (y + rng.next()).min(9999)),
+                )
+            })
+            .collect();
+
+        let qt = build(16, &points);
+        group.bench_function(BenchmarkId::new("QuadTree", name), |b| {
+            b.iter_batched(
+                || qt.clone(),
+                |mut qt| {
+                    for (x, y, to) in moves.iter() {
+                        qt.remove(&(*x, *y));
+                        qt.insert(*to);
+                    }
+                    qt
+                },
+                BatchSize::LargeInput,
+            );
+        });
+
+        let mut index = DynamicIndex::with_node_capacity(16, (0, 10000, 0, 10000));
+        let ids: Vec<_> = points
+            .iter()
+            .step_by(10)
+            .map(|p| index.insert(*p, ()).unwrap())
+            .collect();
+        group.bench_function(BenchmarkId::new("DynamicIndex", name), |b| {
+            b.iter_batched(
+                || index.clone(),
+                |mut index| {
+                    for (id, (_, _, to)) in ids.iter().zip(moves.iter()) {
+                        index.move_to(*id, *to);
+                    }
+                    index.tick();
+                    index
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, remove_benchmark, relocate_benchmark);
+criterion_main!(benches);
@@ -1,32 +1,9 @@
-use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use quadtree::{Boundary, Midpoint, Point, QuadTree};
+mod common;
 
-struct Naive<T: PartialOrd + Copy + Midpoint> {
-    boundary: Boundary<T>,
-    points: Vec<Point<T>>,
-}
-
-impl<T: PartialOrd + Copy + Midpoint> Naive<T>
-where
-    T: PartialOrd + Copy + Midpoint,
-{
-    fn insert(&mut self, point: Point<T>) {
-        if !QuadTree::contains(&self.boundary, &point) {
-            return;
-        }
-        if !self.points.iter().any(|p| *p == point) {
-            self.points.push(point);
-        }
-    }
-
-    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
-        self.points
-            .iter()
-            .filter(|point| QuadTree::contains(boundary, point))
-            .copied()
-            .collect()
-    }
-}
+use common::{distributions, get_rng};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use quadtree::naive::Naive;
+use quadtree::QuadTree;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let mut rng = get_rng(10000);
@@ -43,10 +20,7 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     while at <= to {
         let mut qt = QuadTree::new((0, 10000, 0, 10000));
-        let mut naive = Naive {
-            boundary: (0, 10000, 0, 10000),
-            points: vec![],
-        };
+        let mut naive = Naive::new((0, 10000, 0, 10000));
         for _ in 0..at {
             let p = (rng.next(), rng.next());
             qt.insert(p);
@@ -67,31 +41,51 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, criterion_benchmark);
-criterion_main!(benches);
-
-struct XorShift64 {
-    a: u64,
-    upper: u64,
-}
-
-impl XorShift64 {
-    pub fn next(&mut self) -> u64 {
-        let mut x = self.a;
-        x ^= x << 13;
-        x ^= x >> 7;
-        x ^= x << 17;
-        self.a = x;
-        x % self.upper
+/// Building is mostly allocating nodes, so this is the one to watch when changing how they're
+/// laid out. Clustered points make for deeper trees than uniform ones.
+pub fn build_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Build");
+    group.throughput(Throughput::Elements(20_000));
+    for (name, points) in distributions(20_000) {
+        for capacity in [4, 16, 64].iter() {
+            group.bench_with_input(BenchmarkId::new(name, capacity), capacity, |b, capacity| {
+                b.iter(|| {
+                    let mut qt = QuadTree::with_node_capacity(*capacity, (0, 10000, 0, 10000));
+                    for p in points.iter() {
+                        qt.insert(*p);
+                    }
+                    qt
+                });
+            });
+        }
     }
+    group.finish();
 }
 
-fn get_rng(upper: u64) -> XorShift64 {
-    XorShift64 {
-        a: std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs(),
-        upper,
+pub fn frozen_benchmark(c: &mut Criterion) {
+    let mut rng = get_rng(10000);
+    let mut qt = QuadTree::new((0, 10000, 0, 10000));
+    for _ in 0..50_000 {
+        qt.insert((rng.next(), rng.next()));
     }
+    let frozen = qt.freeze();
+    let mut group = c.benchmark_group("Frozen");
+    for size in [50, 500, 2000].iter() {
+        let search_boundary = (1000, 1000 + size, 2000, 2000 + size);
+        group.bench_with_input(BenchmarkId::new("QuadTree", size), size, |b, _| {
+            b.iter(|| qt.search(&search_boundary));
+        });
+        group.bench_with_input(BenchmarkId::new("FrozenQuadTree", size), size, |b, _| {
+            b.iter(|| frozen.search(&search_boundary));
+        });
+    }
+    group.finish();
 }
+
+criterion_group!(
+    benches,
+    criterion_benchmark,
+    build_benchmark,
+    frozen_benchmark
+);
+criterion_main!(benches);
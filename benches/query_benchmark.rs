@@ -0,0 +1,56 @@
+mod common;
+
+use common::{distributions, get_rng};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use quadtree::QuadTree;
+
+const POINTS: usize = 50_000;
+
+fn build(capacity: usize, points: &[(u64, u64)]) -> QuadTree<u64> {
+    let mut qt = QuadTree::with_node_capacity(capacity, (0, 10000, 0, 10000));
+    for p in points {
+        qt.insert(*p);
+    }
+    qt
+}
+
+/// The nearest 1, 10 and 100 points to one of the stored points, so clustered trees are asked
+/// about the crowded spots.
+pub fn knn_benchmark(c: &mut Criterion) {
+    let mut rng = get_rng(POINTS as u64);
+    let mut group = c.benchmark_group("Knn");
+    for (name, points) in distributions(POINTS) {
+        let at = points[rng.next() as usize];
+        for capacity in [4, 16, 64].iter() {
+            let qt = build(*capacity, &points);
+            for k in [1, 10, 100].iter() {
+                let id = format!("{}/capacity {}", name, capacity);
+                group.bench_with_input(BenchmarkId::new(id, k), k, |b, k| {
+                    b.iter(|| qt.knn(&at, *k));
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+pub fn radius_benchmark(c: &mut Criterion) {
+    let mut rng = get_rng(POINTS as u64);
+    let mut group = c.benchmark_group("Radius");
+    for (name, points) in distributions(POINTS) {
+        let at = points[rng.next() as usize];
+        for capacity in [4, 16, 64].iter() {
+            let qt = build(*capacity, &points);
+            for radius in [10.0, 100.0, 500.0].iter() {
+                let id = format!("{}/capacity {}", name, capacity);
+                group.bench_with_input(BenchmarkId::new(id, radius), radius, |b, radius| {
+                    b.iter(|| qt.search_radius(&at, *radius));
+                });
+            }
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, knn_benchmark, radius_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+//! Loads points from a CSV or GeoJSON file and answers a query against them.
+//!
+//!     cargo run --example quadtree-cli -- points.csv rect 0 10 0 10
+//!     cargo run --example quadtree-cli -- points.csv radius 5 5 2.5
+//!     cargo run --example quadtree-cli -- points.csv knn 5 5 3
+//!
+//! A CSV file needs `x,y` in its first two columns. Lines that don't parse (like a header) are
+//! skipped. A file starting with `{` is read as GeoJSON, taking the positions of every `Point`
+//! and `MultiPoint` in it. Matching points are printed as `x,y`, one per line.
+use quadtree::QuadTree;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::process;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 2 {
+        usage();
+    }
+
+    let content = fs::read_to_string(&args[0]).unwrap_or_else(|e| {
+        eprintln!("Could not read {}: {}", args[0], e);
+        process::exit(1);
+    });
+    let points = if content.trim_start().starts_with('{') {
+        parse_geojson(&content)
+    } else {
+        parse_points(&content)
+    };
+    // The tree needs to know its size up front, so it's made just big enough to hold every point.
+    let qt = QuadTree::from_points_autofit(points)
+        .unwrap_or_else(|| QuadTree::new((0.0, 1.0, 0.0, 1.0)));
+    eprintln!("Loaded {} points", qt.size());
+
+    let numbers: Vec<f64> = args[2..].iter().map(|a| number(a)).collect();
+    let found = match (args[1].as_str(), numbers.as_slice()) {
+        ("rect", [x1, x2, y1, y2]) => qt.search(&(*x1, *x2, *y1, *y2)),
+        ("radius", [x, y, r]) => qt.search_radius(&(*x, *y), *r),
+        ("knn", [x, y, k]) => qt.knn(&(*x, *y), *k as usize),
+        _ => usage(),
+    };
+
+    for (x, y) in found {
+        println!("{},{}", x, y);
+    }
+}
+
+fn parse_points(content: &str) -> Vec<(f64, f64)> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split(',').map(|c| c.trim().parse::<f64>());
+            match (columns.next(), columns.next()) {
+                (Some(Ok(x)), Some(Ok(y))) if !x.is_nan() && !y.is_nan() => Some((x, y)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn parse_geojson(content: &str) -> Vec<(f64, f64)> {
+    let json: Value = serde_json::from_str(content).unwrap_or_else(|e| {
+        eprintln!("Not valid GeoJSON: {}", e);
+        process::exit(1);
+    });
+    let mut points = vec![];
+    collect_geojson(&json, &mut points);
+    points
+}
+
+/// Goes through feature collections, features and geometry collections down to the points.
+fn collect_geojson(json: &Value, points: &mut Vec<(f64, f64)>) {
+    let position = |p: &Value| match (
+        p.get(0).and_then(Value::as_f64),
+        p.get(1).and_then(Value::as_f64),
+    ) {
+        (Some(x), Some(y)) => Some((x, y)),
+        _ => None,
+    };
+    match json.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => {
+            for feature in json["features"].as_array().into_iter().flatten() {
+                collect_geojson(feature, points);
+            }
+        }
+        Some("Feature") => collect_geojson(&json["geometry"], points),
+        Some("GeometryCollection") => {
+            for geometry in json["geometries"].as_array().into_iter().flatten() {
+                collect_geojson(geometry, points);
+            }
+        }
+        Some("Point") => points.extend(position(&json["coordinates"])),
+        Some("MultiPoint") => points.extend(
+            json["coordinates"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(position),
+        ),
+        _ => {}
+    }
+}
+
+fn number(arg: &str) -> f64 {
+    match arg.parse::<f64>() {
+        Ok(n) if !n.is_nan() => n,
+        _ => {
+            eprintln!("Not a number: {}", arg);
+            process::exit(1);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: quadtree-cli <points.csv> <query>");
+    eprintln!();
+    eprintln!("Queries:");
+    eprintln!("  rect <x1> <x2> <y1> <y2>   points inside the rectangle");
+    eprintln!("  radius <x> <y> <r>         points within r of (x, y)");
+    eprintln!("  knn <x> <y> <k>            the k points closest to (x, y)");
+    process::exit(1);
+}
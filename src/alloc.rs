@@ -0,0 +1,186 @@
+use crate::{Boundary, BuildError, Midpoint, Point, QuadTree};
+use std::alloc::{Allocator, Global};
+
+/// A quadtree whose nodes and leaf vectors are allocated with `A` instead of the global
+/// allocator, e.g. an arena that gets thrown away once per frame. Needs the `allocator_api`
+/// feature, which needs nightly.
+///
+/// Every child and leaf `Vec` gets a clone of the allocator, so one with state should be a
+/// cheap handle to it. There's no remove, which suits arenas that can't free a single
+/// allocation anyway.
+///
+/// Only the capacity can be set. Leaves always scan their points for duplicates, and none of the
+/// other `Config` settings (tolerance, grid, max depth) are there.
+#[derive(Debug)]
+pub enum QuadTreeIn<T: PartialOrd + Copy + Midpoint, A: Allocator + Clone = Global> {
+    Leaf(usize, Boundary<T>, Vec<Point<T>, A>),
+    Node(usize, Boundary<T>, [Box<QuadTreeIn<T, A>, A>; 4]),
+}
+
+impl<T, A> QuadTreeIn<T, A>
+where
+    T: PartialOrd + Copy + Midpoint,
+    A: Allocator + Clone,
+{
+    pub fn new_in(boundary: Boundary<T>, alloc: A) -> Self {
+        Self::with_node_capacity_in(64, boundary, alloc)
+    }
+
+    /// Panics if `capacity` is 0, as every insert would subdivide forever.
+    pub fn with_node_capacity_in(capacity: usize, boundary: Boundary<T>, alloc: A) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        QuadTreeIn::Leaf(capacity, boundary, Vec::new_in(alloc))
+    }
+
+    /// Same as `with_node_capacity_in`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_node_capacity_in(
+        capacity: usize,
+        boundary: Boundary<T>,
+        alloc: A,
+    ) -> Result<Self, BuildError> {
+        if capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        Ok(Self::with_node_capacity_in(capacity, boundary, alloc))
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> bool {
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+
+        if let QuadTreeIn::Leaf(capacity, _, points) = self {
+            if points.contains(&point) {
+                return true;
+            }
+            if points.len() < *capacity {
+                points.push(point);
+                return true;
+            }
+        }
+
+        if let QuadTreeIn::Leaf(capacity, boundary, points) = self {
+            let alloc = points.allocator().clone();
+            let leaf = |b| {
+                let leaf = QuadTreeIn::Leaf(*capacity, b, Vec::new_in(alloc.clone()));
+                Box::new_in(leaf, alloc.clone())
+            };
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let mut children = [leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)];
+            for point in points.drain(..) {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(point);
+            }
+            *self = QuadTreeIn::Node(*capacity, *boundary, children);
+        }
+
+        match self {
+            QuadTreeIn::Leaf(_, _, _) => panic!("We should never be a leaf at this point"),
+            QuadTreeIn::Node(_, boundary, children) => {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(point)
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            QuadTreeIn::Leaf(_, _, points) => points.len(),
+            QuadTreeIn::Node(_, _, children) => children.iter().map(|c| c.size()).sum(),
+        }
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            QuadTreeIn::Leaf(_, _, points) => {
+                found.extend(points.iter().filter(|p| QuadTree::contains(boundary, p)))
+            }
+            QuadTreeIn::Node(_, _, children) => {
+                for child in children {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            QuadTreeIn::Leaf(_, boundary, _) => *boundary,
+            QuadTreeIn::Node(_, boundary, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QuadTreeIn;
+    use crate::{BuildError, QuadTree};
+    use std::alloc::{AllocError, Allocator, Global, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+    use std::rc::Rc;
+
+    /// Counts allocations, and otherwise hands everything to the global allocator.
+    #[derive(Clone, Default)]
+    struct Counting(Rc<Cell<usize>>);
+
+    unsafe impl Allocator for Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.0.set(self.0.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    #[test]
+    fn uses_the_allocator() {
+        let alloc = Counting::default();
+        let mut qt = QuadTreeIn::with_node_capacity_in(4, (0, 100, 0, 100), alloc.clone());
+        let mut reference = QuadTree::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            for j in 0..10 {
+                qt.insert((i, j * 10));
+                reference.insert((i, j * 10));
+            }
+        }
+        qt.insert((5, 5));
+        qt.insert((5, 5));
+        reference.insert((5, 5));
+        assert_eq!(qt.size(), reference.size());
+        assert!(alloc.0.get() > 100);
+
+        let mut a = qt.search(&(10, 40, 20, 60));
+        let mut b = reference.search(&(10, 40, 20, 60));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        // A point that's already in a full leaf doesn't split it.
+        let mut full = QuadTreeIn::with_node_capacity_in(2, (0, 10, 0, 10), Global);
+        full.insert((1, 1));
+        full.insert((2, 2));
+        assert!(full.insert((1, 1)));
+        assert!(matches!(full, QuadTreeIn::Leaf(_, _, _)));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn zero_capacity() {
+        assert_eq!(
+            QuadTreeIn::<u32>::try_with_node_capacity_in(0, (0, 10, 0, 10), Global).unwrap_err(),
+            BuildError::ZeroCapacity
+        );
+        assert!(QuadTreeIn::<u32>::try_with_node_capacity_in(1, (0, 10, 0, 10), Global).is_ok());
+        QuadTreeIn::<u32>::with_node_capacity_in(0, (0, 10, 0, 10), Global);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::geom::{covers, overlap_area};
+use crate::{Boundary, Distance, Midpoint, QuadTree};
+use std::collections::BinaryHeap;
+
+/// The result of `approx_count_in`. The real count is somewhere from `low` to `high`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CountEstimate {
+    pub low: usize,
+    pub high: usize,
+    /// A guess at the real count, assuming points are spread evenly inside the nodes that were
+    /// only partly covered.
+    pub estimate: f64,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// Counts the points in `boundary` without looking at all of them. Nodes entirely inside it
+    /// are counted using the size they keep, and nodes only partly inside are split further, the
+    /// biggest first, until they hold no more than `max_error` points between them. With a
+    /// `max_error` of 0 the count is exact.
+    pub fn approx_count_in(&self, boundary: &Boundary<T>, max_error: usize) -> CountEstimate {
+        let mut counter = Counter {
+            boundary,
+            low: 0,
+            uncertain: 0,
+            partial: vec![],
+            heap: BinaryHeap::new(),
+        };
+        counter.visit(self);
+        while counter.uncertain > max_error {
+            let (size, i) = counter
+                .heap
+                .pop()
+                .expect("uncertain points are in the heap");
+            counter.uncertain -= size;
+            if let Some(QuadTree::Node(_, _, _, children)) = counter.partial[i].take() {
+                for child in children.iter() {
+                    counter.visit(child);
+                }
+            }
+        }
+
+        let mut estimate = counter.low as f64;
+        for node in counter.partial.into_iter().flatten() {
+            let b = node.get_boundary();
+            let area = overlap_area(b, b);
+            if area > 0.0 {
+                estimate += node.size() as f64 * overlap_area(b, boundary) / area;
+            }
+        }
+        CountEstimate {
+            low: counter.low,
+            high: counter.low + counter.uncertain,
+            estimate,
+        }
+    }
+}
+
+struct Counter<'a, T: PartialOrd + Copy + Midpoint> {
+    boundary: &'a Boundary<T>,
+    low: usize,
+    /// Points in nodes that are only partly inside the boundary.
+    uncertain: usize,
+    /// Those nodes. They're taken out once they've been split.
+    partial: Vec<Option<&'a QuadTree<T>>>,
+    /// Their sizes and where they are in `partial`, biggest first.
+    heap: BinaryHeap<(usize, usize)>,
+}
+
+impl<'a, T> Counter<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn visit(&mut self, node: &'a QuadTree<T>) {
+        let node_boundary = node.get_boundary();
+        if !QuadTree::intersects(node_boundary, self.boundary) {
+            return;
+        }
+        if covers(self.boundary, node_boundary) {
+            self.low += node.size();
+            return;
+        }
+        match node {
+            QuadTree::Leaf(_, _, points) => {
+                let inside = points
+                    .iter()
+                    .filter(|p| QuadTree::contains(self.boundary, p));
+                self.low += inside.count();
+            }
+            QuadTree::Node(_, _, size, _) => {
+                self.uncertain += size;
+                self.heap.push((*size, self.partial.len()));
+                self.partial.push(Some(node));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn approx_count_in() {
+        let mut qt = Q::with_node_capacity(8, (0, 1000, 0, 1000));
+        let mut rng = crate::tests::get_rng();
+        for _ in 0..20_000 {
+            qt.insert((rng.next() as u32, rng.next() as u32));
+        }
+        let search = (123, 789, 50, 601);
+        let exact = qt.search(&search).len();
+
+        let count = qt.approx_count_in(&search, 0);
+        assert_eq!((count.low, count.high), (exact, exact));
+        assert_eq!(count.estimate, exact as f64);
+
+        let count = qt.approx_count_in(&search, 500);
+        assert!(count.low <= exact && exact <= count.high);
+        assert!(count.high - count.low <= 500);
+        assert!((count.estimate - exact as f64).abs() < 500.0);
+
+        assert_eq!(qt.approx_count_in(&(0, 1000, 0, 1000), 0).low, qt.size());
+    }
+}
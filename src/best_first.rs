@@ -0,0 +1,132 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Goes through the tree best first, handing out points lowest score first along with their
+    /// score. `priority` gives each node a score that no point inside it can beat (for nearest
+    /// neighbours, the distance to its boundary), or `None` to skip it. `score` does the same for
+    /// points. Nodes are only opened once nothing better is left, so stopping after a few results
+    /// doesn't look at the rest of the tree. kNN, ray casting and most relevant first searches can
+    /// all be built on it.
+    pub fn best_first<'a, P, S>(
+        &'a self,
+        mut priority: P,
+        mut score: S,
+    ) -> impl Iterator<Item = (f64, Point<T>)> + 'a
+    where
+        P: FnMut(&Boundary<T>) -> Option<f64> + 'a,
+        S: FnMut(&Point<T>) -> Option<f64> + 'a,
+    {
+        let mut heap = BinaryHeap::new();
+        if let Some(p) = priority(self.get_boundary()) {
+            heap.push(Queued(p, Item::Tree(self)));
+        }
+        std::iter::from_fn(move || loop {
+            match heap.pop()? {
+                Queued(s, Item::Point(p)) => return Some((s, p)),
+                Queued(_, Item::Tree(QuadTree::Leaf(_, _, points))) => {
+                    for p in points {
+                        if let Some(s) = score(p) {
+                            heap.push(Queued(s, Item::Point(*p)));
+                        }
+                    }
+                }
+                Queued(_, Item::Tree(QuadTree::Node(_, _, _, children))) => {
+                    for child in children.iter() {
+                        if let Some(p) = priority(child.get_boundary()) {
+                            heap.push(Queued(p, Item::Tree(child)));
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
+enum Item<'a, T: PartialOrd + Copy + Midpoint> {
+    Tree(&'a QuadTree<T>),
+    Point(Point<T>),
+}
+
+/// Ordered so the heap pops the lowest score first, and points before nodes with the same score.
+struct Queued<'a, T: PartialOrd + Copy + Midpoint>(f64, Item<'a, T>);
+
+impl<T: PartialOrd + Copy + Midpoint> Ord for Queued<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let is_point = |q: &Self| matches!(q.1, Item::Point(_));
+        other
+            .0
+            .total_cmp(&self.0)
+            .then_with(|| is_point(self).cmp(&is_point(other)))
+    }
+}
+
+impl<T: PartialOrd + Copy + Midpoint> PartialOrd for Queued<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: PartialOrd + Copy + Midpoint> PartialEq for Queued<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: PartialOrd + Copy + Midpoint> Eq for Queued<'_, T> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::nearest::{distance_sq, distance_to_boundary_sq};
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn best_first() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..1000 {
+            qt.insert((rng.next(), rng.next()));
+        }
+
+        // Nearest neighbours.
+        let at = (300, 700);
+        let near: Vec<_> = qt
+            .best_first(
+                |b| Some(distance_to_boundary_sq(b, &at)),
+                |p| Some(distance_sq(&at, p)),
+            )
+            .take(10)
+            .map(|(_, p)| p)
+            .collect();
+        let expected = qt.knn(&at, 10);
+        let d = |p| distance_sq(&at, p);
+        assert_eq!(
+            near.iter().map(d).collect::<Vec<_>>(),
+            expected.iter().map(d).collect::<Vec<_>>()
+        );
+
+        // Something of our own: the points furthest right in the top half, skipping a column.
+        let right: Vec<_> = qt
+            .best_first(
+                |b| if b.2 < 500 { Some(-(b.1 as f64)) } else { None },
+                |p| {
+                    if p.1 < 500 && p.0 != 999 {
+                        Some(-(p.0 as f64))
+                    } else {
+                        None
+                    }
+                },
+            )
+            .map(|(_, p)| p)
+            .collect();
+        let mut expected = qt.search(&(0, 999, 0, 500));
+        expected.sort_by_key(|p| std::cmp::Reverse(p.0));
+        assert_eq!(right.len(), expected.len());
+        assert!(right.windows(2).all(|w| w[0].0 >= w[1].0));
+        assert_eq!(right[0].0, expected[0].0);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+use std::time::{Duration, Instant};
+
+/// How much work a `PartialSearch` may do before it stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Visit at most this many nodes and leaves.
+    Nodes(usize),
+    /// Stop once this much time has passed. It's checked between nodes, so a big leaf can make it
+    /// run over a little.
+    Time(Duration),
+}
+
+/// A search that can be stopped part way and picked up again, e.g. to show what's been found so
+/// far every frame. Made with `search_budgeted`.
+#[derive(Debug, Clone)]
+pub struct PartialSearch<'a, T: PartialOrd + Copy + Midpoint> {
+    boundary: Boundary<T>,
+    stack: Vec<&'a QuadTree<T>>,
+    found: Vec<Point<T>>,
+}
+
+impl<'a, T> PartialSearch<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Keeps searching until it's done or `budget` runs out. Returns whether it's done.
+    pub fn resume(&mut self, budget: Budget) -> bool {
+        let started = Instant::now();
+        let mut visited = 0;
+        let boundary = self.boundary;
+        while let Some(node) = self.stack.pop() {
+            match node {
+                QuadTree::Leaf(_, _, points) => self
+                    .found
+                    .extend(points.iter().filter(|p| QuadTree::contains(&boundary, p))),
+                QuadTree::Node(_, _, _, children) => self.stack.extend(
+                    children
+                        .iter()
+                        .rev()
+                        .filter(|c| QuadTree::intersects(c.get_boundary(), &boundary)),
+                ),
+            }
+            visited += 1;
+            let out_of_budget = match budget {
+                Budget::Nodes(n) => visited >= n,
+                Budget::Time(time) => started.elapsed() >= time,
+            };
+            if out_of_budget {
+                break;
+            }
+        }
+        self.is_complete()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Everything found so far.
+    pub fn found(&self) -> &[Point<T>] {
+        &self.found
+    }
+
+    pub fn into_found(self) -> Vec<Point<T>> {
+        self.found
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Starts a search that only does as much as `budget` allows. Check `is_complete` on the
+    /// result to see if that was enough, and `resume` it to find the rest.
+    pub fn search_budgeted(&self, boundary: &Boundary<T>, budget: Budget) -> PartialSearch<'_, T> {
+        let mut search = PartialSearch {
+            boundary: *boundary,
+            stack: vec![],
+            found: vec![],
+        };
+        if Self::intersects(self.get_boundary(), boundary) {
+            search.stack.push(self);
+        }
+        search.resume(budget);
+        search
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Budget;
+    use crate::QuadTree as Q;
+    use std::time::Duration;
+
+    #[test]
+    fn search_budgeted() {
+        let mut qt = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            for j in 0..100 {
+                qt.insert((i, j));
+            }
+        }
+        let search = (10, 60, 10, 60);
+
+        let mut partial = qt.search_budgeted(&search, Budget::Nodes(20));
+        assert!(!partial.is_complete());
+        let so_far = partial.found().len();
+        assert!(so_far < 2500);
+        while !partial.resume(Budget::Nodes(20)) {}
+        let mut found = partial.into_found();
+        let mut expected = qt.search(&search);
+        found.sort();
+        expected.sort();
+        assert_eq!(found, expected);
+
+        let done = qt.search_budgeted(&search, Budget::Time(Duration::from_secs(60)));
+        assert!(done.is_complete());
+        assert_eq!(done.found().len(), 2500);
+        assert!(qt
+            .search_budgeted(&(200, 300, 0, 10), Budget::Nodes(1))
+            .is_complete());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::{Boundary, Config, Duplicates, Grid, Midpoint, QuadTree, Tolerance};
+use std::fmt;
+
+/// Why a `QuadTreeBuilder` couldn't build a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The boundary has `x1 >= x2` or `y1 >= y2` (or NaN in it), so no point could ever be inside.
+    DegenerateBoundary,
+    /// A capacity of 0 would mean subdividing on every insert, forever.
+    ZeroCapacity,
+    /// Storing the same point more than `capacity` times can only end if there's a maximum depth.
+    DuplicatesNeedMaxDepth,
+    /// A grid resolution of 0 (or less, or NaN) has no cells to snap points to.
+    BadResolution,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::DegenerateBoundary => {
+                write!(f, "boundary must have x1 < x2 and y1 < y2")
+            }
+            BuildError::ZeroCapacity => write!(f, "capacity must be at least 1"),
+            BuildError::DuplicatesNeedMaxDepth => {
+                write!(f, "allowing duplicates needs a max depth")
+            }
+            BuildError::BadResolution => write!(f, "grid resolution must be above 0"),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Sets up a tree one setting at a time, and checks that the settings make sense before building
+/// it:
+///
+/// `QuadTreeBuilder::new(boundary).capacity(32).max_depth(16).build()?`
+#[derive(Debug, Clone, Copy)]
+pub struct QuadTreeBuilder<T> {
+    boundary: Boundary<T>,
+    config: Config<T>,
+}
+
+impl<T> QuadTreeBuilder<T>
+where
+    T: PartialOrd + Clone + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        QuadTreeBuilder {
+            boundary,
+            config: Config::default(),
+        }
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.config.capacity = capacity;
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.config.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn duplicates(mut self, duplicates: Duplicates) -> Self {
+        self.config.duplicates = duplicates;
+        self
+    }
+
+    /// Store the same point as many times as it's inserted. Same as `Duplicates::Trust`, and
+    /// `false` goes back to the default of `Duplicates::Linear`.
+    pub fn allow_duplicates(self, allow: bool) -> Self {
+        self.duplicates(if allow {
+            Duplicates::Trust
+        } else {
+            Duplicates::Linear
+        })
+    }
+
+    pub fn tolerance(mut self, tolerance: Tolerance<T>) -> Self {
+        self.config.tolerance = Some(tolerance);
+        self
+    }
+
+    pub fn grid(mut self, grid: Grid<T>) -> Self {
+        self.config.grid = Some(grid);
+        self
+    }
+
+    pub fn build(self) -> Result<QuadTree<T>, BuildError> {
+        QuadTree::try_with_config(self.config, self.boundary)
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Clone + Midpoint,
+{
+    /// Same as `new`, but fails instead of making a tree nothing can be inserted into.
+    pub fn try_new(boundary: Boundary<T>) -> Result<Self, BuildError> {
+        QuadTreeBuilder::new(boundary).build()
+    }
+
+    /// Same as `with_node_capacity`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_node_capacity(
+        capacity: usize,
+        boundary: Boundary<T>,
+    ) -> Result<Self, BuildError> {
+        QuadTreeBuilder::new(boundary).capacity(capacity).build()
+    }
+
+    /// Same as `with_config`, but checks everything `QuadTreeBuilder` does.
+    pub fn try_with_config(config: Config<T>, boundary: Boundary<T>) -> Result<Self, BuildError> {
+        if degenerate(&boundary) {
+            return Err(BuildError::DegenerateBoundary);
+        }
+        if config.capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        if config.duplicates == Duplicates::Trust && config.max_depth.is_none() {
+            return Err(BuildError::DuplicatesNeedMaxDepth);
+        }
+        Ok(QuadTree::with_config(config, boundary))
+    }
+}
+
+/// Whether no point could ever be inside `boundary`.
+pub(crate) fn degenerate<T: PartialOrd>((x1, x2, y1, y2): &Boundary<T>) -> bool {
+    !(x1 < x2 && y1 < y2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuildError, QuadTreeBuilder};
+    use crate::{Duplicates, QuadTree as Q};
+
+    #[test]
+    fn build() {
+        let qt = QuadTreeBuilder::new((0, 100, 0, 100))
+            .capacity(32)
+            .max_depth(16)
+            .allow_duplicates(true)
+            .build()
+            .unwrap();
+        let config = qt.get_config();
+        assert_eq!(config.capacity, 32);
+        assert_eq!(config.max_depth, Some(16));
+        assert_eq!(config.duplicates, Duplicates::Trust);
+
+        let build = |b| QuadTreeBuilder::new(b).build().map(|_| ());
+        assert_eq!(build((10, 0, 0, 10)), Err(BuildError::DegenerateBoundary));
+        assert_eq!(build((0, 10, 5, 5)), Err(BuildError::DegenerateBoundary));
+        assert_eq!(
+            QuadTreeBuilder::new((0.0, f64::NAN, 0.0, 1.0))
+                .build()
+                .map(|_| ()),
+            Err(BuildError::DegenerateBoundary)
+        );
+        assert_eq!(
+            QuadTreeBuilder::new((0, 1, 0, 1))
+                .capacity(0)
+                .build()
+                .map(|_| ()),
+            Err(BuildError::ZeroCapacity)
+        );
+        assert_eq!(
+            QuadTreeBuilder::new((0, 1, 0, 1))
+                .allow_duplicates(true)
+                .build()
+                .map(|_| ()),
+            Err(BuildError::DuplicatesNeedMaxDepth)
+        );
+    }
+
+    #[test]
+    fn try_new() {
+        assert!(Q::try_new((0, 10, 0, 10)).is_ok());
+        let err = Q::try_new((10, 0, 0, 10)).unwrap_err();
+        assert_eq!(err, BuildError::DegenerateBoundary);
+        assert_eq!(err.to_string(), "boundary must have x1 < x2 and y1 < y2");
+        assert!(Q::try_new((0.0, 1.0, 2.0, 2.0)).is_err());
+    }
+
+    #[test]
+    fn tiny_capacities() {
+        assert_eq!(
+            Q::try_with_node_capacity(0, (0, 10, 0, 10)).unwrap_err(),
+            BuildError::ZeroCapacity
+        );
+
+        // A duplicate used to split a full leaf, and with room for one point that never ended.
+        let mut qt = Q::try_with_node_capacity(1, (0, 10, 0, 10)).unwrap();
+        qt.insert((3, 3));
+        qt.insert((3, 3));
+        assert!(matches!(qt, Q::Leaf(_, _, _)));
+        qt.insert((3, 4));
+        qt.insert((3, 4));
+        qt.insert((3, 3));
+        assert_eq!(qt.size(), 2);
+        assert_eq!(qt.search(&(0, 10, 0, 10)).len(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn zero_capacity_panics() {
+        Q::with_node_capacity(0, (0, 10, 0, 10));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{Boundary, BuildError, Midpoint, Point, QuadTree};
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+
+/// A quadtree that lives entirely inside a `bumpalo::Bump` arena. Nodes and leaf vectors are all
+/// bump allocated, so building is cheap and dropping is free: reset or drop the arena and
+/// everything is gone at once. Meant for trees that are rebuilt from scratch every frame.
+///
+/// Leaves are given room for `capacity` points up front, since a leaf `Vec` that grew would
+/// leave its old buffer behind in the arena. For the same reason there's no remove.
+///
+/// Only the capacity can be set. Leaves always scan their points for duplicates, and none of the
+/// other `Config` settings (tolerance, grid, max depth) are there.
+#[derive(Debug)]
+pub enum BumpQuadTree<'bump, T: PartialOrd + Copy + Midpoint> {
+    Leaf(usize, Boundary<T>, BumpVec<'bump, Point<T>>),
+    Node(usize, Boundary<T>, &'bump mut [BumpQuadTree<'bump, T>; 4]),
+}
+
+impl<'bump, T> BumpQuadTree<'bump, T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new_in(boundary: Boundary<T>, bump: &'bump Bump) -> Self {
+        Self::with_node_capacity_in(64, boundary, bump)
+    }
+
+    /// Panics if `capacity` is 0, as every insert would subdivide forever.
+    pub fn with_node_capacity_in(
+        capacity: usize,
+        boundary: Boundary<T>,
+        bump: &'bump Bump,
+    ) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        BumpQuadTree::Leaf(
+            capacity,
+            boundary,
+            BumpVec::with_capacity_in(capacity, bump),
+        )
+    }
+
+    /// Same as `with_node_capacity_in`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_node_capacity_in(
+        capacity: usize,
+        boundary: Boundary<T>,
+        bump: &'bump Bump,
+    ) -> Result<Self, BuildError> {
+        if capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        Ok(Self::with_node_capacity_in(capacity, boundary, bump))
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> bool {
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+
+        if let BumpQuadTree::Leaf(capacity, _, points) = self {
+            if points.contains(&point) {
+                return true;
+            }
+            if points.len() < *capacity {
+                points.push(point);
+                return true;
+            }
+        }
+
+        if let BumpQuadTree::Leaf(capacity, boundary, points) = self {
+            let bump = points.bump();
+            let leaf = |b| Self::with_node_capacity_in(*capacity, b, bump);
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let children = bump.alloc([leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)]);
+            for point in points.drain(..) {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(point);
+            }
+            *self = BumpQuadTree::Node(*capacity, *boundary, children);
+        }
+
+        match self {
+            BumpQuadTree::Leaf(_, _, _) => panic!("We should never be a leaf at this point"),
+            BumpQuadTree::Node(_, boundary, children) => {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(point)
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            BumpQuadTree::Leaf(_, _, points) => points.len(),
+            BumpQuadTree::Node(_, _, children) => children.iter().map(|c| c.size()).sum(),
+        }
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            BumpQuadTree::Leaf(_, _, points) => {
+                found.extend(points.iter().filter(|p| QuadTree::contains(boundary, p)))
+            }
+            BumpQuadTree::Node(_, _, children) => {
+                for child in children.iter() {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            BumpQuadTree::Leaf(_, boundary, _) => *boundary,
+            BumpQuadTree::Node(_, boundary, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BumpQuadTree;
+    use crate::{BuildError, QuadTree};
+    use bumpalo::Bump;
+
+    #[test]
+    fn rebuild_every_frame() {
+        let mut bump = Bump::new();
+        let mut reference = QuadTree::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            reference.insert((i, (i * 37) % 100));
+        }
+        let mut expected = reference.search(&(20, 70, 10, 50));
+        expected.sort();
+
+        for _frame in 0..3 {
+            {
+                let mut qt = BumpQuadTree::with_node_capacity_in(4, (0, 100, 0, 100), &bump);
+                for i in 0..100 {
+                    qt.insert((i, (i * 37) % 100));
+                    qt.insert((i, (i * 37) % 100));
+                }
+                assert_eq!(qt.size(), 100);
+                let mut found = qt.search(&(20, 70, 10, 50));
+                found.sort();
+                assert_eq!(found, expected);
+            }
+            assert!(bump.allocated_bytes() > 0);
+            bump.reset();
+        }
+
+        // A point that's already in a full leaf doesn't split it.
+        let mut full = BumpQuadTree::with_node_capacity_in(2, (0, 10, 0, 10), &bump);
+        full.insert((1, 1));
+        full.insert((2, 2));
+        assert!(full.insert((1, 1)));
+        assert!(matches!(full, BumpQuadTree::Leaf(_, _, _)));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn zero_capacity() {
+        let bump = Bump::new();
+        assert_eq!(
+            BumpQuadTree::<u32>::try_with_node_capacity_in(0, (0, 10, 0, 10), &bump).unwrap_err(),
+            BuildError::ZeroCapacity
+        );
+        assert!(BumpQuadTree::<u32>::try_with_node_capacity_in(1, (0, 10, 0, 10), &bump).is_ok());
+        BumpQuadTree::<u32>::with_node_capacity_in(0, (0, 10, 0, 10), &bump);
+    }
+}
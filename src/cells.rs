@@ -0,0 +1,559 @@
+use crate::nearest::distance_to_boundary_sq;
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+
+/// A leaf of the tree, described by where it is rather than by what's in it. `select_lod` hands
+/// out nodes this way too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CellInfo<T> {
+    pub boundary: Boundary<T>,
+    /// The root is at depth 0.
+    pub depth: usize,
+    /// Number of points stored in the leaf, or below the node.
+    pub size: usize,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Finds the leaf that `point` falls in. `None` if it's outside the tree.
+    pub fn locate(&self, point: &Point<T>) -> Option<CellInfo<T>> {
+        if !Self::contains(self.get_boundary(), point) {
+            return None;
+        }
+        let mut node = self;
+        let mut depth = 0;
+        loop {
+            match node {
+                QuadTree::Leaf(_, boundary, points) => {
+                    return Some(CellInfo {
+                        boundary: *boundary,
+                        depth,
+                        size: points.len(),
+                    })
+                }
+                QuadTree::Node(_, _, _, children) => {
+                    node = &children[Self::quadrant_of(node.get_boundary(), point)];
+                    depth += 1;
+                }
+            }
+        }
+    }
+
+    /// Every leaf with the points in it, top left first like the children of a node.
+    pub fn leaves(&self) -> impl Iterator<Item = (Boundary<T>, &[Point<T>])> + '_ {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || loop {
+            match stack.pop()? {
+                QuadTree::Leaf(_, boundary, points) => return Some((*boundary, &points[..])),
+                QuadTree::Node(_, _, _, children) => stack.extend(children.iter().rev()),
+            }
+        })
+    }
+
+    /// The leaves touching `cell` on each side, as `[top, bottom, left, right]` (top being the low
+    /// `y` side, like everywhere else in the tree). Neighbours can be bigger or smaller than the
+    /// cell, so a side can have any number of them. Corners don't count.
+    pub fn cell_neighbors(&self, cell: &CellInfo<T>) -> [Vec<CellInfo<T>>; 4] {
+        let (x1, x2, y1, y2) = cell.boundary;
+        let overlaps_x = |(bx1, bx2, _, _): &Boundary<T>| *bx1 < x2 && *bx2 > x1;
+        let overlaps_y = |(_, _, by1, by2): &Boundary<T>| *by1 < y2 && *by2 > y1;
+
+        let mut top = vec![];
+        self.visit_cells(&|b| overlaps_x(b) && b.2 < y1 && b.3 >= y1, &mut |c| {
+            if c.boundary.3 == y1 {
+                top.push(c)
+            }
+        });
+        let mut bottom = vec![];
+        self.visit_cells(&|b| overlaps_x(b) && b.2 <= y2 && b.3 > y2, &mut |c| {
+            if c.boundary.2 == y2 {
+                bottom.push(c)
+            }
+        });
+        let mut left = vec![];
+        self.visit_cells(&|b| overlaps_y(b) && b.0 < x1 && b.1 >= x1, &mut |c| {
+            if c.boundary.1 == x1 {
+                left.push(c)
+            }
+        });
+        let mut right = vec![];
+        self.visit_cells(&|b| overlaps_y(b) && b.0 <= x2 && b.1 > x2, &mut |c| {
+            if c.boundary.0 == x2 {
+                right.push(c)
+            }
+        });
+        [top, bottom, left, right]
+    }
+
+    /// Subdivides leaves until every pair of neighbouring leaves is at most one level apart in
+    /// depth. Meshing and numerical stencils need this. Points are kept, they're just moved down.
+    /// Leaves at the max depth aren't subdivided.
+    pub fn balance(&mut self) {
+        let max_depth = self.get_config().max_depth;
+        loop {
+            let mut leaves = vec![];
+            self.visit_cells(&|_| true, &mut |cell| leaves.push(cell));
+            let too_coarse: Vec<CellInfo<T>> = leaves
+                .into_iter()
+                .filter(|cell| {
+                    max_depth.is_none_or(|max| cell.depth < max)
+                        && self
+                            .cell_neighbors(cell)
+                            .iter()
+                            .flatten()
+                            .any(|neighbor| neighbor.depth > cell.depth + 1)
+                })
+                .collect();
+            if too_coarse.is_empty() {
+                return;
+            }
+            for cell in too_coarse {
+                let (x1, _, y1, _) = cell.boundary;
+                self.leaf_at_mut(&(x1, y1)).subdivide(cell.depth);
+            }
+        }
+    }
+
+    /// Groups the occupied leaves (the ones holding at least one point) into connected regions,
+    /// where two leaves are connected if they share an edge. Think islands on a map, or rooms.
+    pub fn connected_components(&self) -> Vec<Vec<CellInfo<T>>> {
+        let mut occupied = vec![];
+        self.visit_cells(&|_| true, &mut |cell| {
+            if cell.size > 0 {
+                occupied.push(cell)
+            }
+        });
+        self.components_of(occupied)
+    }
+
+    /// The empty leaves that can be reached from `start` going from leaf to leaf across shared
+    /// edges, never through a leaf holding points. Treating points as walls, that's the room
+    /// `start` is in. Empty if `start` is outside the tree or in a leaf with points.
+    pub fn flood_fill(&self, start: &Point<T>) -> Vec<CellInfo<T>> {
+        let first = match self.locate(start) {
+            Some(cell) if cell.size == 0 => cell,
+            _ => return vec![],
+        };
+        let compare = |a: &Boundary<T>, b: &Boundary<T>| {
+            a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
+        };
+        let mut free = vec![];
+        self.visit_cells(&|_| true, &mut |cell| {
+            if cell.size == 0 {
+                free.push(cell)
+            }
+        });
+        free.sort_by(|a, b| compare(&a.boundary, &b.boundary));
+        let index_of = |cell: &CellInfo<T>| {
+            free.binary_search_by(|c| compare(&c.boundary, &cell.boundary))
+                .ok()
+        };
+
+        let mut seen = vec![false; free.len()];
+        let mut stack = vec![index_of(&first).expect("an empty leaf")];
+        seen[stack[0]] = true;
+        let mut room = vec![];
+        while let Some(i) = stack.pop() {
+            room.push(free[i]);
+            for neighbor in self.cell_neighbors(&free[i]).iter().flatten() {
+                if let Some(j) = index_of(neighbor) {
+                    if !seen[j] {
+                        seen[j] = true;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+        room
+    }
+
+    /// Whether there's a way from `a` to `b` through empty leaves, as for `flood_fill`.
+    pub fn is_reachable(&self, a: &Point<T>, b: &Point<T>) -> bool {
+        match self.locate(b) {
+            Some(goal) => self
+                .flood_fill(a)
+                .iter()
+                .any(|cell| cell.boundary == goal.boundary),
+            None => false,
+        }
+    }
+
+    /// Labels connected groups among `cells`, which have to be leaves of this tree.
+    pub(crate) fn components_of(&self, mut cells: Vec<CellInfo<T>>) -> Vec<Vec<CellInfo<T>>> {
+        let compare = |a: &Boundary<T>, b: &Boundary<T>| {
+            a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
+        };
+        cells.sort_by(|a, b| compare(&a.boundary, &b.boundary));
+        let mut seen = vec![false; cells.len()];
+        let mut components = vec![];
+
+        for start in 0..cells.len() {
+            if seen[start] {
+                continue;
+            }
+            seen[start] = true;
+            let mut component = vec![];
+            let mut stack = vec![start];
+            while let Some(i) = stack.pop() {
+                component.push(cells[i]);
+                for neighbor in self.cell_neighbors(&cells[i]).iter().flatten() {
+                    let found =
+                        cells.binary_search_by(|c| compare(&c.boundary, &neighbor.boundary));
+                    if let Ok(j) = found {
+                        if !seen[j] {
+                            seen[j] = true;
+                            stack.push(j);
+                        }
+                    }
+                }
+            }
+            components.push(component);
+        }
+        components
+    }
+
+    fn leaf_at_mut(&mut self, point: &Point<T>) -> &mut Self {
+        match self {
+            QuadTree::Leaf(_, _, _) => self,
+            QuadTree::Node(_, _, _, children) => {
+                Self::child_for(children, point).leaf_at_mut(point)
+            }
+        }
+    }
+
+    /// Calls `f` for every leaf whose boundary passes `filter`. Nodes that don't pass aren't
+    /// descended into.
+    pub(crate) fn visit_cells<F, V>(&self, filter: &F, f: &mut V)
+    where
+        F: Fn(&Boundary<T>) -> bool,
+        V: FnMut(CellInfo<T>),
+    {
+        self.visit_cells_at(0, filter, f);
+    }
+
+    fn visit_cells_at<F, V>(&self, depth: usize, filter: &F, f: &mut V)
+    where
+        F: Fn(&Boundary<T>) -> bool,
+        V: FnMut(CellInfo<T>),
+    {
+        match self {
+            QuadTree::Leaf(_, boundary, points) => {
+                if filter(boundary) {
+                    f(CellInfo {
+                        boundary: *boundary,
+                        depth,
+                        size: points.len(),
+                    });
+                }
+            }
+            QuadTree::Node(_, boundary, _, children) => {
+                if filter(boundary) {
+                    for child in children.iter() {
+                        child.visit_cells_at(depth + 1, filter, f);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The leaves the segment from `a` to `b` passes through, in the order it passes through them.
+    /// Only cells the segment actually crosses count, just touching a corner isn't enough. A
+    /// segment running along a seam belongs to the cells on the high side, like points do.
+    pub fn cells_along_segment(&self, a: &Point<T>, b: &Point<T>) -> Vec<CellInfo<T>> {
+        let mut hits: Vec<(f64, CellInfo<T>)> = vec![];
+        self.visit_cells(
+            &|boundary| segment_enters(boundary, a, b).is_some(),
+            &mut |cell| {
+                if let Some(t) = segment_enters(&cell.boundary, a, b) {
+                    hits.push((t, cell));
+                }
+            },
+        );
+        hits.sort_by(|x, y| x.0.total_cmp(&y.0));
+        hits.into_iter().map(|(_, cell)| cell).collect()
+    }
+
+    /// The leaves overlapping the disc of `radius` around `center`, whether they hold points or
+    /// not. A leaf just touching the edge of the disc counts.
+    pub fn cells_within_radius(&self, center: &Point<T>, radius: f64) -> Vec<CellInfo<T>> {
+        let radius_sq = radius * radius;
+        let mut cells = vec![];
+        self.visit_cells(
+            &|boundary| distance_to_boundary_sq(boundary, center) <= radius_sq,
+            &mut |cell| cells.push(cell),
+        );
+        cells
+    }
+}
+
+/// `a - b` as a f64, using nothing but `Distance`.
+pub(crate) fn signed<T: PartialOrd + Copy + Distance>(a: &T, b: &T) -> f64 {
+    if a >= b {
+        a.distance(*b)
+    } else {
+        -b.distance(*a)
+    }
+}
+
+/// Where along the segment (0 at `a`, 1 at `b`) it enters `boundary`. `None` if it misses.
+pub(crate) fn segment_enters<T>(boundary: &Boundary<T>, a: &Point<T>, b: &Point<T>) -> Option<f64>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    let (x1, x2, y1, y2) = boundary;
+    if a == b {
+        return if QuadTree::contains(boundary, a) {
+            Some(0.0)
+        } else {
+            None
+        };
+    }
+    let mut enter: f64 = 0.0;
+    let mut exit: f64 = 1.0;
+    for (from, to, low, high) in [(&a.0, &b.0, x1, x2), (&a.1, &b.1, y1, y2)] {
+        if from == to {
+            if from < low || from >= high {
+                return None;
+            }
+            continue;
+        }
+        let d = signed(to, from);
+        let t_low = signed(low, from) / d;
+        let t_high = signed(high, from) / d;
+        enter = enter.max(t_low.min(t_high));
+        exit = exit.min(t_low.max(t_high));
+    }
+    if enter < exit {
+        Some(enter)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CellInfo;
+    use crate::Config;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn locate() {
+        let mut qt = Q::with_node_capacity(2, (0, 8, 0, 8));
+        assert_eq!(
+            qt.locate(&(3, 3)),
+            Some(CellInfo {
+                boundary: (0, 8, 0, 8),
+                depth: 0,
+                size: 0
+            })
+        );
+
+        qt.insert((1, 1));
+        qt.insert((1, 2));
+        qt.insert((3, 3));
+        assert_eq!(
+            qt.locate(&(3, 3)),
+            Some(CellInfo {
+                boundary: (2, 4, 2, 4),
+                depth: 2,
+                size: 1
+            })
+        );
+        assert_eq!(qt.locate(&(7, 7)).map(|c| (c.depth, c.size)), Some((1, 0)));
+        assert_eq!(qt.locate(&(8, 7)), None);
+    }
+
+    #[test]
+    fn leaves() {
+        let mut qt = Q::with_node_capacity(2, (0, 8, 0, 8));
+        assert_eq!(
+            qt.leaves().collect::<Vec<_>>(),
+            vec![((0, 8, 0, 8), &[][..])]
+        );
+        for p in [(1, 1), (1, 3), (6, 6)] {
+            qt.insert(p);
+        }
+        let leaves: Vec<_> = qt.leaves().collect();
+        assert_eq!(
+            leaves,
+            vec![
+                ((0, 4, 0, 4), &[(1, 1), (1, 3)][..]),
+                ((0, 4, 4, 8), &[][..]),
+                ((4, 8, 0, 4), &[][..]),
+                ((4, 8, 4, 8), &[(6, 6)][..]),
+            ]
+        );
+    }
+
+    #[test]
+    fn cell_neighbors() {
+        let mut qt = Q::with_node_capacity(2, (0, 8, 0, 8));
+        qt.insert((1, 1));
+        qt.insert((1, 2));
+        qt.insert((3, 3));
+        // Top left quadrant is split in four, the other three quadrants are leaves.
+        let boundaries = |cells: &Vec<CellInfo<i32>>| {
+            let mut b: Vec<_> = cells.iter().map(|c| c.boundary).collect();
+            b.sort();
+            b
+        };
+
+        let big = qt.locate(&(6, 1)).unwrap();
+        let [top, bottom, left, right] = qt.cell_neighbors(&big);
+        assert!(top.is_empty() && right.is_empty());
+        assert_eq!(boundaries(&bottom), vec![(4, 8, 4, 8)]);
+        assert_eq!(boundaries(&left), vec![(2, 4, 0, 2), (2, 4, 2, 4)]);
+
+        let small = qt.locate(&(3, 3)).unwrap();
+        let [top, bottom, left, right] = qt.cell_neighbors(&small);
+        assert_eq!(boundaries(&top), vec![(2, 4, 0, 2)]);
+        assert_eq!(boundaries(&bottom), vec![(0, 4, 4, 8)]);
+        assert_eq!(boundaries(&left), vec![(0, 2, 2, 4)]);
+        assert_eq!(boundaries(&right), vec![(4, 8, 0, 4)]);
+    }
+
+    #[test]
+    fn cells_along_segment() {
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        qt.insert((1, 1));
+        qt.insert((3, 3));
+        let top_left_corners = |cells: Vec<CellInfo<i32>>| {
+            cells
+                .iter()
+                .map(|c| (c.boundary.0, c.boundary.2))
+                .collect::<Vec<_>>()
+        };
+
+        // Diagonal through the middle only grazes corners between the cells it crosses.
+        let diagonal = qt.cells_along_segment(&(0, 0), &(8, 8));
+        assert_eq!(top_left_corners(diagonal), vec![(0, 0), (2, 2), (4, 4)]);
+
+        // Backwards along a seam, which belongs to the cells below it.
+        let seam = qt.cells_along_segment(&(7, 4), &(1, 4));
+        assert_eq!(top_left_corners(seam), vec![(4, 4), (0, 4)]);
+
+        let across = qt.cells_along_segment(&(1, 3), &(6, 3));
+        assert_eq!(top_left_corners(across), vec![(0, 2), (2, 2), (4, 0)]);
+
+        assert!(qt.cells_along_segment(&(9, 9), &(12, 12)).is_empty());
+        assert_eq!(qt.cells_along_segment(&(5, 5), &(5, 5)).len(), 1);
+    }
+
+    #[test]
+    fn cells_within_radius() {
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        qt.insert((1, 1));
+        qt.insert((3, 3));
+        let boundaries =
+            |cells: Vec<CellInfo<i32>>| cells.iter().map(|c| c.boundary).collect::<Vec<_>>();
+
+        assert_eq!(
+            boundaries(qt.cells_within_radius(&(1, 1), 0.5)),
+            vec![(0, 2, 0, 2)]
+        );
+        // Touching the two cells next to it, but not the one diagonally across.
+        assert_eq!(
+            boundaries(qt.cells_within_radius(&(1, 1), 1.0)),
+            vec![(0, 2, 0, 2), (0, 2, 2, 4), (2, 4, 0, 2)]
+        );
+        // Empty leaves count too.
+        assert_eq!(qt.cells_within_radius(&(1, 1), 100.0).len(), 7);
+        assert!(qt.cells_within_radius(&(20, 20), 5.0).is_empty());
+    }
+
+    #[test]
+    fn connected_components() {
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        assert!(qt.connected_components().is_empty());
+
+        // An island of two cells in the top left, and a lonely one in the bottom right.
+        qt.insert((1, 1));
+        qt.insert((1, 3));
+        qt.insert((7, 7));
+        let mut components: Vec<Vec<_>> = qt
+            .connected_components()
+            .iter()
+            .map(|c| {
+                let mut corners: Vec<_> = c.iter().map(|c| (c.boundary.0, c.boundary.2)).collect();
+                corners.sort();
+                corners
+            })
+            .collect();
+        components.sort();
+        assert_eq!(components, vec![vec![(0, 0), (0, 2)], vec![(4, 4)]]);
+    }
+
+    #[test]
+    fn flood_fill() {
+        // A wall of points down x = 5 splits the map in two rooms.
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        for y in [1, 3, 5, 7].iter() {
+            qt.insert((5, *y));
+        }
+        let left = qt.flood_fill(&(1, 1));
+        assert_eq!(left.len(), 2);
+        assert!(left.iter().all(|c| c.boundary.1 == 4));
+        let right = qt.flood_fill(&(7, 7));
+        assert_eq!(right.len(), 4);
+        assert!(right.iter().all(|c| c.boundary.0 == 6 && c.size == 0));
+        assert!(qt.flood_fill(&(5, 1)).is_empty());
+        assert!(qt.flood_fill(&(9, 9)).is_empty());
+
+        assert!(qt.is_reachable(&(1, 1), &(1, 7)));
+        assert!(qt.is_reachable(&(7, 0), &(6, 7)));
+        assert!(!qt.is_reachable(&(1, 1), &(7, 7)));
+        assert!(!qt.is_reachable(&(1, 1), &(5, 5)));
+    }
+
+    #[test]
+    fn balance() {
+        let mut qt = Q::with_node_capacity(1, (0, 64, 0, 64));
+        qt.insert((31, 31));
+        qt.insert((30, 30));
+        qt.insert((40, 40));
+        let mut before = qt.search(&(0, 64, 0, 64));
+        before.sort();
+
+        let is_balanced = |qt: &Q<i32>| {
+            let mut leaves = vec![];
+            qt.visit_cells(&|_| true, &mut |cell| leaves.push(cell));
+            leaves.iter().all(|cell| {
+                qt.cell_neighbors(cell)
+                    .iter()
+                    .flatten()
+                    .all(|n| n.depth <= cell.depth + 1)
+            })
+        };
+        assert!(!is_balanced(&qt));
+        qt.balance();
+        assert!(is_balanced(&qt));
+        let mut after = qt.search(&(0, 64, 0, 64));
+        after.sort();
+        assert_eq!(after, before);
+        assert_eq!(qt.locate(&(40, 40)).unwrap().size, 1);
+
+        // Points moved down by balancing stop at the max depth too.
+        let config = Config {
+            capacity: 1,
+            max_depth: Some(3),
+            ..Config::default()
+        };
+        let mut deep = Q::with_config(config, (0, 64, 0, 64));
+        for i in 0..4 {
+            deep.insert((i, i));
+            deep.insert((40 + i, 40));
+        }
+        deep.balance();
+        assert!(is_balanced(&deep));
+        assert_eq!(deep.size(), 8);
+        let mut deepest = 0;
+        deep.visit_cells(&|_| true, &mut |cell| deepest = deepest.max(cell.depth));
+        assert_eq!(deepest, 3);
+    }
+}
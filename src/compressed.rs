@@ -0,0 +1,324 @@
+use crate::geom::covers;
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// A quadtree that skips over cells that would only have one non-empty child. A child of a node
+/// can sit any number of levels below the quadrant it's in, with its own (smaller) boundary, so a
+/// tight cluster far from everything else is found in a couple of steps instead of one node per
+/// halving. How deep the tree gets depends on how the points are spread out, not on how close
+/// together they are.
+///
+/// The first field of each variant is the capacity. Like `InlineQuadTree` it only does the basics,
+/// plus `remove` and `depth`.
+#[derive(Debug, Clone)]
+pub enum CompressedQuadTree<T: PartialOrd + Copy + Midpoint> {
+    Leaf(usize, Boundary<T>, Vec<Point<T>>),
+    Node(
+        usize,
+        Boundary<T>,
+        usize,
+        [Option<Box<CompressedQuadTree<T>>>; 4],
+    ),
+}
+
+impl<T> CompressedQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Self::with_node_capacity(64, boundary)
+    }
+
+    pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
+        CompressedQuadTree::Leaf(capacity, boundary, vec![])
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> bool {
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+        // The root keeps its boundary, whatever the points look like.
+        self.insert_contained(point, false);
+        true
+    }
+
+    /// Returns true if the point wasn't there yet. A leaf that fills up shrinks to the smallest
+    /// cell around its points, unless `compress` is false.
+    fn insert_contained(&mut self, point: Point<T>, compress: bool) -> bool {
+        match self {
+            CompressedQuadTree::Leaf(capacity, boundary, points) => {
+                if points.contains(&point) {
+                    return false;
+                }
+                points.push(point);
+                if points.len() > *capacity {
+                    if let Some(node) = Self::split(*capacity, *boundary, points, compress) {
+                        *self = node;
+                    }
+                }
+                true
+            }
+            CompressedQuadTree::Node(capacity, boundary, size, children) => {
+                let quadrants = QuadTree::quadrants(boundary);
+                let i = QuadTree::quadrant_of(boundary, &point);
+                let pushed = match children[i].take() {
+                    None => {
+                        let leaf = CompressedQuadTree::Leaf(*capacity, quadrants[i], vec![point]);
+                        children[i] = Some(Box::new(leaf));
+                        true
+                    }
+                    Some(mut child) => {
+                        let pushed = if QuadTree::contains(&child.get_boundary(), &point) {
+                            child.insert_contained(point, true)
+                        } else {
+                            child = Box::new(Self::join(*capacity, quadrants[i], *child, point));
+                            true
+                        };
+                        children[i] = Some(child);
+                        pushed
+                    }
+                };
+                if pushed {
+                    *size += 1;
+                }
+                pushed
+            }
+        }
+    }
+
+    /// Turns an overfull leaf into a node. `None` if the points can't be told apart by splitting,
+    /// which only happens when the cell can't be split any further.
+    fn split(
+        capacity: usize,
+        boundary: Boundary<T>,
+        points: &mut Vec<Point<T>>,
+        compress: bool,
+    ) -> Option<Self> {
+        let mut cell = boundary;
+        loop {
+            let all_in = QuadTree::quadrants(&cell)
+                .iter()
+                .copied()
+                .find(|q| points.iter().all(|p| QuadTree::contains(q, p)));
+            match all_in {
+                Some(q) if q == cell => return None,
+                Some(q) if compress => cell = q,
+                _ => break,
+            }
+        }
+
+        let mut node = CompressedQuadTree::Node(capacity, cell, 0, [None, None, None, None]);
+        for point in points.drain(..) {
+            node.insert_contained(point, true);
+        }
+        Some(node)
+    }
+
+    /// Puts `child` and `point`, which are both in `quadrant` but not in each other, under a new
+    /// node at the smallest cell that holds them both.
+    fn join(capacity: usize, quadrant: Boundary<T>, child: Self, point: Point<T>) -> Self {
+        let inner = child.get_boundary();
+        let mut cell = quadrant;
+        while let Some(q) = QuadTree::quadrants(&cell)
+            .iter()
+            .copied()
+            .find(|q| QuadTree::contains(q, &point) && covers(q, &inner))
+        {
+            if q == cell {
+                break;
+            }
+            cell = q;
+        }
+
+        let size = child.size() + 1;
+        let mut children = [None, None, None, None];
+        let quadrants = QuadTree::quadrants(&cell);
+        let mut child = Some(Box::new(child));
+        for (slot, q) in children.iter_mut().zip(quadrants.iter()) {
+            if QuadTree::contains(q, &point) {
+                *slot = Some(Box::new(CompressedQuadTree::Leaf(
+                    capacity,
+                    *q,
+                    vec![point],
+                )));
+            } else if covers(q, &inner) {
+                *slot = child.take();
+            }
+        }
+        CompressedQuadTree::Node(capacity, cell, size, children)
+    }
+
+    /// Removes a point. Returns whether it was there. Nodes left with no more than `capacity`
+    /// points become leaves again, and a node left with a single child is replaced by it.
+    pub fn remove(&mut self, point: &Point<T>) -> bool {
+        QuadTree::contains(&self.get_boundary(), point) && self.remove_contained(point)
+    }
+
+    fn remove_contained(&mut self, point: &Point<T>) -> bool {
+        let removed = match self {
+            CompressedQuadTree::Leaf(_, _, points) => {
+                match points.iter().position(|p| p == point) {
+                    Some(at) => {
+                        points.swap_remove(at);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            CompressedQuadTree::Node(_, boundary, size, children) => {
+                let i = QuadTree::quadrant_of(boundary, point);
+                let removed = match &mut children[i] {
+                    Some(child) if QuadTree::contains(&child.get_boundary(), point) => {
+                        child.remove_contained(point)
+                    }
+                    _ => false,
+                };
+                if removed {
+                    *size -= 1;
+                    Self::tidy(&mut children[i]);
+                }
+                removed
+            }
+        };
+
+        let mut merge = None;
+        if let CompressedQuadTree::Node(capacity, boundary, size, _) = self {
+            if removed && *size <= *capacity {
+                merge = Some((*capacity, *boundary));
+            }
+        }
+        if let Some((capacity, boundary)) = merge {
+            let points = self.search(&boundary);
+            *self = CompressedQuadTree::Leaf(capacity, boundary, points);
+        }
+        removed
+    }
+
+    /// Drops an empty child, and skips over a child node that's down to one child of its own.
+    fn tidy(slot: &mut Option<Box<Self>>) {
+        if slot.as_ref().is_some_and(|child| child.size() == 0) {
+            *slot = None;
+        } else if let Some(CompressedQuadTree::Node(_, _, _, children)) = slot.as_deref_mut() {
+            if children.iter().filter(|c| c.is_some()).count() == 1 {
+                let only = children.iter_mut().find_map(Option::take);
+                *slot = only;
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            CompressedQuadTree::Leaf(_, _, points) => points.len(),
+            CompressedQuadTree::Node(_, _, size, _) => *size,
+        }
+    }
+
+    /// The number of nodes on the longest path from the root to a leaf, so 0 for a lone leaf.
+    pub fn depth(&self) -> usize {
+        match self {
+            CompressedQuadTree::Leaf(_, _, _) => 0,
+            CompressedQuadTree::Node(_, _, _, children) => {
+                1 + children
+                    .iter()
+                    .flatten()
+                    .map(|c| c.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            CompressedQuadTree::Leaf(_, _, points) => {
+                found.extend(points.iter().filter(|p| QuadTree::contains(boundary, p)))
+            }
+            CompressedQuadTree::Node(_, _, _, children) => {
+                for child in children.iter().flatten() {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            CompressedQuadTree::Leaf(_, boundary, _) => *boundary,
+            CompressedQuadTree::Node(_, boundary, _, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompressedQuadTree;
+    use crate::QuadTree;
+
+    #[test]
+    fn same_as_quadtree() {
+        let mut qt = CompressedQuadTree::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut reference = QuadTree::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut rng = crate::tests::get_rng();
+        for _ in 0..3000 {
+            let p = (rng.next() as i32, rng.next() as i32);
+            assert_eq!(qt.insert(p), reference.insert(p));
+        }
+        assert!(!qt.insert((1000, 0)));
+        assert_eq!(qt.size(), reference.size());
+        for query in [
+            (0, 1000, 0, 1000),
+            (13, 517, 100, 101),
+            (999, 1000, 0, 1000),
+        ] {
+            let mut a = qt.search(&query);
+            let mut b = reference.search(&query);
+            a.sort();
+            b.sort();
+            assert_eq!(a, b);
+        }
+
+        for p in reference.search(&(0, 700, 0, 1000)) {
+            assert!(qt.remove(&p));
+        }
+        assert!(!qt.remove(&(1, 1)));
+        assert_eq!(qt.size(), reference.search(&(700, 1000, 0, 1000)).len());
+        assert_eq!(qt.search(&(0, 700, 0, 1000)), vec![]);
+    }
+
+    #[test]
+    fn clusters_stay_shallow() {
+        // Two tight clusters far apart. Without compression it takes about 20 halvings to get
+        // down to either of them.
+        let world = (0, 1 << 24, 0, 1 << 24);
+        let mut qt = CompressedQuadTree::with_node_capacity(4, world);
+        for i in 0..8 {
+            for j in 0..8 {
+                qt.insert((1_000_000 + i, 3_000_000 + j));
+                qt.insert((9_000_000 + i, 12_000_000 + j));
+            }
+        }
+        assert_eq!(qt.size(), 128);
+        assert!(qt.depth() <= 6, "depth {}", qt.depth());
+        assert_eq!(
+            qt.search(&(1_000_006, 1_000_100, 3_000_007, 3_000_008)),
+            vec![(1_000_006, 3_000_007), (1_000_007, 3_000_007)]
+        );
+
+        // Emptying one cluster leaves the other right under the root.
+        for i in 0..8 {
+            for j in 0..8 {
+                assert!(qt.remove(&(1_000_000 + i, 3_000_000 + j)));
+            }
+        }
+        assert_eq!(qt.size(), 64);
+        assert!(qt.depth() <= 5, "depth {}", qt.depth());
+    }
+}
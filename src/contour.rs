@@ -0,0 +1,206 @@
+use crate::density::Kernel;
+use crate::{Boundary, Distance, Midpoint, QuadTree};
+use std::collections::HashMap;
+
+/// A side of a square of the grid, between two neighbouring values. `Across` goes from value
+/// `(i, j)` to `(i + 1, j)`, `Down` from `(i, j)` to `(i, j + 1)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    Across(usize, usize),
+    Down(usize, usize),
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The outlines of where the density is at least each of `thresholds`, worked out from
+    /// `density_grid` by marching squares. There's a list of lines per threshold. Points are
+    /// offsets from the corner `(x1, y1)` of `boundary`, the same way the grid is, so add that
+    /// corner back to get coordinates. Lines that go all the way round end where they start, the
+    /// others run into the edge of the grid.
+    #[allow(clippy::too_many_arguments)]
+    pub fn density_contours<K: Kernel + ?Sized>(
+        &self,
+        boundary: &Boundary<T>,
+        columns: usize,
+        rows: usize,
+        bandwidth: f64,
+        kernel: &K,
+        thresholds: &[f64],
+    ) -> Vec<Vec<Vec<(f64, f64)>>> {
+        let grid = self.density_grid(boundary, columns, rows, bandwidth, kernel);
+        let (x1, x2, y1, y2) = *boundary;
+        let width = crate::cells::signed(&x2, &x1) / columns as f64;
+        let height = crate::cells::signed(&y2, &y1) / rows as f64;
+        thresholds
+            .iter()
+            .map(|threshold| {
+                let mut lines = contours(&grid, columns, rows, *threshold);
+                for point in lines.iter_mut().flatten() {
+                    *point = ((point.0 + 0.5) * width, (point.1 + 0.5) * height);
+                }
+                lines
+            })
+            .collect()
+    }
+}
+
+/// Marching squares over `columns` by `rows` values, a row at a time. Points are in steps of the
+/// grid, with the first value at (0, 0).
+pub(crate) fn contours(
+    grid: &[f64],
+    columns: usize,
+    rows: usize,
+    threshold: f64,
+) -> Vec<Vec<(f64, f64)>> {
+    let value = |i: usize, j: usize| grid[j * columns + i];
+    let inside = |i: usize, j: usize| value(i, j) >= threshold;
+    let crossing = |edge: Edge| {
+        let (i, j, (di, dj)) = match edge {
+            Edge::Across(i, j) => (i, j, (1, 0)),
+            Edge::Down(i, j) => (i, j, (0, 1)),
+        };
+        let (a, b) = (value(i, j), value(i + di, j + dj));
+        let t = (threshold - a) / (b - a);
+        (i as f64 + t * di as f64, j as f64 + t * dj as f64)
+    };
+
+    let mut segments = vec![];
+    for j in 0..rows.saturating_sub(1) {
+        for i in 0..columns.saturating_sub(1) {
+            let top = Edge::Across(i, j);
+            let bottom = Edge::Across(i, j + 1);
+            let left = Edge::Down(i, j);
+            let right = Edge::Down(i + 1, j);
+            let (a, b, c, d) = (
+                inside(i, j),
+                inside(i + 1, j),
+                inside(i + 1, j + 1),
+                inside(i, j + 1),
+            );
+            let crossed: Vec<Edge> = [(top, a, b), (right, b, c), (bottom, d, c), (left, a, d)]
+                .iter()
+                .filter(|(_, p, q)| p != q)
+                .map(|(edge, _, _)| *edge)
+                .collect();
+            match crossed[..] {
+                [e, f] => segments.push((e, f)),
+                [_, _, _, _] => {
+                    // A saddle: the middle decides which opposite corners are joined up.
+                    let middle =
+                        (value(i, j) + value(i + 1, j) + value(i + 1, j + 1) + value(i, j + 1))
+                            / 4.0;
+                    if (middle >= threshold) == a {
+                        segments.push((top, right));
+                        segments.push((bottom, left));
+                    } else {
+                        segments.push((left, top));
+                        segments.push((right, bottom));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Each crossing is shared by the squares on either side of it, so follow them from one
+    // segment to the next. Lines with an end start from it, what's left goes round.
+    let mut at: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (s, (e, f)) in segments.iter().enumerate() {
+        at.entry(*e).or_default().push(s);
+        at.entry(*f).or_default().push(s);
+    }
+    let mut used = vec![false; segments.len()];
+    let mut ends: Vec<Edge> = at
+        .iter()
+        .filter(|(_, s)| s.len() == 1)
+        .map(|(edge, _)| *edge)
+        .collect();
+    ends.sort_by_key(|edge| match edge {
+        Edge::Across(i, j) => (0, *j, *i),
+        Edge::Down(i, j) => (1, *j, *i),
+    });
+    let starts = ends
+        .into_iter()
+        .chain(segments.iter().map(|(e, _)| *e))
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![];
+    for start in starts {
+        let mut edge = start;
+        let mut line = vec![crossing(edge)];
+        while let Some(&s) = at[&edge].iter().find(|s| !used[**s]) {
+            used[s] = true;
+            let (e, f) = segments[s];
+            edge = if e == edge { f } else { e };
+            line.push(crossing(edge));
+        }
+        if line.len() > 1 {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::contours;
+    use crate::{Epanechnikov, QuadTree as Q};
+
+    #[test]
+    fn marching_squares() {
+        // A bump in the middle of a 5 by 5 grid makes one closed line.
+        #[rustfmt::skip]
+        let grid = [
+            0.0, 0.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 1.0, 1.0, 0.0,
+            0.0, 1.0, 2.0, 1.0, 0.0,
+            0.0, 1.0, 1.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 0.0, 0.0,
+        ];
+        let lines = contours(&grid, 5, 5, 1.5);
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert_eq!(line.first(), line.last());
+        assert_eq!(line.len(), 5);
+        assert!(line.contains(&(2.0, 1.5)) && line.contains(&(2.5, 2.0)));
+
+        // A ramp that's cut through from top to bottom is a line with two ends.
+        let ramp: Vec<f64> = (0..12).map(|k| (k % 4) as f64).collect();
+        let lines = contours(&ramp, 4, 3, 1.5);
+        assert_eq!(lines.len(), 1);
+        let mut xs: Vec<_> = lines[0].iter().map(|p| p.0).collect();
+        xs.dedup();
+        assert_eq!(xs, [1.5]);
+        assert_eq!(lines[0].len(), 3);
+        assert!(contours(&ramp, 4, 3, 10.0).is_empty());
+    }
+
+    #[test]
+    fn density_contours() {
+        let mut qt = Q::new((0.0, 100.0, 0.0, 100.0));
+        for i in 0..10 {
+            qt.insert((50.0 + i as f64 / 10.0, 50.0));
+        }
+        let levels = qt.density_contours(
+            &(0.0, 100.0, 0.0, 100.0),
+            20,
+            20,
+            20.0,
+            &Epanechnikov,
+            &[1e-4, 1.0],
+        );
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0].len(), 1);
+        // A ring around the cluster.
+        let ring = &levels[0][0];
+        assert_eq!(ring.first(), ring.last());
+        // The kernel reaches 20 out, and the density is down to the threshold a little before.
+        for (x, y) in ring {
+            let distance = (x - 50.0).hypot(y - 50.0);
+            assert!(distance > 15.0 && distance < 22.0);
+        }
+        assert!(levels[1].is_empty());
+    }
+}
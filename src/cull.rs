@@ -0,0 +1,180 @@
+use crate::cells::{signed, CellInfo};
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// Returns all points inside the convex quadrilateral with the given corners, edges included.
+    /// The corners go round it in order, either way round. Made for culling what a camera can't
+    /// see, where the view on the ground is a trapezoid. Subtrees that are all inside are taken
+    /// whole without looking at their points one by one.
+    pub fn search_quad(&self, corners: &[Point<T>; 4]) -> Vec<Point<T>> {
+        let quad = Quad::new(corners);
+        let mut found = vec![];
+        self.search_quad_into(&quad, &mut found);
+        found
+    }
+
+    fn search_quad_into(&self, quad: &Quad<T>, found: &mut Vec<Point<T>>) {
+        match quad.overlap(self.get_boundary()) {
+            Overlap::Outside => {}
+            Overlap::Inside => {
+                for (_, points) in self.leaves() {
+                    found.extend_from_slice(points);
+                }
+            }
+            Overlap::Partly => match self {
+                QuadTree::Leaf(_, _, points) => {
+                    found.extend(points.iter().filter(|p| quad.holds(p)));
+                }
+                QuadTree::Node(_, _, _, children) => {
+                    for child in children.iter() {
+                        child.search_quad_into(quad, found);
+                    }
+                }
+            },
+        }
+    }
+
+    /// The leaves overlapping the convex quadrilateral, whether they hold points or not, e.g. to
+    /// pick the tiles or draw calls that are in view. Corners go as for `search_quad`.
+    pub fn cells_in_quad(&self, corners: &[Point<T>; 4]) -> Vec<CellInfo<T>> {
+        let quad = Quad::new(corners);
+        let mut cells = vec![];
+        self.visit_cells(
+            &|boundary| quad.overlap(boundary) != Overlap::Outside,
+            &mut |cell| cells.push(cell),
+        );
+        cells
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overlap {
+    Outside,
+    Partly,
+    Inside,
+}
+
+/// The corners worked out relative to the first one, and turned to go counter clockwise (with y
+/// going up).
+struct Quad<T> {
+    origin: Point<T>,
+    corners: [(f64, f64); 4],
+}
+
+impl<T> Quad<T>
+where
+    T: PartialOrd + Copy + Distance,
+{
+    fn new(corners: &[Point<T>; 4]) -> Self {
+        let origin = corners[0];
+        let mut local = [(0.0, 0.0); 4];
+        for (l, corner) in local.iter_mut().zip(corners.iter()) {
+            *l = (signed(&corner.0, &origin.0), signed(&corner.1, &origin.1));
+        }
+        let area: f64 = (0..4)
+            .map(|i| {
+                let (a, b) = (local[i], local[(i + 1) % 4]);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum();
+        if area < 0.0 {
+            local.reverse();
+        }
+        Quad {
+            origin,
+            corners: local,
+        }
+    }
+
+    fn local(&self, (x, y): &Point<T>) -> (f64, f64) {
+        (signed(x, &self.origin.0), signed(y, &self.origin.1))
+    }
+
+    /// How far `p` is to the left of edge `i`. Negative is outside.
+    fn side(&self, i: usize, (px, py): (f64, f64)) -> f64 {
+        let (ax, ay) = self.corners[i];
+        let (bx, by) = self.corners[(i + 1) % 4];
+        (bx - ax) * (py - ay) - (by - ay) * (px - ax)
+    }
+
+    fn holds(&self, point: &Point<T>) -> bool {
+        let p = self.local(point);
+        (0..4).all(|i| self.side(i, p) >= 0.0)
+    }
+
+    fn overlap(&self, (x1, x2, y1, y2): &Boundary<T>) -> Overlap {
+        let box_corners = [(*x1, *y1), (*x1, *y2), (*x2, *y1), (*x2, *y2)];
+        let local = box_corners.map(|corner| self.local(&corner));
+
+        // Apart along x or y.
+        let (min_x, max_x) = (local[0].0, local[3].0);
+        let (min_y, max_y) = (local[0].1, local[3].1);
+        let quad_x = self.corners.iter().map(|c| c.0);
+        let quad_y = self.corners.iter().map(|c| c.1);
+        if quad_x.clone().all(|x| x < min_x)
+            || quad_x.clone().all(|x| x > max_x)
+            || quad_y.clone().all(|y| y < min_y)
+            || quad_y.clone().all(|y| y > max_y)
+        {
+            return Overlap::Outside;
+        }
+        // All of the box on the outside of one edge.
+        if (0..4).any(|i| local.iter().all(|c| self.side(i, *c) < 0.0)) {
+            return Overlap::Outside;
+        }
+        if local
+            .iter()
+            .all(|c| (0..4).all(|i| self.side(i, *c) >= 0.0))
+        {
+            Overlap::Inside
+        } else {
+            Overlap::Partly
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn search_quad() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..2000 {
+            qt.insert((rng.next() as i32, rng.next() as i32));
+        }
+        // Narrow near a camera at y = 900 and wide far from it.
+        let view = [(450, 900), (100, 100), (900, 100), (550, 900)];
+        let inside = |&(x, y): &(i32, i32)| {
+            // Between the two slanted sides, and 100 <= y <= 900.
+            let left = 450 - (900 - y) * 350 / 800;
+            let right = 550 + (900 - y) * 350 / 800;
+            (100..=900).contains(&y) && x >= left && x <= right
+        };
+        let mut found = qt.search_quad(&view);
+        found.sort();
+        let mut expected: Vec<_> = qt
+            .search(&(0, 1000, 0, 1000))
+            .into_iter()
+            .filter(inside)
+            .collect();
+        expected.sort();
+        assert_eq!(found, expected);
+        let mut reversed = view;
+        reversed.reverse();
+        assert_eq!(qt.search_quad(&reversed).len(), found.len());
+
+        let cells = qt.cells_in_quad(&view);
+        assert!(cells.iter().map(|c| c.size).sum::<usize>() >= found.len());
+        assert!(!cells
+            .iter()
+            .any(|c| c.boundary.2 >= 900 && c.boundary.1 <= 400));
+        assert!(qt
+            .search_quad(&[(-9, -9), (-1, -9), (-1, -1), (-9, -1)])
+            .is_empty());
+    }
+}
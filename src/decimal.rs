@@ -0,0 +1,68 @@
+//! `rust_decimal::Decimal` coordinates. It's `Copy`, so it works like any other coordinate, but
+//! at 16 bytes it doesn't fit the binary format. Needs the `rust_decimal` feature.
+use crate::{Distance, Midpoint, NextUp, Snap};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+impl Midpoint for Decimal {
+    fn midpoint(&self, a: Decimal) -> Decimal {
+        match self.checked_add(a) {
+            Some(sum) => sum / Decimal::TWO,
+            // Both large with the same sign, so the difference fits.
+            None => *self + (a - *self) / Decimal::TWO,
+        }
+    }
+}
+
+impl Distance for Decimal {
+    fn distance(&self, a: Decimal) -> f64 {
+        (*self - a).abs().to_f64().unwrap_or(f64::INFINITY)
+    }
+}
+
+impl Snap for Decimal {
+    fn snap(&self, resolution: Decimal) -> Decimal {
+        (*self / resolution).floor() * resolution
+    }
+}
+
+/// One unit in the last place, at the finest scale the number can take.
+impl NextUp for Decimal {
+    fn next_up(&self) -> Decimal {
+        let mut fine = *self;
+        fine.rescale(Decimal::MAX_SCALE);
+        fine.checked_add(Decimal::new(1, fine.scale()))
+            .unwrap_or(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Midpoint, NextUp, QuadTree as Q};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn decimal_coordinates() {
+        let d = |n: i64, scale: u32| Decimal::new(n, scale);
+        let mut qt = Q::with_node_capacity(4, (d(0, 0), d(1, 0), d(0, 0), d(1, 0)));
+        for i in 0..100 {
+            qt.insert((d(i, 2), d(99 - i, 2)));
+        }
+        assert_eq!(qt.size(), 100);
+        assert_eq!(qt.search(&(d(10, 2), d(20, 2), d(0, 0), d(1, 0))).len(), 10);
+        assert_eq!(
+            qt.nearest(&(d(501, 3), d(491, 3))),
+            Some((d(50, 2), d(49, 2)))
+        );
+
+        let fitted = Q::from_points_autofit(vec![(d(1, 1), d(2, 1)), (d(3, 1), d(4, 1))]);
+        assert_eq!(fitted.unwrap().size(), 2);
+
+        assert_eq!(
+            Midpoint::midpoint(&Decimal::MAX, Decimal::MAX),
+            Decimal::MAX
+        );
+        assert!(NextUp::next_up(&d(5, 0)) > d(5, 0));
+        assert_eq!(NextUp::next_up(&Decimal::MAX), Decimal::MAX);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::cells::signed;
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+use std::f64::consts::PI;
+
+/// The shape of the bump each point adds to a density estimate. `weight` gets the distance
+/// divided by the bandwidth, and has to integrate to 1 over the plane.
+pub trait Kernel {
+    fn weight(&self, u: f64) -> f64;
+    /// How many bandwidths out a point can still count. Points further away are never looked at.
+    fn reach(&self) -> f64;
+}
+
+/// The normal distribution. It never quite reaches 0, so it's cut off at 4 bandwidths, by which
+/// point it's down to a 3000th of its peak.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Gaussian;
+
+impl Kernel for Gaussian {
+    fn weight(&self, u: f64) -> f64 {
+        (-0.5 * u * u).exp() / (2.0 * PI)
+    }
+
+    fn reach(&self) -> f64 {
+        4.0
+    }
+}
+
+/// Falls off as 1 - u², to nothing at one bandwidth. Cheaper than `Gaussian` as it looks at
+/// fewer points.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Epanechnikov;
+
+impl Kernel for Epanechnikov {
+    fn weight(&self, u: f64) -> f64 {
+        if u < 1.0 {
+            2.0 / PI * (1.0 - u * u)
+        } else {
+            0.0
+        }
+    }
+
+    fn reach(&self) -> f64 {
+        1.0
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The kernel density estimate at `point`, with a `Gaussian` kernel: how many points there are
+    /// per unit of area around it, as a share of all the points. Integrates to 1 over the plane.
+    pub fn density_at(&self, point: &Point<T>, bandwidth: f64) -> f64 {
+        self.density_at_with(point, bandwidth, &Gaussian)
+    }
+
+    /// `density_at` with a kernel of your own. Only nodes within `kernel.reach()` bandwidths of
+    /// `point` are looked at.
+    pub fn density_at_with<K: Kernel + ?Sized>(
+        &self,
+        point: &Point<T>,
+        bandwidth: f64,
+        kernel: &K,
+    ) -> f64 {
+        self.density_local(point, (0.0, 0.0), bandwidth, kernel)
+    }
+
+    /// `density_at_with` at the middle of each cell of a `columns` by `rows` grid over
+    /// `boundary`, a row at a time from the top. Works for any coordinate type, as the middles of
+    /// the cells are only worked out as offsets from the corner of `boundary`.
+    pub fn density_grid<K: Kernel + ?Sized>(
+        &self,
+        boundary: &Boundary<T>,
+        columns: usize,
+        rows: usize,
+        bandwidth: f64,
+        kernel: &K,
+    ) -> Vec<f64> {
+        let (x1, x2, y1, y2) = *boundary;
+        let width = signed(&x2, &x1) / columns as f64;
+        let height = signed(&y2, &y1) / rows as f64;
+        let mut densities = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let middle = ((column as f64 + 0.5) * width, (row as f64 + 0.5) * height);
+                densities.push(self.density_local(&(x1, y1), middle, bandwidth, kernel));
+            }
+        }
+        densities
+    }
+
+    /// The density at `at`, given as an offset from `origin`.
+    fn density_local<K: Kernel + ?Sized>(
+        &self,
+        origin: &Point<T>,
+        at: (f64, f64),
+        bandwidth: f64,
+        kernel: &K,
+    ) -> f64 {
+        let size = self.size();
+        if size == 0 {
+            return 0.0;
+        }
+        let reach = kernel.reach() * bandwidth;
+        let sum = self.kernel_sum(origin, at, reach * reach, bandwidth, kernel);
+        sum / (size as f64 * bandwidth * bandwidth)
+    }
+
+    fn kernel_sum<K: Kernel + ?Sized>(
+        &self,
+        origin: &Point<T>,
+        (ax, ay): (f64, f64),
+        reach_sq: f64,
+        bandwidth: f64,
+        kernel: &K,
+    ) -> f64 {
+        let local = |(x, y): &Point<T>| (signed(x, &origin.0), signed(y, &origin.1));
+        let (x1, x2, y1, y2) = self.get_boundary();
+        let (bx1, by1) = local(&(*x1, *y1));
+        let (bx2, by2) = local(&(*x2, *y2));
+        let dx = (bx1 - ax).max(ax - bx2).max(0.0);
+        let dy = (by1 - ay).max(ay - by2).max(0.0);
+        if dx * dx + dy * dy > reach_sq {
+            return 0.0;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => points
+                .iter()
+                .map(|p| {
+                    let (px, py) = local(p);
+                    let d_sq = (px - ax) * (px - ax) + (py - ay) * (py - ay);
+                    if d_sq > reach_sq {
+                        0.0
+                    } else {
+                        kernel.weight(d_sq.sqrt() / bandwidth)
+                    }
+                })
+                .sum(),
+            QuadTree::Node(_, _, _, children) => children
+                .iter()
+                .map(|child| child.kernel_sum(origin, (ax, ay), reach_sq, bandwidth, kernel))
+                .sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Epanechnikov, Gaussian, Kernel};
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn density() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(8, (0.0, 1000.0, 0.0, 1000.0));
+        for _ in 0..3000 {
+            qt.insert((rng.next() as f64, rng.next() as f64 / 2.0));
+        }
+        let n = qt.size() as f64;
+        let at = (300.0, 200.0);
+        let h = 40.0;
+        for kernel in [&Gaussian as &dyn Kernel, &Epanechnikov].iter() {
+            let expected: f64 = qt
+                .search(&(0.0, 1000.0, 0.0, 1000.0))
+                .iter()
+                .map(|p| {
+                    let d = ((p.0 - at.0).powi(2) + (p.1 - at.1).powi(2)).sqrt();
+                    if d <= kernel.reach() * h {
+                        kernel.weight(d / h)
+                    } else {
+                        0.0
+                    }
+                })
+                .sum::<f64>()
+                / (n * h * h);
+            let found = qt.density_at_with(&at, h, *kernel);
+            assert!((found - expected).abs() < 1e-12);
+        }
+        // All the points are in the top half, where it's about 1 / 500_000 per unit of area.
+        let top = qt.density_at(&(500.0, 250.0), h);
+        assert!((top - 2e-6).abs() < 1e-6);
+        assert_eq!(qt.density_at(&(500.0, 900.0), h), 0.0);
+
+        let grid = qt.density_grid(&(0.0, 1000.0, 0.0, 1000.0), 4, 2, h, &Epanechnikov);
+        assert_eq!(grid.len(), 8);
+        let middle = qt.density_at_with(&(375.0, 250.0), h, &Epanechnikov);
+        assert!((grid[1] - middle).abs() < 1e-12);
+        assert!(grid[4..].iter().all(|d| *d == 0.0));
+
+        // Integer coordinates work for the grid too.
+        let mut ints = Q::new((0, 100, 0, 100));
+        ints.insert((10, 10));
+        let grid = ints.density_grid(&(0, 100, 0, 100), 10, 10, 5.0, &Gaussian);
+        assert!((grid[11] - Gaussian.weight(5f64.hypot(5.0) / 5.0) / 25.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{Midpoint, Point, QuadTree};
+use std::cmp::Ordering;
+
+/// What it takes to get from one tree to another. See `diff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeDiff<T> {
+    /// Points only in the other tree.
+    pub added: Vec<Point<T>>,
+    /// Points only in this tree.
+    pub removed: Vec<Point<T>>,
+}
+
+impl<T> TreeDiff<T> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// The points that would have to be inserted into and removed from this tree to make it hold
+    /// the same points as `other`.
+    ///
+    /// Both trees are walked together, and wherever they've been split the same way only leaves
+    /// covering the same area are compared to each other, so trees built the same way from
+    /// similar data are quick to diff. Where they differ in shape, all the points below are
+    /// compared.
+    pub fn diff(&self, other: &QuadTree<T>) -> TreeDiff<T> {
+        let mut diff = TreeDiff {
+            added: vec![],
+            removed: vec![],
+        };
+        self.diff_into(other, &mut diff);
+        diff
+    }
+
+    fn diff_into(&self, other: &QuadTree<T>, diff: &mut TreeDiff<T>) {
+        match (self, other) {
+            (QuadTree::Node(_, a, _, ours), QuadTree::Node(_, b, _, theirs)) if a == b => {
+                for (ours, theirs) in ours.iter().zip(theirs.iter()) {
+                    ours.diff_into(theirs, diff);
+                }
+            }
+            (QuadTree::Leaf(_, a, ours), QuadTree::Leaf(_, b, theirs))
+                if a == b && ours == theirs => {}
+            _ => {
+                let mut ours = self.search(self.get_boundary());
+                let mut theirs = other.search(other.get_boundary());
+                ours.sort_by(compare);
+                theirs.sort_by(compare);
+                let (mut i, mut j) = (0, 0);
+                while i < ours.len() && j < theirs.len() {
+                    match compare(&ours[i], &theirs[j]) {
+                        Ordering::Less => {
+                            diff.removed.push(ours[i]);
+                            i += 1;
+                        }
+                        Ordering::Greater => {
+                            diff.added.push(theirs[j]);
+                            j += 1;
+                        }
+                        Ordering::Equal => {
+                            i += 1;
+                            j += 1;
+                        }
+                    }
+                }
+                diff.removed.extend_from_slice(&ours[i..]);
+                diff.added.extend_from_slice(&theirs[j..]);
+            }
+        }
+    }
+}
+
+fn compare<T: PartialOrd>(a: &Point<T>, b: &Point<T>) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn diff() {
+        let mut a = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            a.insert((i, i));
+        }
+        let mut b = a.clone();
+        assert!(a.diff(&b).is_empty());
+
+        b.remove(&(10, 10));
+        b.remove(&(70, 70));
+        b.insert((10, 11));
+        let diff = a.diff(&b);
+        assert_eq!(diff.added, vec![(10, 11)]);
+        assert_eq!(diff.removed, vec![(10, 10), (70, 70)]);
+
+        // Different shapes, same points.
+        let mut c = Q::with_node_capacity(64, (0, 100, 0, 100));
+        for i in (0..100).rev() {
+            c.insert((i, i));
+        }
+        assert!(a.diff(&c).is_empty());
+        assert_eq!(c.diff(&b).added, vec![(10, 11)]);
+    }
+}
@@ -0,0 +1,202 @@
+//! Random sequences of inserts, removes and searches, run on a tree and on `Naive` side by side
+//! with proptest. Every step has to give the same answer on both, and the tree has to stay well
+//! formed. When a sequence fails proptest shrinks it before it's reported.
+use crate::naive::Naive;
+use crate::{Boundary, Config, Duplicates, Point, QuadTree};
+use proptest::prelude::*;
+use proptest::sample::select;
+use proptest::test_runner::{Config as ProptestConfig, TestCaseError, TestRunner};
+
+/// Sequences per scenario, and most steps per sequence.
+const CASES: u32 = 64;
+const STEPS: usize = 200;
+
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Insert(Point<i64>),
+    Remove(Point<i64>),
+    Search(Boundary<i64>),
+}
+
+struct Scenario {
+    name: &'static str,
+    config: Config<i64>,
+    boundary: Boundary<i64>,
+    /// Picks a coordinate between two values, for a point or a search.
+    coordinate: fn(i64, i64) -> BoxedStrategy<i64>,
+}
+
+/// Few distinct values, so the same points come up over and over.
+fn small_grid(low: i64, high: i64) -> BoxedStrategy<i64> {
+    (low..=high).boxed()
+}
+
+/// Mostly the edges of the boundary and the lines it splits along, and just either side of them.
+fn edges(low: i64, high: i64) -> BoxedStrategy<i64> {
+    let quarter = (high - low) / 4;
+    let lines = vec![
+        low,
+        low + quarter,
+        low + 2 * quarter,
+        low + 3 * quarter,
+        high,
+    ];
+    prop_oneof![
+        1 => low..high,
+        3 => (select(lines), -1i64..=1).prop_map(|(line, off)| line + off),
+    ]
+    .boxed()
+}
+
+/// A handful of neighbouring values deep inside a huge boundary, so the tree has to go very deep
+/// to pull them apart.
+fn clustered(low: i64, high: i64) -> BoxedStrategy<i64> {
+    let cluster = low + (high - low) / 3;
+    prop_oneof![
+        1 => low..high,
+        9 => cluster..cluster + 6,
+    ]
+    .boxed()
+}
+
+fn scenarios() -> Vec<Scenario> {
+    let config = |capacity, duplicates, max_depth| Config {
+        capacity,
+        duplicates,
+        max_depth,
+        ..Config::default()
+    };
+    vec![
+        Scenario {
+            name: "duplicates",
+            config: config(2, Duplicates::Linear, None),
+            boundary: (0, 8, 0, 8),
+            coordinate: small_grid,
+        },
+        Scenario {
+            name: "duplicates, sorted",
+            config: config(3, Duplicates::Sorted, None),
+            boundary: (0, 8, 0, 8),
+            coordinate: small_grid,
+        },
+        Scenario {
+            name: "capacity 1",
+            config: config(1, Duplicates::Linear, None),
+            boundary: (0, 8, 0, 8),
+            coordinate: small_grid,
+        },
+        Scenario {
+            name: "boundary edges",
+            config: config(2, Duplicates::Linear, None),
+            boundary: (-64, 64, 0, 128),
+            coordinate: edges,
+        },
+        Scenario {
+            name: "deep clustering",
+            config: config(2, Duplicates::Linear, None),
+            boundary: (0, 1 << 40, 0, 1 << 40),
+            coordinate: clustered,
+        },
+        Scenario {
+            name: "deep clustering, max depth",
+            config: config(2, Duplicates::Sorted, Some(6)),
+            boundary: (0, 1 << 40, 0, 1 << 40),
+            coordinate: clustered,
+        },
+    ]
+}
+
+fn steps(scenario: &Scenario) -> impl Strategy<Value = Vec<Step>> {
+    let (x1, x2, y1, y2) = scenario.boundary;
+    // Go a little past the boundary so points outside it come up too.
+    let x = (scenario.coordinate)(x1 - 1, x2 + 1);
+    let y = (scenario.coordinate)(y1 - 1, y2 + 1);
+    let point = (x.clone(), y.clone());
+    let search = (x.clone(), x, y.clone(), y)
+        .prop_map(|(a, b, c, d)| Step::Search((a.min(b), a.max(b), c.min(d), c.max(d))));
+    let step = prop_oneof![
+        6 => point.clone().prop_map(Step::Insert),
+        2 => point.prop_map(Step::Remove),
+        2 => search,
+    ];
+    proptest::collection::vec(step, 0..STEPS)
+}
+
+/// Runs `steps` and says what went wrong at the first step where tree and oracle disagree.
+fn run(scenario: &Scenario, steps: &[Step]) -> Result<(), String> {
+    let mut qt = QuadTree::with_config(scenario.config, scenario.boundary);
+    let mut naive = Naive::new(scenario.boundary);
+    for (i, step) in steps.iter().enumerate() {
+        let agree = match step {
+            Step::Insert(point) => qt.insert(*point) == naive.insert(*point),
+            Step::Remove(point) => qt.remove(point) == naive.remove(point),
+            Step::Search(boundary) => {
+                let mut a = qt.search(boundary);
+                let mut b = naive.search(boundary);
+                a.sort();
+                b.sort();
+                a == b
+            }
+        };
+        if !agree {
+            return Err(format!("step {} ({:?}) differs", i, step));
+        }
+        if qt.size() != naive.size() {
+            return Err(format!(
+                "after step {} the tree has {} points, not {}",
+                i,
+                qt.size(),
+                naive.size()
+            ));
+        }
+        well_formed(&qt).map_err(|e| format!("after step {}: {}", i, e))?;
+    }
+    Ok(())
+}
+
+/// Sizes add up, points are in their leaves, children are the quadrants of their node, and
+/// nodes hold more than fits in a leaf (or they'd have been merged).
+fn well_formed(tree: &QuadTree<i64>) -> Result<(), String> {
+    match tree {
+        QuadTree::Leaf(_, boundary, points) => {
+            match points.iter().find(|p| !QuadTree::contains(boundary, p)) {
+                Some(p) => Err(format!("{:?} is outside its leaf {:?}", p, boundary)),
+                None => Ok(()),
+            }
+        }
+        QuadTree::Node(config, boundary, size, children) => {
+            let sum: usize = children.iter().map(|c| c.size()).sum();
+            if sum != *size {
+                return Err(format!("node {:?} has size {} not {}", boundary, size, sum));
+            }
+            if *size <= config.capacity {
+                return Err(format!("node {:?} should have been merged", boundary));
+            }
+            let quadrants = QuadTree::quadrants(boundary);
+            for (child, quadrant) in children.iter().zip(quadrants.iter()) {
+                if child.get_boundary() != quadrant {
+                    return Err(format!("{:?} isn't a quadrant of {:?}", child, boundary));
+                }
+                well_formed(child)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[test]
+fn matches_naive() {
+    for scenario in scenarios() {
+        let mut runner = TestRunner::new(ProptestConfig {
+            cases: CASES,
+            failure_persistence: None,
+            ..ProptestConfig::default()
+        });
+        let result = runner.run(&steps(&scenario), |steps| {
+            run(&scenario, &steps).map_err(TestCaseError::fail)
+        });
+        if let Err(e) = result {
+            panic!("{}: {}", scenario.name, e);
+        }
+    }
+}
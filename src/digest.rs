@@ -0,0 +1,117 @@
+use crate::format::FixedBytes;
+use crate::{Midpoint, Point, QuadTree};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Trees are equal when they hold the same points, however many times each, wherever they are in
+/// the tree. The boundary and settings don't count.
+impl<T> PartialEq for QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn eq(&self, other: &Self) -> bool {
+        if self.size() != other.size() {
+            return false;
+        }
+        let sorted = |tree: &Self| {
+            let mut points: Vec<Point<T>> = tree.leaves().flat_map(|(_, p)| p.to_vec()).collect();
+            points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            points
+        };
+        sorted(self) == sorted(other)
+    }
+}
+
+impl<T> Eq for QuadTree<T> where T: Eq + PartialOrd + Copy + Midpoint {}
+
+/// Hashes the points without caring what order they're in, to go with `PartialEq`.
+impl<T> Hash for QuadTree<T>
+where
+    T: Hash + PartialOrd + Copy + Midpoint,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut sum = 0u64;
+        for (_, points) in self.leaves() {
+            for p in points {
+                let mut hasher = DefaultHasher::new();
+                p.hash(&mut hasher);
+                sum = sum.wrapping_add(hasher.finish());
+            }
+        }
+        state.write_usize(self.size());
+        state.write_u64(sum);
+    }
+}
+
+/// The splitmix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// A 128 bit digest of the points, for replicas to check they hold the same data or caches to
+    /// key on. Like `==`, it only depends on which points there are. It's worked out from the
+    /// bytes of the binary format, so it's the same on every platform and version of the crate,
+    /// and works for floats too. Either half makes a fine 64 bit digest.
+    pub fn content_digest(&self) -> u128 {
+        let word = |t: &T| u64::from_le_bytes(t.to_bytes());
+        let (mut low, mut high) = (0u64, 0u64);
+        for (_, points) in self.leaves() {
+            for (x, y) in points {
+                let (x, y) = (word(x), word(y));
+                low = low.wrapping_add(mix(mix(x ^ 0x9e3779b97f4a7c15).wrapping_add(y)));
+                high = high.wrapping_add(mix(mix(y ^ 0xd1b54a32d192ed03).wrapping_add(x)));
+            }
+        }
+        let low = mix(low ^ self.size() as u64);
+        let high = mix(high ^ T::TYPE_TAG);
+        (high as u128) << 64 | low as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+    use std::collections::HashSet;
+
+    #[test]
+    fn digest() {
+        let mut rng = crate::tests::get_rng();
+        let points: Vec<_> = (0..500).map(|_| (rng.next(), rng.next())).collect();
+        let mut a = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut b = Q::with_node_capacity(16, (0, 2000, 0, 2000));
+        for p in &points {
+            a.insert(*p);
+        }
+        for p in points.iter().rev() {
+            b.insert(*p);
+        }
+        assert!(a == b);
+        assert_eq!(a.content_digest(), b.content_digest());
+        let set: HashSet<_> = vec![a.clone(), b.clone()].into_iter().collect();
+        assert_eq!(set.len(), 1);
+
+        b.insert((1500, 1500));
+        assert!(a != b);
+        assert_ne!(a.content_digest(), b.content_digest());
+        b.remove(&(1500, 1500));
+        assert_eq!(a.content_digest(), b.content_digest());
+
+        // Swapping x and y is a different point.
+        let mut c = Q::new((0.0, 10.0, 0.0, 10.0));
+        let mut d = Q::new((0.0, 10.0, 0.0, 10.0));
+        c.insert((1.0, 2.0));
+        d.insert((2.0, 1.0));
+        assert_ne!(c.content_digest(), d.content_digest());
+
+        // Pinned, so a change to the digest shows up here.
+        let mut e = Q::new((0u32, 10, 0, 10));
+        e.insert((3, 4));
+        assert_eq!(e.content_digest(), 135642485022110145539377649434369883269);
+    }
+}
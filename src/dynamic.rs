@@ -0,0 +1,183 @@
+use crate::{Boundary, ItemId, Midpoint, Point, QuadMap};
+use std::collections::HashMap;
+
+/// Keeps track of objects that move around, e.g. the units in a game. Moves are collected with
+/// `move_to` and all made at once by `tick`, once per frame or so.
+///
+/// It remembers which leaf each object was last filed in. `tick` groups the moves by that leaf
+/// and goes down the tree once per leaf. Objects that are still inside their leaf are moved there
+/// and then. Only objects that left their leaf are taken out and filed again from the root.
+#[derive(Debug, Clone)]
+pub struct DynamicIndex<T: PartialOrd + Copy + Midpoint, D> {
+    map: QuadMap<T, D>,
+    /// The boundary of the leaf each object was last filed in. Leaves split and merge, so it's
+    /// only a hint.
+    cells: HashMap<ItemId, Boundary<T>>,
+    /// Where objects are to move to on the next tick.
+    pending: HashMap<ItemId, Point<T>>,
+}
+
+impl<T, D> DynamicIndex<T, D>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Self::from_map(QuadMap::new(boundary))
+    }
+
+    pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
+        Self::from_map(QuadMap::with_node_capacity(capacity, boundary))
+    }
+
+    fn from_map(map: QuadMap<T, D>) -> Self {
+        DynamicIndex {
+            map,
+            cells: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The objects where they were at the last tick, to search and so on.
+    pub fn map(&self) -> &QuadMap<T, D> {
+        &self.map
+    }
+
+    /// Adds an object straight away. None if `point` is outside the map.
+    pub fn insert(&mut self, point: Point<T>, value: D) -> Option<ItemId> {
+        let id = self.map.insert(point, value)?;
+        self.cells.insert(id, self.map.leaf_of(&point));
+        Some(id)
+    }
+
+    /// Takes an object out straight away, along with any move it had waiting.
+    pub fn remove(&mut self, id: ItemId) -> Option<(Point<T>, D)> {
+        self.pending.remove(&id);
+        self.cells.remove(&id);
+        self.map.remove_by_id(id)
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&D> {
+        self.map.get_by_id(id)
+    }
+
+    pub fn get_mut(&mut self, id: ItemId) -> Option<&mut D> {
+        self.map.get_by_id_mut(id)
+    }
+
+    /// Where the object was at the last tick.
+    pub fn position(&self, id: ItemId) -> Option<Point<T>> {
+        self.map.point_of(id)
+    }
+
+    /// Moves the object to `point` on the next tick. Moving it again before then replaces this
+    /// move. False if there's no such object.
+    pub fn move_to(&mut self, id: ItemId, point: Point<T>) -> bool {
+        if !self.cells.contains_key(&id) {
+            return false;
+        }
+        self.pending.insert(id, point);
+        true
+    }
+
+    /// Makes every move since the last tick. Objects moved off the map are taken out and given
+    /// back.
+    pub fn tick(&mut self) -> Vec<(ItemId, Point<T>, D)> {
+        let cells = &self.cells;
+        let mut moves: Vec<_> = self
+            .pending
+            .drain()
+            .map(|(id, to)| (cells[&id], id, to))
+            .collect();
+        moves.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        let mut refile = vec![];
+        let mut moves = moves.into_iter().peekable();
+        while let Some((cell, id, to)) = moves.next() {
+            let mut group = vec![(id, to)];
+            while let Some((_, id, to)) = moves.next_if(|(c, _, _)| *c == cell) {
+                group.push((id, to));
+            }
+            let at = self.map.point_of(id).expect("objects are in the map");
+            let ids: Vec<_> = group.iter().map(|(id, _)| *id).collect();
+            let (leaf, rest) = self.map.move_in_leaf(&at, group);
+            // The ones left over get their cell when they're filed again.
+            for id in ids {
+                self.cells.insert(id, leaf);
+            }
+            refile.extend(rest);
+        }
+
+        let mut gone = vec![];
+        for (id, to) in refile {
+            match self.map.relocate(id, to) {
+                Some(value) => {
+                    self.cells.remove(&id);
+                    gone.push((id, to, value));
+                }
+                None => {
+                    self.cells.insert(id, self.map.leaf_of(&to));
+                }
+            }
+        }
+        gone
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DynamicIndex;
+
+    #[test]
+    fn moves_on_tick() {
+        let mut rng = crate::tests::get_rng();
+        let mut index = DynamicIndex::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut objects: Vec<_> = (0..200)
+            .map(|i| {
+                let point = (rng.next(), rng.next());
+                (index.insert(point, i).unwrap(), point)
+            })
+            .collect();
+
+        for _ in 0..10 {
+            for (id, point) in objects.iter_mut() {
+                // Mostly small steps that stay in the same leaf, now and then a long way.
+                *point = if rng.next() < 100 {
+                    (rng.next(), rng.next())
+                } else {
+                    ((point.0 + rng.next() % 3) % 1000, point.1)
+                };
+                assert!(index.move_to(*id, *point));
+            }
+            assert!(index.tick().is_empty());
+            for (id, point) in &objects {
+                assert_eq!(index.position(*id), Some(*point));
+            }
+            let mut found: Vec<_> = index.map().search(&(0, 1000, 0, 1000));
+            found.sort();
+            let mut expected: Vec<_> = objects
+                .iter()
+                .map(|(id, p)| (*p, index.get(*id).unwrap()))
+                .collect();
+            expected.sort();
+            assert_eq!(found, expected);
+        }
+
+        let (id, point) = objects[0];
+        index.move_to(id, (2000, 0));
+        // Only the last move counts.
+        index.move_to(objects[1].0, (1, 1));
+        index.move_to(objects[1].0, (2, 2));
+        assert_eq!(index.position(objects[1].0), Some(objects[1].1));
+        assert_eq!(index.tick(), [(id, (2000, 0), 0)]);
+        assert_eq!(index.position(objects[1].0), Some((2, 2)));
+        assert!(!index.move_to(id, point));
+        assert_eq!(index.map().size(), 199);
+
+        index.move_to(objects[2].0, (3, 3));
+        assert_eq!(
+            index.remove(objects[2].0).map(|(p, _)| p),
+            Some(objects[2].1)
+        );
+        assert!(index.tick().is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// What a search had to do to find its points. See `search_explain`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Nodes and leaves whose boundary was checked against the search.
+    pub nodes_visited: usize,
+    /// Visited nodes and leaves that didn't intersect the search, so nothing below them was looked at.
+    pub nodes_pruned: usize,
+    /// Leaves whose points were looked at.
+    pub leaves_scanned: usize,
+    /// Points checked against the search boundary.
+    pub points_tested: usize,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Same as `search`, but also says how much work it was. Useful for figuring out why some
+    /// searches are so much slower than others.
+    pub fn search_explain(&self, boundary: &Boundary<T>) -> (Vec<Point<T>>, SearchStats) {
+        let mut found = vec![];
+        let mut stats = SearchStats::default();
+        self.search_explain_into(boundary, &mut found, &mut stats);
+        (found, stats)
+    }
+
+    fn search_explain_into(
+        &self,
+        boundary: &Boundary<T>,
+        found: &mut Vec<Point<T>>,
+        stats: &mut SearchStats,
+    ) {
+        stats.nodes_visited += 1;
+        if !Self::intersects(self.get_boundary(), boundary) {
+            stats.nodes_pruned += 1;
+            return;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                stats.leaves_scanned += 1;
+                stats.points_tested += points.len();
+                found.extend(points.iter().filter(|p| Self::contains(boundary, p)));
+            }
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.search_explain_into(boundary, found, stats);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchStats;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn search_explain() {
+        let mut qt = Q::with_node_capacity(2, (0, 8, 0, 8));
+        for p in [(1, 1), (1, 3), (3, 1), (6, 6)] {
+            qt.insert(p);
+        }
+        // The root, its four children and the four leaves of the top left quadrant.
+        let (found, stats) = qt.search_explain(&(0, 2, 0, 2));
+        assert_eq!(found, vec![(1, 1)]);
+        assert_eq!(
+            stats,
+            SearchStats {
+                nodes_visited: 9,
+                nodes_pruned: 6,
+                leaves_scanned: 1,
+                points_tested: 1,
+            }
+        );
+
+        let (found, stats) = qt.search_explain(&(20, 30, 20, 30));
+        assert!(found.is_empty());
+        assert_eq!(stats.nodes_visited, 1);
+        assert_eq!(stats.nodes_pruned, 1);
+    }
+}
@@ -0,0 +1,86 @@
+//! Coordinates as fixed point numbers from the `fixed` crate, for when results have to be the same
+//! on every machine. Everything is done on the underlying integers, so it's exact. Needs the
+//! `fixed` feature.
+use crate::format::FixedBytes;
+use crate::{Distance, Midpoint, NextUp, Snap};
+use fixed::types::extra::{LeEqU32, LeEqU64};
+use fixed::{FixedI32, FixedI64, FixedU32, FixedU64};
+
+macro_rules! fixed_coordinates {
+    ($fixed:ident, $frac:ident, $tag:expr, $size:expr) => {
+        impl<Frac: $frac> Midpoint for $fixed<Frac> {
+            fn midpoint(&self, a: Self) -> Self {
+                Self::from_bits(Midpoint::midpoint(&self.to_bits(), a.to_bits()))
+            }
+        }
+
+        impl<Frac: $frac> Distance for $fixed<Frac> {
+            fn distance(&self, a: Self) -> f64 {
+                (self.to_num::<f64>() - a.to_num::<f64>()).abs()
+            }
+        }
+
+        impl<Frac: $frac> Snap for $fixed<Frac> {
+            fn snap(&self, resolution: Self) -> Self {
+                Self::from_bits(self.to_bits().snap(resolution.to_bits()))
+            }
+        }
+
+        /// The smallest step up, i.e. `DELTA`.
+        impl<Frac: $frac> NextUp for $fixed<Frac> {
+            fn next_up(&self) -> Self {
+                Self::from_bits(NextUp::next_up(&self.to_bits()))
+            }
+        }
+
+        /// The number of fractional bits is part of the tag, so e.g. `I32F32` can't be read back
+        /// as `I48F16`.
+        impl<Frac: $frac> FixedBytes for $fixed<Frac> {
+            const TYPE_TAG: u64 = $tag << 8 | Self::FRAC_NBITS as u64;
+
+            fn to_bytes(&self) -> [u8; 8] {
+                let mut bytes = [0; 8];
+                bytes[..$size].copy_from_slice(&self.to_le_bytes());
+                bytes
+            }
+
+            fn from_bytes(bytes: [u8; 8]) -> Self {
+                let mut own = [0; $size];
+                own.copy_from_slice(&bytes[..$size]);
+                Self::from_le_bytes(own)
+            }
+        }
+    };
+}
+
+fixed_coordinates!(FixedI32, LeEqU32, 1, 4);
+fixed_coordinates!(FixedI64, LeEqU64, 2, 8);
+fixed_coordinates!(FixedU32, LeEqU32, 3, 4);
+fixed_coordinates!(FixedU64, LeEqU64, 4, 8);
+
+#[cfg(test)]
+mod tests {
+    use crate::format::FixedBytes;
+    use crate::{NextUp, QuadTree as Q, Snap};
+    use fixed::types::{I16F16, I32F32, I48F16};
+
+    #[test]
+    fn fixed_coordinates() {
+        let f = I32F32::from_num::<f64>;
+        let mut qt = Q::with_node_capacity(4, (f(-50.0), f(50.0), f(-50.0), f(50.0)));
+        for i in 0..40 {
+            let v = i as f64 * 2.5 - 49.0;
+            qt.insert((f(v), f(-v)));
+        }
+        assert_eq!(qt.size(), 40);
+        assert_eq!(qt.search(&(f(-50.0), f(-40.0), f(-50.0), f(50.0))).len(), 4);
+        assert_eq!(qt.nearest(&(f(1.2), f(-1.2))), Some((f(1.0), f(-1.0))));
+        assert_eq!(Q::<I32F32>::from_bytes(&qt.to_bytes()).unwrap().size(), 40);
+        assert_ne!(I32F32::TYPE_TAG, I48F16::TYPE_TAG);
+
+        let h = I16F16::from_num::<f64>;
+        assert_eq!(h(-1.25).snap(h(1.0)), h(-2.0));
+        assert_eq!(NextUp::next_up(&h(1.0)), h(1.0) + I16F16::DELTA);
+        assert_eq!(NextUp::next_up(&I16F16::MAX), I16F16::MAX);
+    }
+}
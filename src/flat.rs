@@ -0,0 +1,117 @@
+use crate::{Midpoint, QuadTree};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+/// A tree as a handful of plain arrays, ready to be copied into GPU storage buffers so a compute
+/// shader can walk the same structure. Made by `QuadTree::to_flat_buffers`.
+///
+/// Nodes are numbered breadth first, with the root at 0, and the four children of a node are
+/// next to each other in the usual order. Points are stored depth first, so the points below any
+/// node, leaf or not, are one range of `points`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlatBuffers<T> {
+    /// `[x1, x2, y1, y2]` of each node.
+    pub boundaries: Vec<[T; 4]>,
+    /// The index of the first of a node's four children, or `FlatBuffers::LEAF`.
+    pub first_child: Vec<u32>,
+    /// The points below each node, as `[start, end)` in `points`.
+    pub point_ranges: Vec<[u32; 2]>,
+    pub points: Vec<[T; 2]>,
+}
+
+impl<T> FlatBuffers<T> {
+    /// What `first_child` holds for a leaf.
+    pub const LEAF: u32 = u32::MAX;
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Copies the tree into a `FlatBuffers`. Indices are `u32`, as shaders like them, so this
+    /// panics for trees with more than about 4 billion nodes or points.
+    pub fn to_flat_buffers(&self) -> FlatBuffers<T> {
+        let mut nodes = vec![];
+        let mut first_child = vec![];
+        let mut queue = VecDeque::new();
+        queue.push_back(self);
+        while let Some(node) = queue.pop_front() {
+            nodes.push(node);
+            match node {
+                QuadTree::Leaf(_, _, _) => first_child.push(FlatBuffers::<T>::LEAF),
+                QuadTree::Node(_, _, _, children) => {
+                    let first = nodes.len() + queue.len();
+                    first_child.push(u32::try_from(first).expect("too many nodes"));
+                    queue.extend(children.iter());
+                }
+            }
+        }
+
+        let mut flat = FlatBuffers {
+            boundaries: nodes
+                .iter()
+                .map(|node| {
+                    let (x1, x2, y1, y2) = *node.get_boundary();
+                    [x1, x2, y1, y2]
+                })
+                .collect(),
+            first_child,
+            point_ranges: vec![[0, 0]; nodes.len()],
+            points: Vec::with_capacity(self.size()),
+        };
+        fill_points(&nodes, 0, &mut flat);
+        flat
+    }
+}
+
+/// Appends the points below node `i` depth first and records their range.
+fn fill_points<T>(nodes: &[&QuadTree<T>], i: usize, flat: &mut FlatBuffers<T>)
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    let index = |n: usize| u32::try_from(n).expect("too many points");
+    let start = index(flat.points.len());
+    match nodes[i] {
+        QuadTree::Leaf(_, _, points) => flat.points.extend(points.iter().map(|&(x, y)| [x, y])),
+        QuadTree::Node(_, _, _, _) => {
+            let first = flat.first_child[i] as usize;
+            for child in first..first + 4 {
+                fill_points(nodes, child, flat);
+            }
+        }
+    }
+    flat.point_ranges[i] = [start, index(flat.points.len())];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlatBuffers;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn to_flat_buffers() {
+        let mut qt = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        qt.insert((1.0, 1.0));
+        qt.insert((3.0, 3.0));
+        qt.insert((7.0, 7.0));
+        let flat = qt.to_flat_buffers();
+
+        // Root, its four children, then the four children of the top left one.
+        assert_eq!(flat.boundaries.len(), 9);
+        assert_eq!(flat.first_child[0], 1);
+        assert_eq!(flat.first_child[1], 5);
+        assert_eq!(
+            flat.first_child[2..].to_vec(),
+            vec![FlatBuffers::<f64>::LEAF; 7]
+        );
+        assert_eq!(flat.boundaries[4], [4.0, 8.0, 4.0, 8.0]);
+        assert_eq!(flat.boundaries[8], [2.0, 4.0, 2.0, 4.0]);
+
+        assert_eq!(flat.points, vec![[1.0, 1.0], [3.0, 3.0], [7.0, 7.0]]);
+        assert_eq!(flat.point_ranges[0], [0, 3]);
+        assert_eq!(flat.point_ranges[1], [0, 2]);
+        assert_eq!(flat.point_ranges[4], [2, 3]);
+        assert_eq!(flat.point_ranges[5], [0, 1]);
+        assert_eq!(flat.point_ranges[8], [1, 2]);
+    }
+}
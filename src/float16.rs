@@ -0,0 +1,62 @@
+//! Half precision coordinates, for when memory matters more than precision. The arithmetic is done
+//! in `f32` and rounded back. Needs the `half` feature.
+use crate::{Distance, Midpoint, NextUp, Snap};
+use half::f16;
+
+impl Midpoint for f16 {
+    fn midpoint(&self, a: f16) -> f16 {
+        f16::from_f32((self.to_f32() + a.to_f32()) / 2.0)
+    }
+}
+
+impl Distance for f16 {
+    fn distance(&self, a: f16) -> f64 {
+        (self.to_f64() - a.to_f64()).abs()
+    }
+}
+
+impl Snap for f16 {
+    fn snap(&self, resolution: f16) -> f16 {
+        f16::from_f32(self.to_f32().snap(resolution.to_f32()))
+    }
+}
+
+impl NextUp for f16 {
+    fn next_up(&self) -> f16 {
+        let bits = self.to_bits();
+        if self.is_nan() || *self == f16::INFINITY {
+            *self
+        } else if *self == f16::ZERO {
+            // Both zeros.
+            f16::from_bits(1)
+        } else if self.is_sign_positive() {
+            f16::from_bits(bits + 1)
+        } else {
+            f16::from_bits(bits - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NextUp, QuadTree as Q};
+    use half::f16;
+
+    #[test]
+    fn f16_coordinates() {
+        let h = f16::from_f32;
+        let mut qt = Q::with_node_capacity(4, (h(0.0), h(100.0), h(0.0), h(100.0)));
+        for i in 0..50 {
+            qt.insert((h(i as f32 * 2.0), h(99.0 - i as f32 * 2.0)));
+        }
+        assert_eq!(qt.size(), 50);
+        let found = qt.search(&(h(0.0), h(10.0), h(0.0), h(100.0)));
+        assert_eq!(found.len(), 5);
+        assert_eq!(qt.nearest(&(h(3.0), h(96.0))), Some((h(2.0), h(97.0))));
+        assert_eq!(Q::<f16>::from_bytes(&qt.to_bytes()).unwrap().size(), 50);
+
+        assert_eq!(NextUp::next_up(&h(-0.0)), f16::from_bits(1));
+        assert_eq!(NextUp::next_up(&f16::MAX), f16::INFINITY);
+        assert!(NextUp::next_up(&h(-1.0)) > h(-1.0));
+    }
+}
@@ -0,0 +1,688 @@
+//! A flat, offset based binary format for trees, that can be queried without loading it first.
+//!
+//! Everything is little endian and every field takes up 8 bytes, so the layout is the same on
+//! every platform:
+//!
+//! - Header: magic `b"QUADTREV"`, format version, coordinate type tag, capacity, duplicate
+//!   strategy, node count and point count.
+//! - Nodes, in breadth first order, 56 bytes each: the boundary (4 coordinates), a kind (0 for a
+//!   leaf, 1 for a node), then for leaves the index of the first point and the number of points,
+//!   and for nodes the index of the first of its four children (which are always next to each
+//!   other) and 0.
+//! - Points, two coordinates each, grouped by leaf.
+//! - Trailer: the length of everything before it, then a CRC-32 of the same bytes. Both are
+//!   checked on load, so a truncated or damaged file is an error rather than wrong answers.
+//!
+//! Nothing is ever read by casting pointers, so the bytes can come from anywhere (a `Vec`, a
+//! memory mapped file) with any alignment.
+//!
+//! Files from before the version field was added start with `b"QUADTREE"` and have no version;
+//! they count as version 0. Version 1 had no trailer. `QuadTree::from_bytes` reads any version,
+//! `MappedQuadTree` only the current one, so old files have to go through `migrate` once before
+//! they can be mapped.
+use crate::{Boundary, Config, Duplicates, Midpoint, Point, QuadTree};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt;
+use std::marker::PhantomData;
+
+const MAGIC: &[u8; 8] = b"QUADTREV";
+const MAGIC_V0: &[u8; 8] = b"QUADTREE";
+const VERSION: u64 = 2;
+const HEADER_SIZE: usize = 7 * 8;
+const TRAILER_SIZE: usize = 2 * 8;
+pub(crate) const NODE_SIZE: usize = 7 * 8;
+const POINT_SIZE: usize = 2 * 8;
+const LEAF: u64 = 0;
+pub(crate) const NODE: u64 = 1;
+
+/// A coordinate type that can be written to the binary format. Each coordinate takes up 8 bytes.
+/// The tag is stored in the header, so a file written with one type can't be read as another.
+pub trait FixedBytes: Sized {
+    const TYPE_TAG: u64;
+    fn to_bytes(&self) -> [u8; 8];
+    fn from_bytes(bytes: [u8; 8]) -> Self;
+}
+
+macro_rules! fixed_bytes {
+    ($t:ty, $tag:expr, $size:expr) => {
+        impl FixedBytes for $t {
+            const TYPE_TAG: u64 = $tag;
+
+            fn to_bytes(&self) -> [u8; 8] {
+                let mut bytes = [0; 8];
+                bytes[..$size].copy_from_slice(&self.to_le_bytes());
+                bytes
+            }
+
+            fn from_bytes(bytes: [u8; 8]) -> Self {
+                let mut own = [0; $size];
+                own.copy_from_slice(&bytes[..$size]);
+                <$t>::from_le_bytes(own)
+            }
+        }
+    };
+}
+
+fixed_bytes!(f32, 1, 4);
+fixed_bytes!(f64, 2, 8);
+fixed_bytes!(i32, 3, 4);
+fixed_bytes!(i64, 4, 8);
+fixed_bytes!(u32, 5, 4);
+fixed_bytes!(u64, 6, 8);
+#[cfg(feature = "half")]
+fixed_bytes!(half::f16, 7, 2);
+
+/// Why some bytes couldn't be read as a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatError {
+    /// Doesn't start with the magic bytes, so it's not a tree at all.
+    NotATree,
+    /// It's a tree, but of a different coordinate type.
+    WrongCoordinateType,
+    /// The bytes end before the header says they should.
+    Truncated,
+    /// Something in it points somewhere it shouldn't.
+    Corrupt,
+    /// Written in another version of the format. Older ones can be upgraded with `migrate`.
+    UnsupportedVersion(u64),
+    /// The bytes don't match the checksum they were written with.
+    ChecksumMismatch,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::NotATree => write!(f, "not a serialized quadtree"),
+            FormatError::WrongCoordinateType => write!(f, "quadtree has another coordinate type"),
+            FormatError::Truncated => write!(f, "serialized quadtree is truncated"),
+            FormatError::Corrupt => write!(f, "serialized quadtree is corrupt"),
+            FormatError::ChecksumMismatch => write!(f, "serialized quadtree fails its checksum"),
+            FormatError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "serialized quadtree has unsupported format version {}",
+                    v
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Writes the tree in the format described in the `format` module. Tolerance, grid and
+    /// maximum depth settings aren't saved.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut nodes = vec![];
+        let mut points = vec![];
+        let (mut next_node, mut next_point) = (1, 0);
+        // With the root at 0 and its children right after, records come out in index order.
+        lay_out(
+            self,
+            0,
+            &mut next_node,
+            &mut next_point,
+            &mut |_, record| nodes.extend_from_slice(&record),
+            &mut points,
+        );
+
+        let mut out = header(self.get_config(), next_node, next_point);
+        out.extend_from_slice(&nodes);
+        out.extend_from_slice(&points);
+        let trailer = trailer(&out);
+        out.extend_from_slice(&trailer);
+        out
+    }
+
+    /// Reads a tree written by `to_bytes` back into memory, including ones written by older
+    /// versions of this crate.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FormatError> {
+        let bytes = migrate(bytes)?;
+        let mapped = MappedQuadTree::new(&bytes)?;
+        Ok(mapped.load(0))
+    }
+}
+
+/// Upgrades bytes written in any older version of the format to the current one. Bytes that are
+/// already current are handed back as they are.
+pub fn migrate(bytes: &[u8]) -> Result<Cow<'_, [u8]>, FormatError> {
+    let mut bytes = Cow::Borrowed(bytes);
+    let mut version = version_of(&bytes)?;
+    while version < VERSION {
+        bytes = Cow::Owned(match version {
+            0 => migrate_v0(&bytes),
+            1 => migrate_v1(&bytes),
+            _ => unreachable!(),
+        });
+        version += 1;
+    }
+    if version > VERSION {
+        return Err(FormatError::UnsupportedVersion(version));
+    }
+    Ok(bytes)
+}
+
+fn version_of(bytes: &[u8]) -> Result<u64, FormatError> {
+    if bytes.len() >= 16 && &bytes[..8] == MAGIC {
+        Ok(read_u64(bytes, 8))
+    } else if bytes.len() >= 8 && &bytes[..8] == MAGIC_V0 {
+        Ok(0)
+    } else {
+        Err(FormatError::NotATree)
+    }
+}
+
+/// Version 0 is version 1 without the version field.
+fn migrate_v0(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 8);
+    out.extend_from_slice(MAGIC);
+    push_u64(&mut out, 1);
+    out.extend_from_slice(&bytes[8..]);
+    out
+}
+
+/// Version 1 is version 2 without the trailer. Whatever follows the points is dropped.
+fn migrate_v1(bytes: &[u8]) -> Vec<u8> {
+    let mut out = bytes.to_vec();
+    if bytes.len() >= HEADER_SIZE {
+        let node_count = read_u64(bytes, 40) as usize;
+        let point_count = read_u64(bytes, 48) as usize;
+        let end = node_count
+            .checked_mul(NODE_SIZE)
+            .and_then(|n| point_count.checked_mul(POINT_SIZE)?.checked_add(n))
+            .and_then(|n| n.checked_add(HEADER_SIZE));
+        if let Some(end) = end.filter(|&end| end <= bytes.len()) {
+            out.truncate(end);
+        }
+    }
+    out[8..16].copy_from_slice(&2u64.to_le_bytes());
+    let trailer = trailer(&out);
+    out.extend_from_slice(&trailer);
+    out
+}
+
+pub(crate) fn header<T: FixedBytes>(
+    config: &Config<T>,
+    node_count: usize,
+    point_count: usize,
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_SIZE);
+    out.extend_from_slice(MAGIC);
+    push_u64(&mut out, VERSION);
+    push_u64(&mut out, T::TYPE_TAG);
+    push_u64(&mut out, config.capacity as u64);
+    push_u64(&mut out, duplicates_to_u64(config.duplicates));
+    push_u64(&mut out, node_count as u64);
+    push_u64(&mut out, point_count as u64);
+    out
+}
+
+/// The trailer for a file made of `bytes`.
+pub(crate) fn trailer(bytes: &[u8]) -> [u8; TRAILER_SIZE] {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    trailer_for(bytes.len(), crc)
+}
+
+pub(crate) fn trailer_for(length: usize, crc: Crc32) -> [u8; TRAILER_SIZE] {
+    let mut trailer = [0; TRAILER_SIZE];
+    trailer[..8].copy_from_slice(&(length as u64).to_le_bytes());
+    trailer[8..].copy_from_slice(&(crc.finish() as u64).to_le_bytes());
+    trailer
+}
+
+/// CRC-32 as zip and PNG use it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Crc32(u32);
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            bit += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Crc32(!0)
+    }
+
+    pub(crate) fn update(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = CRC_TABLE[((self.0 ^ b as u32) & 0xff) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    pub(crate) fn finish(self) -> u32 {
+        !self.0
+    }
+}
+
+pub(crate) fn node_record<T: FixedBytes>(
+    (x1, x2, y1, y2): &Boundary<T>,
+    kind: u64,
+    first: usize,
+    count: usize,
+) -> [u8; NODE_SIZE] {
+    let mut record = [0; NODE_SIZE];
+    for (i, c) in [x1, x2, y1, y2].iter().enumerate() {
+        record[i * 8..i * 8 + 8].copy_from_slice(&c.to_bytes());
+    }
+    record[32..40].copy_from_slice(&kind.to_le_bytes());
+    record[40..48].copy_from_slice(&(first as u64).to_le_bytes());
+    record[48..56].copy_from_slice(&(count as u64).to_le_bytes());
+    record
+}
+
+/// Lays out `tree` breadth first with its root at index `root`. The nodes below it are numbered
+/// from `next_node` on and its points from `next_point` on, and both are moved past what was used.
+/// Node records are handed to `nodes` along with their index, points are appended to `points`.
+pub(crate) fn lay_out<T, N>(
+    tree: &QuadTree<T>,
+    root: usize,
+    next_node: &mut usize,
+    next_point: &mut usize,
+    nodes: &mut N,
+    points: &mut Vec<u8>,
+) where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+    N: FnMut(usize, [u8; NODE_SIZE]),
+{
+    let mut queue = VecDeque::new();
+    queue.push_back((root, tree));
+    while let Some((index, node)) = queue.pop_front() {
+        match node {
+            QuadTree::Leaf(_, boundary, leaf_points) => {
+                nodes(
+                    index,
+                    node_record(boundary, LEAF, *next_point, leaf_points.len()),
+                );
+                for (x, y) in leaf_points {
+                    points.extend_from_slice(&x.to_bytes());
+                    points.extend_from_slice(&y.to_bytes());
+                }
+                *next_point += leaf_points.len();
+            }
+            QuadTree::Node(_, boundary, _, children) => {
+                nodes(index, node_record(boundary, NODE, *next_node, 0));
+                for child in children.iter() {
+                    queue.push_back((*next_node, child));
+                    *next_node += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A tree in the binary format, queried right where it is, e.g. in a memory mapped file much
+/// bigger than RAM. It's read only.
+#[derive(Debug, Clone, Copy)]
+pub struct MappedQuadTree<'a, T> {
+    bytes: &'a [u8],
+    config: Config<T>,
+    node_count: usize,
+    point_count: usize,
+    coordinates: PhantomData<T>,
+}
+
+impl<'a, T> MappedQuadTree<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Checks the header, the checksum and every node once, so that queries never have to. Only
+    /// reads the current version of the format.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        Self::read(bytes, true)
+    }
+
+    /// Same as `new`, but skips the checksum, which has to read every byte. For files so big that
+    /// it would take too long. Nodes are still checked, so a damaged file can't make queries
+    /// panic, but it can make them return the wrong points.
+    pub fn new_unverified(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        Self::read(bytes, false)
+    }
+
+    fn read(bytes: &'a [u8], verify: bool) -> Result<Self, FormatError> {
+        let version = version_of(bytes)?;
+        if version != VERSION {
+            return Err(FormatError::UnsupportedVersion(version));
+        }
+        if bytes.len() < HEADER_SIZE {
+            return Err(FormatError::Truncated);
+        }
+        if read_u64(bytes, 16) != T::TYPE_TAG {
+            return Err(FormatError::WrongCoordinateType);
+        }
+        let config = Config {
+            capacity: read_u64(bytes, 24) as usize,
+            duplicates: duplicates_from_u64(read_u64(bytes, 32)).ok_or(FormatError::Corrupt)?,
+            ..Config::default()
+        };
+        if config.capacity == 0 {
+            return Err(FormatError::Corrupt);
+        }
+        let node_count = read_u64(bytes, 40) as usize;
+        let point_count = read_u64(bytes, 48) as usize;
+        let expected = node_count
+            .checked_mul(NODE_SIZE)
+            .and_then(|n| point_count.checked_mul(POINT_SIZE)?.checked_add(n))
+            .and_then(|n| n.checked_add(HEADER_SIZE))
+            .ok_or(FormatError::Corrupt)?;
+        if bytes.len().saturating_sub(expected) < TRAILER_SIZE {
+            return Err(FormatError::Truncated);
+        }
+        if read_u64(bytes, expected) != expected as u64 {
+            return Err(FormatError::Corrupt);
+        }
+        if verify && trailer(&bytes[..expected])[..] != bytes[expected..expected + TRAILER_SIZE] {
+            return Err(FormatError::ChecksumMismatch);
+        }
+        if node_count == 0 {
+            return Err(FormatError::Corrupt);
+        }
+
+        let mapped = MappedQuadTree {
+            bytes,
+            config,
+            node_count,
+            point_count,
+            coordinates: PhantomData,
+        };
+        for i in 0..node_count {
+            let (kind, first, count) = mapped.node_links(i);
+            let ok = match kind {
+                // Children always come later, so there can't be any cycles.
+                NODE => {
+                    first > i as u64
+                        && first
+                            .checked_add(4)
+                            .is_some_and(|end| end <= node_count as u64)
+                }
+                LEAF => first
+                    .checked_add(count)
+                    .is_some_and(|end| end <= point_count as u64),
+                _ => false,
+            };
+            if !ok {
+                return Err(FormatError::Corrupt);
+            }
+        }
+        Ok(mapped)
+    }
+
+    pub fn size(&self) -> usize {
+        self.point_count
+    }
+
+    pub fn boundary(&self) -> Boundary<T> {
+        self.node_boundary(0)
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            if !QuadTree::intersects(&self.node_boundary(i), boundary) {
+                continue;
+            }
+            match self.node_links(i) {
+                (LEAF, first, count) => {
+                    for p in first..first + count {
+                        let point = self.point(p as usize);
+                        if QuadTree::contains(boundary, &point) {
+                            found.push(point);
+                        }
+                    }
+                }
+                (_, first, _) => stack.extend((first..first + 4).rev().map(|c| c as usize)),
+            }
+        }
+        found
+    }
+
+    fn load(&self, i: usize) -> QuadTree<T> {
+        let boundary = self.node_boundary(i);
+        match self.node_links(i) {
+            (LEAF, first, count) => {
+                let points = (first..first + count).map(|p| self.point(p as usize));
+                QuadTree::Leaf(self.config, boundary, points.collect())
+            }
+            (_, first, _) => {
+                let first = first as usize;
+                let children = Box::new([
+                    self.load(first),
+                    self.load(first + 1),
+                    self.load(first + 2),
+                    self.load(first + 3),
+                ]);
+                let size = children.iter().map(|c| c.size()).sum();
+                QuadTree::Node(self.config, boundary, size, children)
+            }
+        }
+    }
+
+    fn node_boundary(&self, i: usize) -> Boundary<T> {
+        let at = HEADER_SIZE + i * NODE_SIZE;
+        (
+            read_coordinate(self.bytes, at),
+            read_coordinate(self.bytes, at + 8),
+            read_coordinate(self.bytes, at + 16),
+            read_coordinate(self.bytes, at + 24),
+        )
+    }
+
+    fn node_links(&self, i: usize) -> (u64, u64, u64) {
+        let at = HEADER_SIZE + i * NODE_SIZE + 32;
+        (
+            read_u64(self.bytes, at),
+            read_u64(self.bytes, at + 8),
+            read_u64(self.bytes, at + 16),
+        )
+    }
+
+    fn point(&self, p: usize) -> Point<T> {
+        let at = HEADER_SIZE + self.node_count * NODE_SIZE + p * POINT_SIZE;
+        (
+            read_coordinate(self.bytes, at),
+            read_coordinate(self.bytes, at + 8),
+        )
+    }
+}
+
+/// Memory maps a file written with `to_bytes`. Use `MappedQuadTree::new` on the result to query
+/// it. Needs the `memmap2` feature.
+///
+/// # Safety
+///
+/// The file must not be changed, by this or any other process, while it is mapped.
+#[cfg(feature = "memmap2")]
+pub unsafe fn map_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<memmap2::Mmap> {
+    let file = std::fs::File::open(path)?;
+    memmap2::Mmap::map(&file)
+}
+
+fn duplicates_to_u64(duplicates: Duplicates) -> u64 {
+    match duplicates {
+        Duplicates::Linear => 0,
+        Duplicates::Sorted => 1,
+        Duplicates::Trust => 2,
+    }
+}
+
+fn duplicates_from_u64(n: u64) -> Option<Duplicates> {
+    match n {
+        0 => Some(Duplicates::Linear),
+        1 => Some(Duplicates::Sorted),
+        2 => Some(Duplicates::Trust),
+        _ => None,
+    }
+}
+
+fn push_u64(out: &mut Vec<u8>, n: u64) {
+    out.extend_from_slice(&n.to_le_bytes());
+}
+
+fn read_slot(bytes: &[u8], at: usize) -> [u8; 8] {
+    let mut slot = [0; 8];
+    slot.copy_from_slice(&bytes[at..at + 8]);
+    slot
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(read_slot(bytes, at))
+}
+
+fn read_coordinate<T: FixedBytes>(bytes: &[u8], at: usize) -> T {
+    T::from_bytes(read_slot(bytes, at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate, Crc32, FormatError, MappedQuadTree, HEADER_SIZE, TRAILER_SIZE};
+    use crate::QuadTree as Q;
+
+    fn tree() -> Q<f64> {
+        let mut qt = Q::with_node_capacity(4, (0.0, 100.0, 0.0, 100.0));
+        for i in 0..50 {
+            for j in 0..20 {
+                qt.insert((i as f64 * 2.0, j as f64 * 5.0 + 0.5));
+            }
+        }
+        qt
+    }
+
+    #[test]
+    fn round_trip() {
+        let qt = tree();
+        let bytes = qt.to_bytes();
+        let back = Q::<f64>::from_bytes(&bytes).unwrap();
+        assert_eq!(back.size(), qt.size());
+        assert_eq!(back.to_bytes(), bytes);
+
+        // Reading from an unaligned spot has to work just as well.
+        let mut shifted = vec![0u8];
+        shifted.extend_from_slice(&bytes);
+        let mapped = MappedQuadTree::<f64>::new(&shifted[1..]).unwrap();
+        assert_eq!(mapped.size(), qt.size());
+        assert_eq!(mapped.boundary(), (0.0, 100.0, 0.0, 100.0));
+        let search = (10.0, 30.5, 20.0, 60.0);
+        let mut a = mapped.search(&search);
+        let mut b = qt.search(&search);
+        a.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        b.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn bad_bytes() {
+        let bytes = tree().to_bytes();
+        assert_eq!(
+            MappedQuadTree::<f64>::new(b"not a tree").unwrap_err(),
+            FormatError::NotATree
+        );
+        assert_eq!(
+            MappedQuadTree::<i64>::new(&bytes).unwrap_err(),
+            FormatError::WrongCoordinateType
+        );
+        assert_eq!(
+            MappedQuadTree::<f64>::new(&bytes[..bytes.len() - 1]).unwrap_err(),
+            FormatError::Truncated
+        );
+
+        // Point the root at itself.
+        let mut corrupt = bytes.clone();
+        corrupt[HEADER_SIZE + 40..HEADER_SIZE + 48].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(
+            MappedQuadTree::<f64>::new(&corrupt).unwrap_err(),
+            FormatError::ChecksumMismatch
+        );
+        assert_eq!(
+            MappedQuadTree::<f64>::new_unverified(&corrupt).unwrap_err(),
+            FormatError::Corrupt
+        );
+        // And at children way past the end.
+        corrupt[HEADER_SIZE + 40..HEADER_SIZE + 48].copy_from_slice(&(u64::MAX - 1).to_le_bytes());
+        assert_eq!(
+            MappedQuadTree::<f64>::new_unverified(&corrupt).unwrap_err(),
+            FormatError::Corrupt
+        );
+
+        let mut future = bytes;
+        future[8..16].copy_from_slice(&3u64.to_le_bytes());
+        assert_eq!(
+            Q::<f64>::from_bytes(&future).unwrap_err(),
+            FormatError::UnsupportedVersion(3)
+        );
+    }
+
+    #[test]
+    fn checksum() {
+        let mut crc = Crc32::new();
+        crc.update(b"123456789");
+        assert_eq!(crc.finish(), 0xcbf4_3926);
+
+        let bytes = tree().to_bytes();
+        // Moving a point is still a perfectly good tree, just not the one that was written.
+        let mut flipped = bytes.clone();
+        let last_point = bytes.len() - TRAILER_SIZE - 8;
+        flipped[last_point] ^= 1;
+        assert_eq!(
+            Q::<f64>::from_bytes(&flipped).unwrap_err(),
+            FormatError::ChecksumMismatch
+        );
+        assert!(MappedQuadTree::<f64>::new_unverified(&flipped).is_ok());
+
+        let mut longer = bytes;
+        longer.extend_from_slice(&[0; 8]);
+        assert!(MappedQuadTree::<f64>::new(&longer).is_ok());
+    }
+
+    #[test]
+    fn reads_version_0() {
+        let qt = tree();
+        let current = qt.to_bytes();
+        // Version 0 had the old magic, no version field and no trailer, otherwise the same.
+        let mut old = b"QUADTREE".to_vec();
+        old.extend_from_slice(&current[16..current.len() - TRAILER_SIZE]);
+
+        assert_eq!(
+            MappedQuadTree::<f64>::new(&old).unwrap_err(),
+            FormatError::UnsupportedVersion(0)
+        );
+        assert_eq!(Q::<f64>::from_bytes(&old).unwrap().to_bytes(), current);
+        assert_eq!(&*migrate(&old).unwrap(), &current[..]);
+    }
+
+    #[cfg(feature = "memmap2")]
+    #[test]
+    fn map_file() {
+        let qt = tree();
+        let path = std::env::temp_dir().join(format!("quadtree-map-{}", std::process::id()));
+        std::fs::write(&path, qt.to_bytes()).unwrap();
+        let map = unsafe { super::map_file(&path) }.unwrap();
+        let mapped = MappedQuadTree::<f64>::new(&map).unwrap();
+        assert_eq!(mapped.size(), qt.size());
+        drop(map);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{geom, Boundary, FlatBuffers, Midpoint, Point, QuadTree};
+
+/// A read only copy of a tree, laid out in a few flat arrays instead of boxed nodes, which makes
+/// searching it quite a bit faster. Handy for serving a tree that's built once and then only
+/// queried. Made with `QuadTree::freeze`.
+///
+/// Nodes are stored breadth first with a node's four children next to each other, and points
+/// depth first, so every node knows the one range of points below it. A node that lies wholly
+/// inside a search hands over that range without looking at its children.
+#[derive(Debug, Clone)]
+pub struct FrozenQuadTree<T> {
+    boundaries: Vec<Boundary<T>>,
+    first_child: Vec<u32>,
+    ranges: Vec<(u32, u32)>,
+    points: Vec<Point<T>>,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn freeze(&self) -> FrozenQuadTree<T> {
+        FrozenQuadTree::from(self.to_flat_buffers())
+    }
+}
+
+impl<T: Copy> From<FlatBuffers<T>> for FrozenQuadTree<T> {
+    fn from(flat: FlatBuffers<T>) -> Self {
+        FrozenQuadTree {
+            boundaries: flat
+                .boundaries
+                .into_iter()
+                .map(|[x1, x2, y1, y2]| (x1, x2, y1, y2))
+                .collect(),
+            first_child: flat.first_child,
+            ranges: flat
+                .point_ranges
+                .into_iter()
+                .map(|[start, end]| (start, end))
+                .collect(),
+            points: flat.points.into_iter().map(|[x, y]| (x, y)).collect(),
+        }
+    }
+}
+
+impl<T> FrozenQuadTree<T>
+where
+    T: PartialOrd + Copy,
+{
+    pub fn get_boundary(&self) -> Boundary<T> {
+        self.boundaries[0]
+    }
+
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Every point, in the order they're stored.
+    pub fn points(&self) -> &[Point<T>] {
+        &self.points
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        if geom::intersects(&self.boundaries[0], boundary) {
+            self.search_from(0, boundary, &mut found);
+        }
+        found
+    }
+
+    fn search_from(&self, i: usize, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        let (start, end) = self.ranges[i];
+        let points = &self.points[start as usize..end as usize];
+        let first = self.first_child[i];
+        if geom::covers(boundary, &self.boundaries[i]) {
+            found.extend_from_slice(points);
+        } else if first == FlatBuffers::<T>::LEAF {
+            found.extend(points.iter().filter(|p| geom::contains(boundary, p)));
+        } else {
+            let first = first as usize;
+            for child in first..first + 4 {
+                let (start, end) = self.ranges[child];
+                if start < end && geom::intersects(&self.boundaries[child], boundary) {
+                    self.search_from(child, boundary, found);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn same_as_quadtree() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..2000 {
+            qt.insert((rng.next(), rng.next()));
+        }
+        let frozen = qt.freeze();
+        assert_eq!(frozen.size(), qt.size());
+        assert_eq!(frozen.get_boundary(), (0, 1000, 0, 1000));
+        for _ in 0..50 {
+            let (x, y) = (rng.next(), rng.next());
+            let query = (x, x + rng.next() / 4, y, y + rng.next() / 4);
+            let mut a = frozen.search(&query);
+            let mut b = qt.search(&query);
+            a.sort();
+            b.sort();
+            assert_eq!(a, b);
+        }
+        assert!(frozen.search(&(2000, 3000, 0, 10)).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+//! Operations on boundaries and points, the same ones the tree uses, for building queries of
+//! your own. Boundaries are half open like everywhere else: a point on `x2` or `y2` is outside.
+use crate::nearest::distance_to_boundary_sq;
+use crate::{Boundary, Distance, NextUp, Point};
+use std::ops::{Add, Sub};
+
+/// Ways of making a `Boundary` without working out the tuple by hand. Have it in scope to call
+/// them as `Boundary::from_center_half_extents(..)`.
+pub trait BoundaryExt<T>: Sized {
+    /// The boundary reaching `hx` to either side of `cx`, and `hy` above and below `cy`.
+    fn from_center_half_extents(cx: T, cy: T, hx: T, hy: T) -> Self
+    where
+        T: Add<Output = T> + Sub<Output = T> + Copy;
+
+    /// The smallest boundary that `contains` all the points, or `None` if there aren't any.
+    fn bounding<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point<T>>,
+        T: PartialOrd + Copy + NextUp;
+}
+
+impl<T> BoundaryExt<T> for Boundary<T> {
+    fn from_center_half_extents(cx: T, cy: T, hx: T, hy: T) -> Self
+    where
+        T: Add<Output = T> + Sub<Output = T> + Copy,
+    {
+        (cx - hx, cx + hx, cy - hy, cy + hy)
+    }
+
+    fn bounding<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point<T>>,
+        T: PartialOrd + Copy + NextUp,
+    {
+        let mut points = points.into_iter();
+        let (x, y) = points.next()?;
+        let (x1, x2, y1, y2) = points.fold((x, x, y, y), |(x1, x2, y1, y2), (x, y)| {
+            (min(x1, x), max(x2, x), min(y1, y), max(y2, y))
+        });
+        Some((x1, x2.next_up(), y1, y2.next_up()))
+    }
+}
+
+/// Whether `point` is inside `boundary`.
+pub fn contains<T: PartialOrd>((x1, x2, y1, y2): &Boundary<T>, (x, y): &Point<T>) -> bool {
+    *x1 <= *x && *x2 > *x && *y1 <= *y && *y2 > *y
+}
+
+/// Whether the two boundaries overlap. Only touching doesn't count.
+pub fn intersects<T: PartialOrd>(
+    (a_x1, a_x2, a_y1, a_y2): &Boundary<T>,
+    (b_x1, b_x2, b_y1, b_y2): &Boundary<T>,
+) -> bool {
+    a_x1 < b_x2 && a_x2 > b_x1 && a_y1 < b_y2 && a_y2 > b_y1
+}
+
+/// Whether all of `inner` is inside `outer`.
+pub fn covers<T: PartialOrd>(outer: &Boundary<T>, inner: &Boundary<T>) -> bool {
+    outer.0 <= inner.0 && inner.1 <= outer.1 && outer.2 <= inner.2 && inner.3 <= outer.3
+}
+
+/// The smallest boundary around both.
+pub fn union<T: PartialOrd + Copy>(
+    (a_x1, a_x2, a_y1, a_y2): &Boundary<T>,
+    (b_x1, b_x2, b_y1, b_y2): &Boundary<T>,
+) -> Boundary<T> {
+    (
+        min(*a_x1, *b_x1),
+        max(*a_x2, *b_x2),
+        min(*a_y1, *b_y1),
+        max(*a_y2, *b_y2),
+    )
+}
+
+/// The part the two boundaries have in common, if they `intersects`.
+pub fn intersection<T: PartialOrd + Copy>(a: &Boundary<T>, b: &Boundary<T>) -> Option<Boundary<T>> {
+    if !intersects(a, b) {
+        return None;
+    }
+    Some((max(a.0, b.0), min(a.1, b.1), max(a.2, b.2), min(a.3, b.3)))
+}
+
+/// The area of the intersection. Zero if they don't intersect.
+pub fn overlap_area<T: PartialOrd + Copy + Distance>(a: &Boundary<T>, b: &Boundary<T>) -> f64 {
+    match intersection(a, b) {
+        Some((x1, x2, y1, y2)) => x1.distance(x2) * y1.distance(y2),
+        None => 0.0,
+    }
+}
+
+/// Moves `point` onto the closest spot of `boundary`. Points already inside stay where they are.
+/// Note that a point moved onto the `x2` or `y2` edge isn't `contains`'d by the boundary.
+pub fn clamp<T: PartialOrd + Copy>((x1, x2, y1, y2): &Boundary<T>, (x, y): &Point<T>) -> Point<T> {
+    (max(*x1, min(*x, *x2)), max(*y1, min(*y, *y2)))
+}
+
+/// Distance from `point` to the closest spot of `boundary`. Zero if it's inside.
+pub fn distance<T: PartialOrd + Copy + Distance>(boundary: &Boundary<T>, point: &Point<T>) -> f64 {
+    distance_to_boundary_sq(boundary, point).sqrt()
+}
+
+fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geom() {
+        let a = (0, 10, 0, 10);
+        let b = (5, 20, -5, 5);
+        assert!(contains(&a, &(0, 0)));
+        assert!(!contains(&a, &(10, 5)));
+        assert!(intersects(&a, &b));
+        assert!(!intersects(&a, &(10, 20, 0, 10)));
+        assert!(covers(&a, &(0, 10, 2, 3)));
+        assert!(!covers(&a, &b));
+        assert_eq!(union(&a, &b), (0, 20, -5, 10));
+        assert_eq!(intersection(&a, &b), Some((5, 10, 0, 5)));
+        assert_eq!(intersection(&a, &(10, 20, 0, 10)), None);
+        assert_eq!(overlap_area(&a, &b), 25.0);
+        assert_eq!(overlap_area(&a, &(10, 20, 0, 10)), 0.0);
+        assert_eq!(clamp(&a, &(-3, 4)), (0, 4));
+        assert_eq!(clamp(&a, &(12, 15)), (10, 10));
+        assert_eq!(distance(&a, &(13, 14)), 5.0);
+        assert_eq!(distance(&a, &(3, 3)), 0.0);
+    }
+
+    #[test]
+    fn boundary_constructors() {
+        assert_eq!(
+            Boundary::from_center_half_extents(5.0, 2.0, 1.5, 2.0),
+            (3.5, 6.5, 0.0, 4.0)
+        );
+
+        let points = vec![(3.0, -1.0), (-2.0, 4.0), (0.5, 0.5)];
+        let b: Boundary<f64> = Boundary::bounding(points.clone()).unwrap();
+        assert_eq!((b.0, b.2), (-2.0, -1.0));
+        assert!(points.iter().all(|p| contains(&b, p)));
+        assert_eq!(Boundary::bounding(vec![(1, 2), (3, 0)]), Some((1, 4, 0, 3)));
+        assert_eq!(Boundary::<u32>::bounding(vec![]), None);
+    }
+}
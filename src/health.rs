@@ -0,0 +1,131 @@
+use crate::{Midpoint, Point, QuadTree};
+
+/// How well a tree's shape fits the points in it. See `QuadTree::health`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Health {
+    pub size: usize,
+    pub leaves: usize,
+    pub empty_leaves: usize,
+    /// How deep the deepest leaf is, with the root at 0.
+    pub depth: usize,
+    /// The points in the tree over the room its leaves have, between 0 and 1. A tree that's just
+    /// been built from uniform data tends to be somewhere around 0.3 to 0.5.
+    pub fill: f64,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Clone + Midpoint,
+{
+    /// Walks the whole tree to measure its shape. Removes merge nodes back as they go, but
+    /// `balance`, `from_quadrants` and trees loaded from elsewhere can leave nodes holding a few
+    /// points spread over lots of (mostly empty) leaves. That shows up as a low `fill`.
+    pub fn health(&self) -> Health {
+        let mut health = Health {
+            size: self.size(),
+            leaves: 0,
+            empty_leaves: 0,
+            depth: 0,
+            fill: 1.0,
+        };
+        self.measure(0, &mut health);
+        let room = health.leaves * self.get_config().capacity;
+        if room > 0 {
+            health.fill = health.size as f64 / room as f64;
+        }
+        health
+    }
+
+    fn measure(&self, depth: usize, health: &mut Health) {
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                health.leaves += 1;
+                if points.is_empty() {
+                    health.empty_leaves += 1;
+                }
+                health.depth = health.depth.max(depth);
+            }
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.measure(depth + 1, health);
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the tree from its points if its `fill` has dropped below `min_fill`, and returns
+    /// whether it did. Use a `min_fill` well below what a fresh tree of your data has, so it
+    /// doesn't rebuild over and over.
+    pub fn maybe_rebuild(&mut self, min_fill: f64) -> bool {
+        if matches!(self, QuadTree::Leaf(_, _, _)) || self.health().fill >= min_fill {
+            return false;
+        }
+        self.rebuild();
+        true
+    }
+
+    /// Builds the tree again from scratch with the points it holds, giving it the same shape it
+    /// would have had if they'd been the only ones ever inserted.
+    pub fn rebuild(&mut self) {
+        let fresh = QuadTree::Leaf(
+            self.get_config().clone(),
+            self.get_boundary().clone(),
+            vec![],
+        );
+        let old = std::mem::replace(self, fresh);
+        let mut points = Vec::with_capacity(old.size());
+        old.into_points(&mut points);
+        // They're all inside and already unique (and snapped, if there's a grid).
+        for point in points {
+            self.insert_contained(point, false, 0, &mut ());
+        }
+    }
+
+    fn into_points(self, out: &mut Vec<Point<T>>) {
+        match self {
+            QuadTree::Leaf(_, _, mut points) => out.append(&mut points),
+            QuadTree::Node(_, _, _, children) => {
+                for child in *children {
+                    child.into_points(out);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn rebuild_after_balance() {
+        let mut qt = Q::with_node_capacity(4, (0, 1024, 0, 1024));
+        for x in 0..4 {
+            for y in 0..4 {
+                qt.insert((520 + x, 520 + y));
+            }
+        }
+        qt.insert((1000, 1000));
+        let fresh = qt.health();
+        assert!(!qt.maybe_rebuild(fresh.fill / 2.0));
+
+        // Balancing splits the big empty leaves next to the cluster.
+        qt.balance();
+        let balanced = qt.health();
+        assert_eq!(balanced.size, 17);
+        assert!(
+            balanced.fill < fresh.fill / 2.0,
+            "{:?} {:?}",
+            balanced,
+            fresh
+        );
+
+        let mut before = qt.search(&(0, 1024, 0, 1024));
+        assert!(qt.maybe_rebuild(fresh.fill / 2.0));
+        assert_eq!(qt.health(), fresh);
+        let mut after = qt.search(&(0, 1024, 0, 1024));
+        before.sort();
+        after.sort();
+        assert_eq!(before, after);
+    }
+}
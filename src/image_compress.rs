@@ -0,0 +1,252 @@
+//! Lossy image compression with a region quadtree. The image is split into quarters until every
+//! block is close enough to one colour, and each block is stored as its average colour.
+//!
+//! The bytes are:
+//!
+//! - Header: magic `b"QTIC"`, then width and height as little endian `u32`s.
+//! - The blocks, depth first, starting with the whole image and going through the quarters in
+//!   the same order as the children of a node. A block is a 0 followed by its red, green and blue,
+//!   or a 1 followed by its four quarters. Blocks of a single pixel are never split, so they skip
+//!   the 0, and blocks with no pixels (from sides that aren't a power of two) take no bytes.
+
+use crate::{Boundary, QuadTree};
+use std::fmt;
+
+const MAGIC: &[u8; 4] = b"QTIC";
+
+/// An RGB image, a row at a time from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl Image {
+    fn pixel(&self, x: u32, y: u32) -> [u8; 3] {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+}
+
+/// Why some bytes couldn't be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Doesn't start with the magic bytes.
+    NotAnImage,
+    /// The bytes end in the middle of a block.
+    Truncated,
+    /// A block has a tag other than 0 or 1, or there are bytes left over after the last block.
+    Corrupt,
+    /// The header asks for more pixels than fit in memory.
+    TooLarge,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::NotAnImage => write!(f, "not a quadtree compressed image"),
+            DecodeError::Truncated => write!(f, "compressed image is truncated"),
+            DecodeError::Corrupt => write!(f, "compressed image is corrupt"),
+            DecodeError::TooLarge => write!(f, "compressed image is too large"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Compresses `image`, splitting blocks until the variance of their colours is at most
+/// `tolerance`. The variance is summed over red, green and blue, so a tolerance of 0 keeps the
+/// image exactly and 300 allows about 10 either way in each.
+pub fn encode(image: &Image, tolerance: f64) -> Vec<u8> {
+    assert_eq!(
+        image.pixels.len() as u64,
+        image.width as u64 * image.height as u64,
+        "pixels must be width * height"
+    );
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&image.width.to_le_bytes());
+    bytes.extend_from_slice(&image.height.to_le_bytes());
+    encode_block(
+        image,
+        (0, image.width, 0, image.height),
+        tolerance,
+        &mut bytes,
+    );
+    bytes
+}
+
+fn encode_block(image: &Image, boundary: Boundary<u32>, tolerance: f64, bytes: &mut Vec<u8>) {
+    let (x1, x2, y1, y2) = boundary;
+    let count = ((x2 - x1) as u64 * (y2 - y1) as u64) as f64;
+    if count == 0.0 {
+        return;
+    }
+    if count == 1.0 {
+        bytes.extend_from_slice(&image.pixel(x1, y1));
+        return;
+    }
+    let pixels = || (y1..y2).flat_map(move |y| (x1..x2).map(move |x| image.pixel(x, y)));
+    let mut mean = [0.0; 3];
+    for pixel in pixels() {
+        for (m, c) in mean.iter_mut().zip(pixel.iter()) {
+            *m += *c as f64 / count;
+        }
+    }
+    let variance: f64 = pixels()
+        .map(|pixel| {
+            (0..3)
+                .map(|i| (pixel[i] as f64 - mean[i]).powi(2))
+                .sum::<f64>()
+        })
+        .sum::<f64>()
+        / count;
+    if variance <= tolerance {
+        bytes.push(0);
+        bytes.extend(mean.iter().map(|m| m.round() as u8));
+    } else {
+        bytes.push(1);
+        for quarter in QuadTree::quadrants(&boundary).iter() {
+            encode_block(image, *quarter, tolerance, bytes);
+        }
+    }
+}
+
+/// Turns bytes from `encode` back into pixels.
+pub fn decode(bytes: &[u8]) -> Result<Image, DecodeError> {
+    if bytes.len() < 12 || &bytes[..4] != MAGIC {
+        return Err(DecodeError::NotAnImage);
+    }
+    let u32_at =
+        |at: usize| u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+    let (width, height) = (u32_at(4), u32_at(8));
+    // The header can't be trusted, so all the blocks are read before the pixels are allocated.
+    let mut blocks = vec![];
+    let mut rest = &bytes[12..];
+    decode_block((0, width, 0, height), &mut rest, &mut blocks)?;
+    if !rest.is_empty() {
+        return Err(DecodeError::Corrupt);
+    }
+    let len = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or(DecodeError::TooLarge)?;
+    let mut pixels = vec![];
+    pixels
+        .try_reserve_exact(len)
+        .map_err(|_| DecodeError::TooLarge)?;
+    pixels.resize(len, [0; 3]);
+    for ((x1, x2, y1, y2), colour) in blocks {
+        for y in y1..y2 {
+            let row = y as usize * width as usize;
+            pixels[row + x1 as usize..row + x2 as usize].fill(colour);
+        }
+    }
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn decode_block(
+    boundary: Boundary<u32>,
+    bytes: &mut &[u8],
+    blocks: &mut Vec<(Boundary<u32>, [u8; 3])>,
+) -> Result<(), DecodeError> {
+    let (x1, x2, y1, y2) = boundary;
+    let count = (x2 - x1) as u64 * (y2 - y1) as u64;
+    if count == 0 {
+        return Ok(());
+    }
+    let tag = if count == 1 { 0 } else { take(bytes, 1)?[0] };
+    match tag {
+        0 => {
+            let colour = take(bytes, 3)?;
+            blocks.push((boundary, [colour[0], colour[1], colour[2]]));
+            Ok(())
+        }
+        1 => {
+            for quarter in QuadTree::quadrants(&boundary).iter() {
+                decode_block(*quarter, bytes, blocks)?;
+            }
+            Ok(())
+        }
+        _ => Err(DecodeError::Corrupt),
+    }
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], DecodeError> {
+    if bytes.len() < n {
+        return Err(DecodeError::Truncated);
+    }
+    let (taken, rest) = bytes.split_at(n);
+    *bytes = rest;
+    Ok(taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, DecodeError, Image};
+
+    #[test]
+    fn round_trip() {
+        // A sky over some grass, with noise in the grass.
+        let mut rng = crate::tests::get_rng();
+        let (width, height) = (20, 13);
+        let pixels = (0..width * height)
+            .map(|i| {
+                if i / width < 6 {
+                    [100, 150, 250]
+                } else {
+                    [30, 120 + (rng.next() % 10) as u8, 40]
+                }
+            })
+            .collect();
+        let image = Image {
+            width,
+            height,
+            pixels,
+        };
+
+        let exact = encode(&image, 0.0);
+        assert_eq!(decode(&exact), Ok(image.clone()));
+        assert!(exact.len() < 12 + image.pixels.len() * 3);
+
+        let lossy = encode(&image, 30.0);
+        assert!(lossy.len() < exact.len() / 2);
+        let decoded = decode(&lossy).unwrap();
+        assert_eq!(decoded.pixels[0], [100, 150, 250]);
+        let off = decoded
+            .pixels
+            .iter()
+            .zip(image.pixels.iter())
+            .map(|(a, b)| (a[1] as i32 - b[1] as i32).abs())
+            .max();
+        assert!(off.unwrap() < 10);
+
+        assert_eq!(
+            decode(&lossy[..lossy.len() - 1]),
+            Err(DecodeError::Truncated)
+        );
+        assert_eq!(decode(b"QTIX12345678"), Err(DecodeError::NotAnImage));
+        let mut extra = lossy.clone();
+        extra.push(0);
+        assert_eq!(decode(&extra), Err(DecodeError::Corrupt));
+    }
+
+    #[test]
+    fn forged_header() {
+        let header = |width: u32, height: u32| {
+            let mut bytes = b"QTIC".to_vec();
+            bytes.extend_from_slice(&width.to_le_bytes());
+            bytes.extend_from_slice(&height.to_le_bytes());
+            bytes
+        };
+        let mut split = header(70000, 70000);
+        split.extend_from_slice(&[1, 0, 0, 0]);
+        assert_eq!(decode(&split), Err(DecodeError::Truncated));
+
+        let mut flat = header(u32::MAX, u32::MAX);
+        flat.extend_from_slice(&[0, 1, 2, 3]);
+        assert_eq!(decode(&flat), Err(DecodeError::TooLarge));
+    }
+}
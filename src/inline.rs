@@ -0,0 +1,125 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// A quadtree whose capacity is known at compile time, so leaves keep their points in an array
+/// right inside them instead of in a `Vec` on the heap, and nodes don't need to remember the
+/// capacity. Unused slots hold `T::default()`.
+///
+/// Every leaf takes up room for `CAP` points whether it holds them or not, so this pays off for
+/// small capacities. Splitting copies the points out of the array into four boxed children.
+#[derive(Debug, Clone)]
+pub enum InlineQuadTree<T: PartialOrd + Copy + Midpoint + Default, const CAP: usize> {
+    Leaf(Boundary<T>, usize, [Point<T>; CAP]),
+    Node(Boundary<T>, [Box<InlineQuadTree<T, CAP>>; 4]),
+}
+
+impl<T, const CAP: usize> InlineQuadTree<T, CAP>
+where
+    T: PartialOrd + Copy + Midpoint + Default,
+{
+    /// Panics if `CAP` is 0.
+    pub fn new(boundary: Boundary<T>) -> Self {
+        assert!(CAP > 0, "capacity must be at least 1");
+        InlineQuadTree::Leaf(boundary, 0, [(T::default(), T::default()); CAP])
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> bool {
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+
+        if let InlineQuadTree::Leaf(_, len, points) = self {
+            if points[..*len].contains(&point) {
+                return true;
+            }
+            if *len < CAP {
+                points[*len] = point;
+                *len += 1;
+                return true;
+            }
+        }
+
+        if let InlineQuadTree::Leaf(boundary, len, points) = self {
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let leaf = |b| Box::new(InlineQuadTree::new(b));
+            let mut children = [leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)];
+            for point in &points[..*len] {
+                children[QuadTree::quadrant_of(boundary, point)].insert(*point);
+            }
+            *self = InlineQuadTree::Node(*boundary, children);
+        }
+
+        match self {
+            InlineQuadTree::Leaf(_, _, _) => panic!("We should never be a leaf at this point"),
+            InlineQuadTree::Node(boundary, children) => {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(point)
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            InlineQuadTree::Leaf(_, len, _) => *len,
+            InlineQuadTree::Node(_, children) => children.iter().map(|c| c.size()).sum(),
+        }
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            InlineQuadTree::Leaf(_, len, points) => found.extend(
+                points[..*len]
+                    .iter()
+                    .filter(|p| QuadTree::contains(boundary, p)),
+            ),
+            InlineQuadTree::Node(_, children) => {
+                for child in children {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            InlineQuadTree::Leaf(boundary, _, _) => *boundary,
+            InlineQuadTree::Node(boundary, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InlineQuadTree;
+    use crate::QuadTree;
+
+    #[test]
+    fn same_as_quadtree() {
+        let mut qt = InlineQuadTree::<i32, 4>::new((0, 100, 0, 100));
+        let mut reference = QuadTree::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            for j in 0..10 {
+                qt.insert((i, j * 10));
+                reference.insert((i, j * 10));
+            }
+        }
+        qt.insert((5, 5));
+        qt.insert((5, 5));
+        reference.insert((5, 5));
+        assert!(!qt.insert((100, 5)));
+        assert_eq!(qt.size(), reference.size());
+
+        let mut a = qt.search(&(10, 40, 20, 60));
+        let mut b = reference.search(&(10, 40, 20, 60));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{Boundary, CellInfo, Distance, Midpoint, Point, QuadTree};
+use egui::{Color32, Rect, Response, Sense, Stroke, StrokeKind, Ui, Vec2};
+use std::fmt::Debug;
+
+/// An egui widget that draws a tree. Drag to pan, scroll to zoom, double click to go back to
+/// where it started. Hovering a leaf shows its boundary, depth and number of points, and the
+/// leaves the last query had to look into are highlighted. Needs the `egui` feature.
+///
+/// Keep it around between frames, it's what remembers the pan and zoom.
+#[derive(Debug, Clone)]
+pub struct Inspector<T> {
+    pan: Vec2,
+    zoom: f32,
+    last_query: Option<Boundary<T>>,
+}
+
+impl<T> Default for Inspector<T> {
+    fn default() -> Self {
+        Inspector {
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+            last_query: None,
+        }
+    }
+}
+
+impl<T> Inspector<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance + Debug,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Highlights what a search for `boundary` touches, or nothing for `None`.
+    pub fn set_last_query(&mut self, boundary: Option<Boundary<T>>) {
+        self.last_query = boundary;
+    }
+
+    /// Draws `tree` into all the space `ui` has left.
+    pub fn show(&mut self, ui: &mut Ui, tree: &QuadTree<T>) -> Response {
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), Sense::click_and_drag());
+        if response.dragged() {
+            self.pan += response.drag_delta();
+        }
+        if response.double_clicked() {
+            *self = Inspector {
+                last_query: self.last_query,
+                ..Inspector::default()
+            };
+        }
+        if let Some(pointer) = response.hover_pos() {
+            let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+            if scroll != 0.0 {
+                // Zoom around the pointer, so what's under it stays there.
+                let factor = (scroll * 0.005).exp();
+                let anchor = pointer - rect.min;
+                self.pan = anchor - (anchor - self.pan) * factor;
+                self.zoom *= factor;
+            }
+        }
+
+        let (x1, x2, y1, y2) = *tree.get_boundary();
+        let scale = Vec2::new(
+            rect.width() * self.zoom / x1.distance(x2) as f32,
+            rect.height() * self.zoom / y1.distance(y2) as f32,
+        );
+        let (pan, origin) = (self.pan, rect.min);
+        let to_screen = |(x, y): &Point<T>| {
+            origin + pan + Vec2::new(x.distance(x1) as f32, y.distance(y1) as f32) * scale
+        };
+        let cell_rect = |(bx1, bx2, by1, by2): &Boundary<T>| {
+            Rect::from_min_max(to_screen(&(*bx1, *by1)), to_screen(&(*bx2, *by2)))
+        };
+
+        let painter = ui.painter_at(rect);
+        let highlight = Color32::from_rgba_unmultiplied(255, 200, 0, 60);
+        let outline = Stroke::new(1.0_f32, Color32::GRAY);
+        for (boundary, points) in tree.leaves() {
+            let cell = cell_rect(&boundary);
+            if !cell.intersects(rect) {
+                continue;
+            }
+            if let Some(query) = &self.last_query {
+                if QuadTree::intersects(&boundary, query) {
+                    painter.rect_filled(cell, 0.0, highlight);
+                }
+            }
+            painter.rect_stroke(cell, 0.0, outline, StrokeKind::Inside);
+            for point in points {
+                painter.circle_filled(to_screen(point), 2.0, Color32::LIGHT_RED);
+            }
+        }
+        if let Some(query) = &self.last_query {
+            let stroke = Stroke::new(2.0_f32, Color32::YELLOW);
+            painter.rect_stroke(cell_rect(query), 0.0, stroke, StrokeKind::Middle);
+        }
+
+        let hovered = response.hover_pos().and_then(|pointer| {
+            let mut found: Option<CellInfo<T>> = None;
+            tree.visit_cells(&|b| cell_rect(b).contains(pointer), &mut |c| {
+                found = Some(c)
+            });
+            found
+        });
+        match hovered {
+            Some(cell) => {
+                painter.rect_stroke(
+                    cell_rect(&cell.boundary),
+                    0.0,
+                    Stroke::new(2.0_f32, Color32::WHITE),
+                    StrokeKind::Inside,
+                );
+                response.on_hover_text(format!(
+                    "boundary: {:?}\ndepth: {}\npoints: {}",
+                    cell.boundary, cell.depth, cell.size
+                ))
+            }
+            None => response,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Inspector;
+    use crate::QuadTree as Q;
+    use egui::{Context, RawInput, Rect, Shape, Vec2};
+
+    #[test]
+    fn show() {
+        let mut qt = Q::with_node_capacity(2, (0, 100, 0, 100));
+        for p in [(10, 10), (20, 20), (30, 30), (80, 80)] {
+            qt.insert(p);
+        }
+        let mut inspector = Inspector::new();
+        inspector.set_last_query(Some((0, 10, 0, 10)));
+
+        let ctx = Context::default();
+        let input = || RawInput {
+            screen_rect: Some(Rect::from_min_size(Default::default(), Vec2::splat(400.0))),
+            ..Default::default()
+        };
+        let mut shapes = 0;
+        let output = ctx.run(input(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                inspector.show(ui, &qt);
+            });
+        });
+        for clipped in &output.shapes {
+            if let Shape::Rect(_) | Shape::Circle(_) = clipped.shape {
+                shapes += 1;
+            }
+        }
+        // 7 leaves, 4 points and the query, at least.
+        assert!(shapes >= 12, "{}", shapes);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{Boundary, Midpoint, Observer, Point, QuadTree};
+
+/// One change to a tree, as recorded by a `Journal`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit<T> {
+    Inserted(Point<T>),
+    Removed(Point<T>),
+}
+
+/// Records inserts and removes so they can be undone and redone. It's an `Observer`, so it's
+/// turned on by passing it to `insert_observed` and `remove_observed`.
+///
+/// Only changes that actually happened are recorded, so inserting a duplicate doesn't add
+/// anything to undo. Changes made to the tree without the journal aren't known to it, and undoing
+/// past them can give odd results.
+#[derive(Debug, Clone)]
+pub struct Journal<T> {
+    done: Vec<Edit<T>>,
+    undone: Vec<Edit<T>>,
+}
+
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Journal {
+            done: vec![],
+            undone: vec![],
+        }
+    }
+}
+
+impl<T> Journal<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reverts the latest change to `tree`, and returns it. `None` if there's nothing to undo.
+    pub fn undo(&mut self, tree: &mut QuadTree<T>) -> Option<Edit<T>> {
+        let edit = self.done.pop()?;
+        match edit {
+            Edit::Inserted(point) => tree.remove(&point),
+            Edit::Removed(point) => tree.insert(point),
+        };
+        self.undone.push(edit);
+        Some(edit)
+    }
+
+    /// Makes the latest undone change again, and returns it. `None` if there's nothing to redo.
+    pub fn redo(&mut self, tree: &mut QuadTree<T>) -> Option<Edit<T>> {
+        let edit = self.undone.pop()?;
+        match edit {
+            Edit::Inserted(point) => tree.insert(point),
+            Edit::Removed(point) => tree.remove(&point),
+        };
+        self.done.push(edit);
+        Some(edit)
+    }
+
+    /// Forgets everything, e.g. after saving.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+}
+
+impl<T: Copy> Observer<T> for Journal<T> {
+    fn inserted(&mut self, point: &Point<T>, _: &Boundary<T>, _: usize) {
+        self.done.push(Edit::Inserted(*point));
+        self.undone.clear();
+    }
+
+    fn removed(&mut self, point: &Point<T>, _: &Boundary<T>, _: usize) {
+        self.done.push(Edit::Removed(*point));
+        self.undone.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edit, Journal};
+    use crate::QuadTree;
+
+    #[test]
+    fn undo_redo() {
+        let mut qt = QuadTree::with_node_capacity(2, (0, 10, 0, 10));
+        let mut journal = Journal::new();
+        for p in [(1, 1), (2, 2), (2, 2), (8, 8)] {
+            qt.insert_observed(p, &mut journal);
+        }
+        qt.remove_observed(&(1, 1), &mut journal);
+        assert_eq!(qt.size(), 2);
+
+        assert_eq!(journal.undo(&mut qt), Some(Edit::Removed((1, 1))));
+        assert_eq!(journal.undo(&mut qt), Some(Edit::Inserted((8, 8))));
+        let mut found = qt.search(&(0, 10, 0, 10));
+        found.sort();
+        assert_eq!(found, vec![(1, 1), (2, 2)]);
+
+        assert_eq!(journal.redo(&mut qt), Some(Edit::Inserted((8, 8))));
+        assert_eq!(qt.size(), 3);
+
+        // A new change throws away what could have been redone.
+        qt.insert_observed((5, 5), &mut journal);
+        assert!(!journal.can_redo());
+        assert_eq!(journal.redo(&mut qt), None);
+
+        while journal.undo(&mut qt).is_some() {}
+        assert_eq!(qt.size(), 0);
+    }
+}
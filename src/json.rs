@@ -0,0 +1,86 @@
+use crate::{Boundary, Midpoint, QuadTree};
+use std::fmt::{Display, Write};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Display,
+{
+    /// Dumps the shape of the tree as JSON, for looking at it in other tools. Every node and leaf
+    /// is an object with its `boundary` (`[x1, x2, y1, y2]`), `depth` and `size` (the number of
+    /// points below it). Nodes have their four `children`, and leaves their `points` as `[x, y]`
+    /// pairs if `include_points` is set.
+    ///
+    /// Coordinates are written with `Display`, so infinite or NaN floats will not be valid JSON.
+    pub fn to_debug_json(&self, include_points: bool) -> String {
+        let mut out = String::new();
+        self.write_debug_json(&mut out, 0, include_points);
+        out
+    }
+
+    fn write_debug_json(&self, out: &mut String, depth: usize, include_points: bool) {
+        let (x1, x2, y1, y2): Boundary<T> = *self.get_boundary();
+        // Writing to a String can't fail.
+        let _ = write!(
+            out,
+            r#"{{"boundary":[{},{},{},{}],"depth":{},"size":{}"#,
+            x1,
+            x2,
+            y1,
+            y2,
+            depth,
+            self.size()
+        );
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                if include_points {
+                    out.push_str(r#","points":["#);
+                    for (i, (x, y)) in points.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        let _ = write!(out, "[{},{}]", x, y);
+                    }
+                    out.push(']');
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                out.push_str(r#","children":["#);
+                for (i, child) in children.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    child.write_debug_json(out, depth + 1, include_points);
+                }
+                out.push(']');
+            }
+        }
+        out.push('}');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn to_debug_json() {
+        let mut qt = Q::with_node_capacity(1, (0, 4, 0, 4));
+        qt.insert((1, 1));
+        assert_eq!(
+            qt.to_debug_json(true),
+            r#"{"boundary":[0,4,0,4],"depth":0,"size":1,"points":[[1,1]]}"#
+        );
+
+        qt.insert((3, 3));
+        assert_eq!(
+            qt.to_debug_json(false),
+            concat!(
+                r#"{"boundary":[0,4,0,4],"depth":0,"size":2,"children":["#,
+                r#"{"boundary":[0,2,0,2],"depth":1,"size":1},"#,
+                r#"{"boundary":[0,2,2,4],"depth":1,"size":0},"#,
+                r#"{"boundary":[2,4,0,2],"depth":1,"size":0},"#,
+                r#"{"boundary":[2,4,2,4],"depth":1,"size":1}]}"#
+            )
+        );
+    }
+}
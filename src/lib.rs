@@ -1,133 +1,808 @@
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+#[cfg(feature = "allocator_api")]
+mod alloc;
+mod approx;
+mod best_first;
+mod budget;
+mod builder;
+#[cfg(feature = "bumpalo")]
+mod bump;
+mod cells;
+mod compressed;
+mod contour;
+mod cull;
+#[cfg(feature = "rust_decimal")]
+mod decimal;
+mod density;
+mod diff;
+#[cfg(test)]
+mod differential;
+mod digest;
+mod dynamic;
+mod explain;
+#[cfg(feature = "fixed")]
+mod fixed_point;
+mod flat;
+#[cfg(feature = "half")]
+mod float16;
+pub mod format;
+mod frozen;
+pub mod geom;
+mod health;
+mod hilbert;
+pub mod image_compress;
+mod inline;
+#[cfg(feature = "egui")]
+mod inspector;
+mod journal;
+mod json;
+mod lod;
+mod map;
+pub mod mesh;
+pub mod naive;
+mod nav;
+mod nearest;
+mod observer;
+mod occupancy;
+#[cfg(feature = "ordered-float")]
+mod ordered;
+mod paged;
+#[cfg(feature = "tokio")]
+mod paged_async;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "plotters")]
+mod plot;
+mod pow2;
+mod progressive;
+mod quadkey;
+#[cfg(feature = "image")]
+mod raster;
+mod robust;
+#[cfg(feature = "rstar")]
+mod rtree;
+mod sample;
+mod select;
+mod shard;
+#[cfg(feature = "arc-swap")]
+mod shared;
+mod stream;
+mod temporal;
+mod territory;
+#[cfg(feature = "test-utils")]
+pub mod testing;
+mod thin;
+mod wal;
+mod watch;
+mod weighted;
+
+#[cfg(feature = "allocator_api")]
+pub use alloc::QuadTreeIn;
+pub use approx::CountEstimate;
+pub use budget::{Budget, PartialSearch};
+pub use builder::{BuildError, QuadTreeBuilder};
+#[cfg(feature = "bumpalo")]
+pub use bump::BumpQuadTree;
+pub use cells::CellInfo;
+pub use compressed::CompressedQuadTree;
+pub use density::{Epanechnikov, Gaussian, Kernel};
+pub use diff::TreeDiff;
+pub use dynamic::DynamicIndex;
+pub use explain::SearchStats;
+pub use flat::FlatBuffers;
+pub use format::{FixedBytes, FormatError, MappedQuadTree};
+pub use frozen::FrozenQuadTree;
+pub use geom::BoundaryExt;
+pub use health::Health;
+pub use hilbert::hilbert_key;
+pub use inline::InlineQuadTree;
+#[cfg(feature = "egui")]
+pub use inspector::Inspector;
+pub use journal::{Edit, Journal};
+pub use map::{Entry, ItemId, OccupiedEntry, QuadMap, VacantEntry};
+pub use nav::NavGraph;
+pub use observer::Observer;
+pub use occupancy::OccupancyMap;
+pub use paged::PagedQuadTree;
+#[cfg(feature = "tokio")]
+pub use paged_async::AsyncPagedQuadTree;
+#[cfg(feature = "rayon")]
+pub use parallel::{ParLeaves, ParPoints};
+pub use pow2::Pow2QuadTree;
+pub use progressive::Refinement;
+#[cfg(feature = "image")]
+pub use raster::RegionQuadTree;
+pub use robust::Robust;
+pub use select::Axis;
+#[cfg(feature = "arc-swap")]
+pub use shared::SharedQuadTree;
+pub use stream::write_streaming;
+pub use temporal::TemporalQuadTree;
+pub use wal::DurableQuadTree;
+pub use watch::{WatchId, Watcher};
+pub use weighted::WeightedQuadTree;
+
+/// With the `tracing` feature, inserts that go this deep into the tree log a warning. It's a good
+/// sign that a lot of points are crammed into a tiny spot.
+#[cfg(feature = "tracing")]
+const DEEP_INSERT: usize = 24;
+
+/// With the `tracing` feature, searches finding at least this many points are logged.
+#[cfg(feature = "tracing")]
+const LARGE_SEARCH: usize = 10_000;
+
 pub type Point<T> = (T, T);
 pub type Boundary<T> = (T, T, T, T);
 
-#[derive(Debug)]
-pub enum QuadTree<T: PartialOrd + Copy + Midpoint> {
-    Leaf(usize, Boundary<T>, Vec<Point<T>>),
-    Node(usize, Boundary<T>, [Box<QuadTree<T>>; 4]),
+/// Settings shared by every node in a tree.
+#[derive(Debug, Clone, Copy)]
+pub struct Config<T> {
+    /// How many points a leaf holds before it is subdivided.
+    pub capacity: usize,
+    pub duplicates: Duplicates,
+    /// Treat points closer than this to an existing point as duplicates.
+    pub tolerance: Option<Tolerance<T>>,
+    /// Snap points to a grid before inserting them.
+    pub grid: Option<Grid<T>>,
+    /// Leaves this deep are never subdivided, however full they get. Instead their points are
+    /// kept sorted, so looking for duplicates stays a binary search even with lots of points
+    /// crammed into one tiny spot.
+    pub max_depth: Option<usize>,
+}
+
+impl<T> Default for Config<T> {
+    fn default() -> Self {
+        Config {
+            capacity: 64,
+            duplicates: Duplicates::Linear,
+            tolerance: None,
+            grid: None,
+            max_depth: None,
+        }
+    }
+}
+
+/// A grid that inserted points get snapped to. Noisy points in the same grid cell become the same
+/// point, which also puts a bound on how deep the tree can get.
+#[derive(Debug, Clone, Copy)]
+pub struct Grid<T> {
+    resolution: T,
+    snap: fn(&T, T) -> T,
+}
+
+impl<T: Snap + PartialOrd + Default> Grid<T> {
+    /// Panics if `resolution` isn't above 0, as there'd be no cells to snap to.
+    pub fn new(resolution: T) -> Self {
+        assert!(resolution > T::default(), "grid resolution must be above 0");
+        Grid {
+            resolution,
+            snap: T::snap,
+        }
+    }
+
+    /// Same as `new`, but fails instead of panicking.
+    pub fn try_new(resolution: T) -> Result<Self, BuildError> {
+        if resolution > T::default() {
+            Ok(Grid::new(resolution))
+        } else {
+            Err(BuildError::BadResolution)
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn snap_point(&self, (x, y): Point<T>) -> Point<T> {
+        (
+            (self.snap)(&x, self.resolution.clone()),
+            (self.snap)(&y, self.resolution.clone()),
+        )
+    }
+}
+
+/// A distance within which two points count as the same point. Without it, floats that are only
+/// a rounding error apart are different points, and a bunch of them in one spot will make the tree
+/// subdivide very deep.
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance<T> {
+    epsilon: f64,
+    distance: fn(&T, T) -> f64,
+}
+
+impl<T: Distance> Tolerance<T> {
+    pub fn new(epsilon: f64) -> Self {
+        Tolerance {
+            epsilon,
+            distance: T::distance,
+        }
+    }
+}
+
+/// How a leaf figures out whether it already holds a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Scan every point in the leaf. Cheap for small capacities, O(capacity) for large ones.
+    Linear,
+    /// Keep the points of each leaf sorted and binary search them: O(log capacity) lookups.
+    Sorted,
+    /// Don't look at all. The caller promises never to insert the same point twice.
+    Trust,
+}
+
+/// A node also keeps the number of points below it, so `size` doesn't have to count them.
+///
+/// Coordinates only have to be `Clone`, so arbitrary precision numbers work too. Points are moved
+/// into the tree, and only cloned when a search hands them back or a node splits its boundary.
+/// Some of the extras (nearest neighbours, the binary format, ...) still need `Copy`.
+#[derive(Debug, Clone)]
+pub enum QuadTree<T: PartialOrd + Clone + Midpoint> {
+    Leaf(Config<T>, Boundary<T>, Vec<Point<T>>),
+    Node(Config<T>, Boundary<T>, usize, Box<[QuadTree<T>; 4]>),
 }
 
-impl<T: PartialOrd + Copy + Midpoint> QuadTree<T>
+impl<T: PartialOrd + Clone + Midpoint> QuadTree<T>
 where
-    T: PartialOrd + Copy + Midpoint,
+    T: PartialOrd + Clone + Midpoint,
 {
+    /// `boundary` needs `x1 < x2` and `y1 < y2`, otherwise nothing could ever be inserted. That's
+    /// only checked in debug builds, use `try_new` or `QuadTreeBuilder` to always have it checked.
     pub fn new(boundary: Boundary<T>) -> Self {
         Self::with_node_capacity(64, boundary)
     }
 
     pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
-        QuadTree::Leaf(capacity, boundary, vec![])
+        Self::with_config(
+            Config {
+                capacity,
+                ..Config::default()
+            },
+            boundary,
+        )
+    }
+
+    /// Panics if the capacity is 0, as every insert would subdivide forever. `try_with_config`
+    /// returns an error instead.
+    pub fn with_config(config: Config<T>, boundary: Boundary<T>) -> Self {
+        debug_assert!(
+            !builder::degenerate(&boundary),
+            "boundary must have x1 < x2 and y1 < y2"
+        );
+        assert!(config.capacity > 0, "capacity must be at least 1");
+        QuadTree::Leaf(config, boundary, vec![])
     }
 
     pub fn insert(&mut self, point: Point<T>) -> bool {
-        if !Self::contains(&self.get_boundary(), &point) {
+        self.insert_observed(point, &mut ())
+    }
+
+    /// Same as `insert`, but tells `observer` about the insert and any subdividing it causes.
+    pub fn insert_observed<O: Observer<T>>(&mut self, point: Point<T>, observer: &mut O) -> bool {
+        let point = self.snap(point);
+        if !Self::contains(self.get_boundary(), &point) {
             return false;
         }
+        if let Some(tolerance) = &self.get_config().tolerance {
+            if self.has_point_near(&point, tolerance) {
+                return true;
+            }
+        }
+        self.insert_contained(point, true, 0, observer);
+        true
+    }
+
+    /// Inserts without checking that the point is inside the tree and without looking for
+    /// duplicates. Only use this when you know both already hold, e.g. when bulk loading
+    /// deduplicated data. A point outside the boundary panics, and more than `capacity` copies of
+    /// the same point will subdivide forever.
+    pub fn insert_unchecked(&mut self, point: Point<T>) {
+        let point = self.snap(point);
+        debug_assert!(Self::contains(self.get_boundary(), &point));
+        self.insert_contained(point, false, 0, &mut ());
+    }
+
+    fn insert_contained<O: Observer<T>>(
+        &mut self,
+        point: Point<T>,
+        check_duplicates: bool,
+        depth: usize,
+        observer: &mut O,
+    ) -> bool {
+        if let QuadTree::Leaf(config, boundary, points) = self {
+            let at_max_depth = config.max_depth.is_some_and(|max| depth >= max);
+            if points.len() < config.capacity || at_max_depth {
+                let at = if points.len() < config.capacity {
+                    Self::push_point(config, points, point, check_duplicates)
+                } else {
+                    Self::push_overflow(config, points, point, check_duplicates)
+                };
+                if let Some(at) = at {
+                    observer.inserted(&points[at], boundary, depth);
+                }
+                #[cfg(feature = "tracing")]
+                if depth >= DEEP_INSERT {
+                    tracing::warn!(depth, "deep insert");
+                }
+                return at.is_some();
+            }
+            // Splitting won't get rid of a duplicate, and with a capacity of 1 it would go on
+            // splitting forever.
+            if check_duplicates && Self::holds(config, points, &point) {
+                return false;
+            }
+        }
+
+        self.subdivide_observed(depth, observer);
 
-        if let QuadTree::Leaf(capacity, _, points) = self {
-            if points.len() < *capacity {
-                if !points.iter().any(|p| *p == point) {
-                    points.push(point);
+        match self {
+            QuadTree::Leaf(_, _, _) => panic!("We should never be a leaf at this point"),
+            QuadTree::Node(_, _, size, children) => {
+                let pushed = Self::child_for(children, &point).insert_contained(
+                    point,
+                    check_duplicates,
+                    depth + 1,
+                    observer,
+                );
+                if pushed {
+                    *size += 1;
                 }
-                return true;
+                pushed
             }
         }
+    }
+
+    /// Turns a leaf into a node with four leaves, moving its points down. Does nothing to a node.
+    /// `depth` is how deep the leaf is, so the points moved down stop at the max depth.
+    pub(crate) fn subdivide(&mut self, depth: usize) {
+        self.subdivide_observed(depth, &mut ());
+    }
+
+    fn subdivide_observed<O: Observer<T>>(&mut self, depth: usize, observer: &mut O) {
+        if let QuadTree::Leaf(config, boundary, points) = self {
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("subdivide", depth, points = points.len()).entered();
+
+            let [top_lef, bot_lef, top_rig, bot_rig] = Self::quadrants(boundary);
+            let mut children = Box::new([
+                QuadTree::Leaf(config.clone(), top_lef, vec![]),
+                QuadTree::Leaf(config.clone(), bot_lef, vec![]),
+                QuadTree::Leaf(config.clone(), top_rig, vec![]),
+                QuadTree::Leaf(config.clone(), bot_rig, vec![]),
+            ]);
 
-        if let QuadTree::Leaf(capacity, boundary, points) = self {
-            let (x1, x2, y1, y2) = boundary;
-            let mid_x = x1.midpoint(*x2);
-            let mid_y = y1.midpoint(*y2);
+            // These are already known to be unique, so there's no need to look for duplicates
+            // again while moving them down. They aren't new either, so the observer doesn't hear
+            // about them.
+            let size = points.len();
+            for point in points.drain(..) {
+                Self::child_for(&mut children, &point).insert_contained(
+                    point,
+                    false,
+                    depth + 1,
+                    &mut (),
+                );
+            }
 
-            let mut top_lef = QuadTree::Leaf(*capacity, (*x1, mid_x, *y1, mid_y), vec![]);
-            let mut bot_lef = QuadTree::Leaf(*capacity, (*x1, mid_x, mid_y, *y2), vec![]);
-            let mut top_rig = QuadTree::Leaf(*capacity, (mid_x, *x2, *y1, mid_y), vec![]);
-            let mut bot_rig = QuadTree::Leaf(*capacity, (mid_x, *x2, mid_y, *y2), vec![]);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(depth, "subdivided leaf");
 
-            for point in points {
-                if top_lef.insert(*point) {
-                    continue;
+            observer.subdivided(boundary, depth);
+            *self = QuadTree::Node(config.clone(), boundary.clone(), size, children);
+        }
+    }
+
+    /// Returns where the point went, if it was pushed.
+    fn push_point(
+        config: &Config<T>,
+        points: &mut Vec<Point<T>>,
+        point: Point<T>,
+        check: bool,
+    ) -> Option<usize> {
+        match config.duplicates {
+            Duplicates::Linear => {
+                if check && points.contains(&point) {
+                    return None;
                 }
-                if bot_lef.insert(*point) {
-                    continue;
+                points.push(point);
+            }
+            Duplicates::Sorted => {
+                let found = points.binary_search_by(|p| {
+                    p.partial_cmp(&point).unwrap_or(std::cmp::Ordering::Equal)
+                });
+                match found {
+                    Ok(_) if check => return None,
+                    Ok(at) | Err(at) => {
+                        points.insert(at, point);
+                        return Some(at);
+                    }
                 }
-                if top_rig.insert(*point) {
-                    continue;
+            }
+            Duplicates::Trust => points.push(point),
+        }
+        Some(points.len() - 1)
+    }
+
+    /// Whether `points` already has `point`, looked for the way `config` says.
+    fn holds(config: &Config<T>, points: &[Point<T>], point: &Point<T>) -> bool {
+        match config.duplicates {
+            Duplicates::Linear => points.contains(point),
+            Duplicates::Sorted => points
+                .binary_search_by(|p| p.partial_cmp(point).unwrap_or(std::cmp::Ordering::Equal))
+                .is_ok(),
+            Duplicates::Trust => false,
+        }
+    }
+
+    /// Pushes onto a full leaf at the maximum depth. Its points are sorted the moment it fills up
+    /// (unless they already are), and kept that way from then on.
+    fn push_overflow(
+        config: &Config<T>,
+        points: &mut Vec<Point<T>>,
+        point: Point<T>,
+        check: bool,
+    ) -> Option<usize> {
+        let compare =
+            |a: &Point<T>, b: &Point<T>| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal);
+        if points.len() == config.capacity && config.duplicates != Duplicates::Sorted {
+            points.sort_by(compare);
+        }
+        match points.binary_search_by(|p| compare(p, &point)) {
+            Ok(_) if check && config.duplicates != Duplicates::Trust => None,
+            Ok(at) | Err(at) => {
+                points.insert(at, point);
+                Some(at)
+            }
+        }
+    }
+
+    /// Removes a point. Returns whether it was there. Nodes whose children end up holding no more
+    /// than `capacity` points between them are merged back into a leaf.
+    pub fn remove(&mut self, point: &Point<T>) -> bool {
+        self.remove_observed(point, &mut ())
+    }
+
+    /// Same as `remove`, but tells `observer` about the removal and any merging it causes.
+    pub fn remove_observed<O: Observer<T>>(&mut self, point: &Point<T>, observer: &mut O) -> bool {
+        let point = self.snap(point.clone());
+        if !Self::contains(self.get_boundary(), &point) {
+            return false;
+        }
+        self.remove_contained(&point, 0, observer)
+    }
+
+    fn remove_contained<O: Observer<T>>(
+        &mut self,
+        point: &Point<T>,
+        depth: usize,
+        observer: &mut O,
+    ) -> bool {
+        let removed = match self {
+            QuadTree::Leaf(_, boundary, points) => match points.iter().position(|p| p == point) {
+                Some(at) => {
+                    points.remove(at);
+                    observer.removed(point, boundary, depth);
+                    true
                 }
-                if bot_rig.insert(*point) {
-                    continue;
+                None => false,
+            },
+            QuadTree::Node(_, _, size, children) => {
+                let removed =
+                    Self::child_for(children, point).remove_contained(point, depth + 1, observer);
+                if removed {
+                    *size -= 1;
                 }
-                panic!("Should never reach here");
+                removed
             }
-
-            let node = QuadTree::Node(
-                *capacity,
-                *boundary,
-                [
-                    Box::new(top_lef),
-                    Box::new(bot_lef),
-                    Box::new(top_rig),
-                    Box::new(bot_rig),
-                ],
-            );
-            *self = node;
+        };
+        if removed {
+            self.merge_if_small(depth, observer);
         }
+        removed
+    }
 
-        match self {
-            QuadTree::Leaf(_, _, _) => panic!("We should never be a leaf at this point"),
-            QuadTree::Node(_, _, children) => {
-                for child in children {
-                    if child.insert(point) {
-                        return true;
-                    }
+    fn merge_if_small<O: Observer<T>>(&mut self, depth: usize, observer: &mut O) {
+        if let QuadTree::Node(config, boundary, size, children) = self {
+            let all_leaves = children
+                .iter()
+                .all(|child| matches!(child, QuadTree::Leaf(_, _, _)));
+            if !all_leaves || *size > config.capacity {
+                return;
+            }
+            let mut points = vec![];
+            for child in children.iter_mut() {
+                if let QuadTree::Leaf(_, _, child_points) = child {
+                    points.append(child_points);
                 }
-                panic!("Should not get here!");
             }
+            if config.duplicates == Duplicates::Sorted {
+                points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            }
+            observer.merged(boundary, depth);
+            *self = QuadTree::Leaf(config.clone(), boundary.clone(), points);
         }
     }
 
+    fn snap(&self, point: Point<T>) -> Point<T> {
+        match &self.get_config().grid {
+            Some(grid) => grid.snap_point(point),
+            None => point,
+        }
+    }
+
+    /// Looks through every node within `tolerance` of `point`, since a near duplicate can sit on
+    /// the other side of a seam.
+    fn has_point_near(&self, point: &Point<T>, tolerance: &Tolerance<T>) -> bool {
+        let Tolerance { epsilon, distance } = tolerance;
+        let epsilon_sq = epsilon * epsilon;
+        let boundary = self.get_boundary();
+        if nearest::distance_to_boundary_sq_by(boundary, point, distance) > epsilon_sq {
+            return false;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => points
+                .iter()
+                .any(|p| nearest::distance_sq_by(p, point, distance) <= epsilon_sq),
+            QuadTree::Node(_, _, _, children) => children
+                .iter()
+                .any(|child| child.has_point_near(point, tolerance)),
+        }
+    }
+
+    fn child_for<'a>(children: &'a mut [QuadTree<T>; 4], point: &Point<T>) -> &'a mut Self {
+        // The top right child starts at the middle x, the bottom left one at the middle y.
+        let i = Self::quadrant_at(
+            point,
+            &children[2].get_boundary().0,
+            &children[1].get_boundary().2,
+        );
+        &mut children[i]
+    }
+
     pub fn size(&self) -> usize {
         match self {
             QuadTree::Leaf(_, _, points) => points.len(),
-            QuadTree::Node(_, _, [a, b, c, d]) => a.size() + b.size() + c.size() + d.size(),
+            QuadTree::Node(_, _, size, _) => *size,
         }
     }
 
     pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
-        if !Self::intersects(&self.get_boundary(), boundary) {
-            return vec![];
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("search").entered();
+
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+
+        #[cfg(feature = "tracing")]
+        if found.len() >= LARGE_SEARCH {
+            tracing::info!(points = found.len(), "large search");
+        }
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<Point<T>>) {
+        if !Self::intersects(self.get_boundary(), boundary) {
+            return;
         }
         match self {
-            QuadTree::Leaf(_, _, points) => points
-                .iter()
-                .copied()
-                .filter(|point| Self::contains(&boundary, point))
-                .collect(),
-            QuadTree::Node(_, _, children) => children
-                .iter()
-                .flat_map(|child| child.search(boundary))
-                .collect(),
+            QuadTree::Leaf(_, _, points) => found.extend(
+                points
+                    .iter()
+                    .filter(|point| Self::contains(boundary, point))
+                    .cloned(),
+            ),
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.search_into(boundary, found);
+                }
+            }
         }
     }
 
-    fn get_boundary(&self) -> Boundary<T> {
+    /// Answers several searches in one pass over the tree. Each node is only checked against the
+    /// queries that still intersect it, and the result at index `i` belongs to `boundaries[i]`.
+    pub fn search_many(&self, boundaries: &[Boundary<T>]) -> Vec<Vec<Point<T>>> {
+        let mut results = vec![vec![]; boundaries.len()];
+        let active: Vec<usize> = (0..boundaries.len()).collect();
+        self.search_many_into(boundaries, &active, &mut results);
+        results
+    }
+
+    fn search_many_into(
+        &self,
+        boundaries: &[Boundary<T>],
+        active: &[usize],
+        results: &mut [Vec<Point<T>>],
+    ) {
+        let own = self.get_boundary();
+        let active: Vec<usize> = active
+            .iter()
+            .copied()
+            .filter(|i| Self::intersects(own, &boundaries[*i]))
+            .collect();
+        if active.is_empty() {
+            return;
+        }
         match self {
-            QuadTree::Leaf(_, boundary, _) => *boundary,
-            QuadTree::Node(_, boundary, _) => *boundary,
+            QuadTree::Leaf(_, _, points) => {
+                for i in active {
+                    let boundary = &boundaries[i];
+                    results[i].extend(
+                        points
+                            .iter()
+                            .filter(|p| Self::contains(boundary, p))
+                            .cloned(),
+                    );
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.search_many_into(boundaries, &active, results);
+                }
+            }
         }
     }
 
-    pub fn contains((x1, x2, y1, y2): &Boundary<T>, (x, y): &Point<T>) -> bool {
-        *x1 <= *x && *x2 > *x && *y1 <= *y && *y2 > *y
+    /// Like `search`, but instead of collecting points it marks `id_of(point)` in `ids` for every
+    /// point found. Handy when points map to stable IDs and the consumer wants a bitset anyway.
+    pub fn search_ids<S, F>(&self, boundary: &Boundary<T>, id_of: F, ids: &mut S)
+    where
+        S: IdSet,
+        F: Fn(&Point<T>) -> usize,
+    {
+        self.search_ids_into(boundary, &id_of, ids);
     }
 
-    fn intersects(
-        (a_x1, a_x2, a_y1, a_y2): &Boundary<T>,
-        (b_x1, b_x2, b_y1, b_y2): &Boundary<T>,
-    ) -> bool {
-        a_x1 < b_x2 && a_x2 > b_x1 && a_y1 < b_y2 && a_y2 > b_y1
+    fn search_ids_into<S, F>(&self, boundary: &Boundary<T>, id_of: &F, ids: &mut S)
+    where
+        S: IdSet,
+        F: Fn(&Point<T>) -> usize,
+    {
+        if !Self::intersects(self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                for point in points.iter().filter(|p| Self::contains(boundary, p)) {
+                    ids.insert_id(id_of(point));
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.search_ids_into(boundary, id_of, ids);
+                }
+            }
+        }
+    }
+
+    fn get_config(&self) -> &Config<T> {
+        match self {
+            QuadTree::Leaf(config, _, _) => config,
+            QuadTree::Node(config, _, _, _) => config,
+        }
+    }
+
+    fn get_boundary(&self) -> &Boundary<T> {
+        match self {
+            QuadTree::Leaf(_, boundary, _) => boundary,
+            QuadTree::Node(_, boundary, _, _) => boundary,
+        }
+    }
+
+    /// The four boundaries a node splits into, in the same order as the children of a `Node`: top
+    /// left, bottom left, top right, bottom right.
+    pub fn quadrants((x1, x2, y1, y2): &Boundary<T>) -> [Boundary<T>; 4] {
+        let mid_x = x1.midpoint(x2.clone());
+        let mid_y = y1.midpoint(y2.clone());
+        [
+            (x1.clone(), mid_x.clone(), y1.clone(), mid_y.clone()),
+            (x1.clone(), mid_x.clone(), mid_y.clone(), y2.clone()),
+            (mid_x.clone(), x2.clone(), y1.clone(), mid_y.clone()),
+            (mid_x, x2.clone(), mid_y, y2.clone()),
+        ]
+    }
+
+    /// Which of `quadrants(boundary)` (and so which child of a node) `point` belongs in, for a
+    /// point inside `boundary`. It's decided by comparing against the middle of the boundary,
+    /// and a point right on the middle goes right or down, as boundaries hold their low edges
+    /// but not their high ones.
+    pub fn quadrant_of((x1, x2, y1, y2): &Boundary<T>, point: &Point<T>) -> usize {
+        let mid_x = x1.midpoint(x2.clone());
+        let mid_y = y1.midpoint(y2.clone());
+        Self::quadrant_at(point, &mid_x, &mid_y)
+    }
+
+    fn quadrant_at((x, y): &Point<T>, mid_x: &T, mid_y: &T) -> usize {
+        2 * (x >= mid_x) as usize + (y >= mid_y) as usize
+    }
+
+    /// Same as `geom::contains`.
+    pub fn contains(boundary: &Boundary<T>, point: &Point<T>) -> bool {
+        geom::contains(boundary, point)
+    }
+
+    fn intersects(a: &Boundary<T>, b: &Boundary<T>) -> bool {
+        geom::intersects(a, b)
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + NextUp,
+{
+    /// Builds a tree just big enough to hold all the points, for when the extent isn't known up
+    /// front. `None` if there are no points.
+    pub fn from_points_autofit<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point<T>>,
+    {
+        let points: Vec<_> = points.into_iter().collect();
+        let mut qt = QuadTree::new(Boundary::bounding(points.iter().copied())?);
+        for point in points {
+            qt.insert(point);
+        }
+        Some(qt)
+    }
+}
+
+/// The basic operations shared by `QuadTree` and `naive::Naive`, so code can be written once and
+/// run against either of them.
+pub trait SpatialIndex<T> {
+    fn insert(&mut self, point: Point<T>) -> bool;
+    fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>>;
+    fn size(&self) -> usize;
+}
+
+impl<T> SpatialIndex<T> for QuadTree<T>
+where
+    T: PartialOrd + Clone + Midpoint,
+{
+    fn insert(&mut self, point: Point<T>) -> bool {
+        QuadTree::insert(self, point)
+    }
+
+    fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        QuadTree::search(self, boundary)
+    }
+
+    fn size(&self) -> usize {
+        QuadTree::size(self)
+    }
+}
+
+/// Something `search_ids` can write IDs into.
+pub trait IdSet {
+    fn insert_id(&mut self, id: usize);
+}
+
+/// A plain bitset of 64 bit words. It grows to fit whatever ID it is given.
+impl IdSet for Vec<u64> {
+    fn insert_id(&mut self, id: usize) {
+        let word = id / 64;
+        if word >= self.len() {
+            self.resize(word + 1, 0);
+        }
+        self[word] |= 1 << (id % 64);
+    }
+}
+
+impl IdSet for Vec<bool> {
+    fn insert_id(&mut self, id: usize) {
+        if id >= self.len() {
+            self.resize(id + 1, false);
+        }
+        self[id] = true;
+    }
+}
+
+#[cfg(feature = "fixedbitset")]
+impl IdSet for fixedbitset::FixedBitSet {
+    fn insert_id(&mut self, id: usize) {
+        self.grow(id + 1);
+        self.insert(id);
     }
 }
 
+/// Halfway between two coordinates. For integers it's rounded towards zero, and it's worked out
+/// in a wider type so that it can't overflow, even for coordinates right at the ends of the type.
 pub trait Midpoint {
     fn midpoint(&self, a: Self) -> Self;
 }
@@ -146,37 +821,181 @@ impl Midpoint for f64 {
 
 impl Midpoint for i32 {
     fn midpoint(&self, a: i32) -> i32 {
-        (*self + a) / 2
+        ((*self as i64 + a as i64) / 2) as i32
     }
 }
 
 impl Midpoint for i64 {
     fn midpoint(&self, a: i64) -> i64 {
-        (*self + a) / 2
+        ((*self as i128 + a as i128) / 2) as i64
     }
 }
 
 impl Midpoint for u32 {
     fn midpoint(&self, a: u32) -> u32 {
-        (*self + a) / 2
+        ((*self as u64 + a as u64) / 2) as u32
     }
 }
 
 impl Midpoint for u64 {
     fn midpoint(&self, a: u64) -> u64 {
-        (*self + a) / 2
+        ((*self as u128 + a as u128) / 2) as u64
     }
 }
 
 impl Midpoint for usize {
     fn midpoint(&self, a: usize) -> usize {
-        (*self + a) / 2
+        ((*self as u128 + a as u128) / 2) as usize
+    }
+}
+
+/// Distance between two coordinates along one axis. This is what the nearest neighbour and radius
+/// queries are built on, so implement it for your own type if you need those.
+pub trait Distance {
+    fn distance(&self, a: Self) -> f64;
+}
+
+impl Distance for f32 {
+    fn distance(&self, a: f32) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+impl Distance for f64 {
+    fn distance(&self, a: f64) -> f64 {
+        (*self - a).abs()
+    }
+}
+
+impl Distance for i32 {
+    fn distance(&self, a: i32) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+impl Distance for i64 {
+    fn distance(&self, a: i64) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+impl Distance for u32 {
+    fn distance(&self, a: u32) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+impl Distance for u64 {
+    fn distance(&self, a: u64) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+impl Distance for usize {
+    fn distance(&self, a: usize) -> f64 {
+        (*self as f64 - a as f64).abs()
+    }
+}
+
+/// Rounds a coordinate down to a multiple of `resolution`. Used by `Grid`.
+pub trait Snap {
+    fn snap(&self, resolution: Self) -> Self;
+}
+
+impl Snap for f32 {
+    fn snap(&self, resolution: f32) -> f32 {
+        (*self / resolution).floor() * resolution
+    }
+}
+
+impl Snap for f64 {
+    fn snap(&self, resolution: f64) -> f64 {
+        (*self / resolution).floor() * resolution
+    }
+}
+
+impl Snap for i32 {
+    fn snap(&self, resolution: i32) -> i32 {
+        *self - self.rem_euclid(resolution)
+    }
+}
+
+impl Snap for i64 {
+    fn snap(&self, resolution: i64) -> i64 {
+        *self - self.rem_euclid(resolution)
+    }
+}
+
+impl Snap for u32 {
+    fn snap(&self, resolution: u32) -> u32 {
+        *self - *self % resolution
+    }
+}
+
+impl Snap for u64 {
+    fn snap(&self, resolution: u64) -> u64 {
+        *self - *self % resolution
+    }
+}
+
+impl Snap for usize {
+    fn snap(&self, resolution: usize) -> usize {
+        *self - *self % resolution
+    }
+}
+
+/// The smallest coordinate bigger than this one. Boundaries don't include their `x2` and `y2`, so
+/// this is what `BoundaryExt::bounding` uses to make sure the biggest points end up inside.
+pub trait NextUp {
+    fn next_up(&self) -> Self;
+}
+
+impl NextUp for f32 {
+    fn next_up(&self) -> f32 {
+        f32::next_up(*self)
+    }
+}
+
+impl NextUp for f64 {
+    fn next_up(&self) -> f64 {
+        f64::next_up(*self)
+    }
+}
+
+impl NextUp for i32 {
+    fn next_up(&self) -> i32 {
+        self.saturating_add(1)
+    }
+}
+
+impl NextUp for i64 {
+    fn next_up(&self) -> i64 {
+        self.saturating_add(1)
+    }
+}
+
+impl NextUp for u32 {
+    fn next_up(&self) -> u32 {
+        self.saturating_add(1)
+    }
+}
+
+impl NextUp for u64 {
+    fn next_up(&self) -> u64 {
+        self.saturating_add(1)
+    }
+}
+
+impl NextUp for usize {
+    fn next_up(&self) -> usize {
+        self.saturating_add(1)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::QuadTree as Q;
+    use super::{BuildError, Config, Duplicates, Grid, Midpoint, QuadTreeBuilder, Snap, Tolerance};
 
     #[test]
     fn types_work() {
@@ -193,21 +1012,120 @@ mod tests {
     fn contains() {
         let qt = Q::new((0, 10, 0, 10));
         let b = qt.get_boundary();
-        assert!(Q::contains(&b, &(0, 0)));
-        assert!(Q::contains(&b, &(1, 2)));
-        assert!(!Q::contains(&b, &(0, 10)));
-        assert!(!Q::contains(&b, &(10, 10)));
-        assert!(!Q::contains(&b, &(5, 11)));
+        assert!(Q::contains(b, &(0, 0)));
+        assert!(Q::contains(b, &(1, 2)));
+        assert!(!Q::contains(b, &(0, 10)));
+        assert!(!Q::contains(b, &(10, 10)));
+        assert!(!Q::contains(b, &(5, 11)));
     }
 
     #[test]
     fn intersects() {
         let qt = Q::new((5, 10, 5, 10));
         let b = qt.get_boundary();
-        assert!(Q::intersects(&b, &(6, 7, 6, 7)));
-        assert!(Q::intersects(&b, &(9, 11, 9, 11)));
-        assert!(!Q::intersects(&b, &(10, 11, 10, 11)));
-        assert!(!Q::intersects(&b, &(4, 5, 4, 5)));
+        assert!(Q::intersects(b, &(6, 7, 6, 7)));
+        assert!(Q::intersects(b, &(9, 11, 9, 11)));
+        assert!(!Q::intersects(b, &(10, 11, 10, 11)));
+        assert!(!Q::intersects(b, &(4, 5, 4, 5)));
+    }
+
+    #[test]
+    fn quadrant_of() {
+        let b = (0, 10, 0, 10);
+        assert_eq!(Q::quadrant_of(&b, &(0, 0)), 0);
+        assert_eq!(Q::quadrant_of(&b, &(4, 5)), 1);
+        assert_eq!(Q::quadrant_of(&b, &(5, 4)), 2);
+        assert_eq!(Q::quadrant_of(&b, &(5, 5)), 3);
+        let quadrants = Q::quadrants(&b);
+        for x in 0..10 {
+            for y in 0..10 {
+                let i = Q::quadrant_of(&b, &(x, y));
+                assert!(Q::contains(&quadrants[i], &(x, y)));
+            }
+        }
+
+        // Whatever order they come in, seam points end up in the same leaves.
+        let seam = [(5, 5), (5, 2), (2, 5), (5, 8), (8, 5)];
+        let mut a = Q::with_node_capacity(1, b);
+        let mut z = Q::with_node_capacity(1, b);
+        for p in seam.iter() {
+            a.insert(*p);
+        }
+        for p in seam.iter().rev() {
+            z.insert(*p);
+        }
+        for p in seam.iter() {
+            assert_eq!(a.locate(p), z.locate(p));
+        }
+    }
+
+    #[test]
+    fn midpoint_at_the_extremes() {
+        // Called through the trait, since the integer types have a `midpoint` of their own now.
+        assert_eq!(Midpoint::midpoint(&i64::MIN, i64::MAX), 0);
+        assert_eq!(Midpoint::midpoint(&i64::MAX, i64::MAX - 2), i64::MAX - 1);
+        assert_eq!(Midpoint::midpoint(&-3i64, 0), -1);
+        assert_eq!(Midpoint::midpoint(&u64::MAX, u64::MAX - 2), u64::MAX - 1);
+        assert_eq!(Midpoint::midpoint(&i32::MIN, i32::MIN + 2), i32::MIN + 1);
+        assert_eq!(Midpoint::midpoint(&u32::MAX, 1), u32::MAX / 2 + 1);
+        assert_eq!(Midpoint::midpoint(&usize::MAX, usize::MAX), usize::MAX);
+
+        let mut qt = Q::with_node_capacity(2, (i64::MIN, i64::MAX, i64::MIN, i64::MAX));
+        let points = [
+            (i64::MIN, i64::MIN),
+            (i64::MAX - 1, i64::MAX - 1),
+            (0, 0),
+            (-1, 1),
+            (i64::MAX - 1, i64::MIN),
+        ];
+        for p in points.iter() {
+            assert!(qt.insert(*p));
+        }
+        assert_eq!(qt.size(), points.len());
+        for p in points.iter() {
+            assert_eq!(qt.search(&(p.0, p.0 + 1, p.1, p.1 + 1)), vec![*p]);
+        }
+    }
+
+    #[test]
+    fn clone_coordinates() {
+        // Stands in for something like a big decimal: `Clone`, but not `Copy`.
+        #[derive(Debug, Clone, PartialEq, PartialOrd)]
+        struct Big(Box<i64>);
+
+        impl Midpoint for Big {
+            fn midpoint(&self, a: Big) -> Big {
+                Big(Box::new(Midpoint::midpoint(&*self.0, *a.0)))
+            }
+        }
+
+        let big = |n: i64| Big(Box::new(n));
+        let config = Config {
+            capacity: 2,
+            tolerance: Some(Tolerance {
+                epsilon: 0.5,
+                distance: |a: &Big, b: Big| (*a.0 - *b.0).abs() as f64,
+            }),
+            ..Config::default()
+        };
+        let mut qt = Q::with_config(config, (big(0), big(16), big(0), big(16)));
+        for i in 0..8 {
+            assert!(qt.insert((big(i * 2), big(15 - i * 2))));
+        }
+        assert_eq!(qt.size(), 8);
+        assert_eq!(
+            qt.search(&(big(0), big(4), big(0), big(16))),
+            vec![(big(0), big(15)), (big(2), big(13))]
+        );
+        assert!(qt.remove(&(big(2), big(13))));
+        assert!(!qt.remove(&(big(2), big(13))));
+        assert_eq!(qt.size(), 7);
+
+        let built = QuadTreeBuilder::new((big(0), big(16), big(0), big(16)))
+            .capacity(2)
+            .build()
+            .unwrap();
+        assert_eq!(built.get_config().capacity, 2);
     }
 
     #[test]
@@ -304,10 +1222,60 @@ mod tests {
 
         // Go through each element in first result, and make sure each of them exists in the others
         for point in points1 {
-            assert!(points2.iter().any(|p| *p == point));
-            assert!(points3.iter().any(|p| *p == point));
-            assert!(points4.iter().any(|p| *p == point));
+            assert!(points2.contains(&point));
+            assert!(points3.contains(&point));
+            assert!(points4.contains(&point));
+        }
+    }
+
+    #[test]
+    fn max_depth() {
+        fn depth(qt: &Q<f64>) -> usize {
+            match qt {
+                Q::Leaf(_, _, _) => 0,
+                Q::Node(_, _, _, children) => 1 + children.iter().map(depth).max().unwrap(),
+            }
+        }
+
+        let config = Config {
+            capacity: 4,
+            max_depth: Some(3),
+            ..Config::default()
+        };
+        let mut qt = Q::with_config(config, (0.0, 1.0, 0.0, 1.0));
+        for i in (0..1000).rev() {
+            qt.insert((0.5 + i as f64 * 1e-9, 0.5));
+            qt.insert((0.5 + i as f64 * 1e-9, 0.5));
         }
+        assert_eq!(depth(&qt), 3);
+        assert_eq!(qt.size(), 1000);
+        assert_eq!(qt.search(&(0.5, 0.5 + 5e-9, 0.0, 1.0)).len(), 5);
+        assert!(qt.remove(&(0.5 + 7e-9, 0.5)));
+        assert_eq!(qt.size(), 999);
+
+        // Would otherwise subdivide forever.
+        let mut same = Q::with_config(
+            Config {
+                capacity: 1,
+                ..config
+            },
+            (0.0, 1.0, 0.0, 1.0),
+        );
+        for _ in 0..10 {
+            same.insert_unchecked((0.25, 0.25));
+        }
+        assert_eq!(same.size(), 10);
+    }
+
+    #[test]
+    fn from_points_autofit() {
+        let points = vec![(0.5, 2.0), (-1.0, 7.25), (3.0, 3.0), (3.0, 3.0)];
+        let qt = Q::from_points_autofit(points.clone()).unwrap();
+        assert_eq!(qt.size(), 3);
+        for p in points {
+            assert_eq!(qt.search(&(p.0, p.0 + 0.001, p.1, p.1 + 0.001)), vec![p]);
+        }
+        assert!(Q::<i32>::from_points_autofit(vec![]).is_none());
     }
 
     #[test]
@@ -342,7 +1310,194 @@ mod tests {
         }
     }
 
-    struct XorShift64 {
+    #[test]
+    fn search_many_matches_search() {
+        let mut rng = get_rng();
+        let mut qt = Q::with_node_capacity(8, (0, 1000, 0, 1000));
+        for _ in 0..2000 {
+            qt.insert((rng.next(), rng.next()));
+        }
+
+        let boundaries = vec![
+            (0, 1000, 0, 1000),
+            (100, 200, 300, 400),
+            (500, 501, 500, 501),
+            (2000, 3000, 2000, 3000),
+        ];
+        let results = qt.search_many(&boundaries);
+        assert_eq!(results.len(), boundaries.len());
+        for (boundary, found) in boundaries.iter().zip(results) {
+            let mut expected = qt.search(boundary);
+            let mut found = found;
+            expected.sort();
+            found.sort();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn search_ids() {
+        let mut qt = Q::with_node_capacity(2, (0, 10, 0, 10));
+        for i in 0..10 {
+            qt.insert((i, i));
+        }
+        let id_of = |(x, _): &(u32, u32)| *x as usize;
+
+        let mut words: Vec<u64> = vec![];
+        qt.search_ids(&(2, 5, 0, 10), id_of, &mut words);
+        assert_eq!(words, vec![0b11100]);
+
+        let mut flags: Vec<bool> = vec![];
+        qt.search_ids(&(8, 20, 8, 20), id_of, &mut flags);
+        assert_eq!(flags.iter().filter(|f| **f).count(), 2);
+        assert!(flags[8] && flags[9]);
+    }
+
+    #[test]
+    fn insert_unchecked() {
+        let mut checked = Q::with_node_capacity(4, (0, 100, 0, 100));
+        let mut unchecked = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            for j in 0..100 {
+                checked.insert((i, j));
+                unchecked.insert_unchecked((i, j));
+            }
+        }
+        assert_eq!(unchecked.size(), checked.size());
+        let mut a = unchecked.search(&(10, 20, 30, 40));
+        let mut b = checked.search(&(10, 20, 30, 40));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        // It really doesn't look for duplicates.
+        unchecked.insert_unchecked((1, 1));
+        assert_eq!(unchecked.size(), checked.size() + 1);
+    }
+
+    #[test]
+    fn remove() {
+        let mut qt = Q::with_node_capacity(4, (0, 10, 0, 10));
+        for i in 0..10 {
+            qt.insert((i, i));
+        }
+        assert!(matches!(qt, Q::Node(_, _, _, _)));
+        assert!(qt.remove(&(3, 3)));
+        assert!(!qt.remove(&(3, 3)));
+        assert!(!qt.remove(&(3, 4)));
+        assert!(!qt.remove(&(30, 30)));
+        assert_eq!(qt.size(), 9);
+        assert!(qt.search(&(3, 4, 3, 4)).is_empty());
+
+        for i in 4..10 {
+            qt.remove(&(i, i));
+        }
+        assert!(matches!(qt, Q::Leaf(_, _, _)));
+        let mut left = qt.search(&(0, 10, 0, 10));
+        left.sort();
+        assert_eq!(left, vec![(0, 0), (1, 1), (2, 2)]);
+
+        let config = Config {
+            capacity: 2,
+            duplicates: Duplicates::Sorted,
+            ..Config::default()
+        };
+        let mut sorted = Q::with_config(config, (0, 10, 0, 10));
+        for p in [(5, 5), (1, 1), (2, 2), (3, 1)] {
+            sorted.insert(p);
+        }
+        sorted.remove(&(5, 5));
+        sorted.remove(&(1, 1));
+        match sorted {
+            Q::Leaf(_, _, points) => assert_eq!(points, vec![(2, 2), (3, 1)]),
+            _ => panic!("Should have merged"),
+        }
+    }
+
+    #[test]
+    fn duplicate_strategies() {
+        let mut rng = get_rng();
+        let points: Vec<_> = (0..3000).map(|_| (rng.next(), rng.next())).collect();
+
+        let mut linear = Q::with_node_capacity(16, (0, 1000, 0, 1000));
+        let sorted_config = Config {
+            capacity: 16,
+            duplicates: Duplicates::Sorted,
+            ..Config::default()
+        };
+        let mut sorted = Q::with_config(sorted_config, (0, 1000, 0, 1000));
+        for p in &points {
+            linear.insert(*p);
+            sorted.insert(*p);
+            sorted.insert(*p);
+        }
+        assert_eq!(sorted.size(), linear.size());
+        let mut a = sorted.search(&(100, 600, 200, 900));
+        let mut b = linear.search(&(100, 600, 200, 900));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        let trust_config = Config {
+            capacity: 16,
+            duplicates: Duplicates::Trust,
+            ..Config::default()
+        };
+        let mut trust = Q::with_config(trust_config, (0, 10, 0, 10));
+        trust.insert((1, 1));
+        trust.insert((1, 1));
+        assert_eq!(trust.size(), 2);
+    }
+
+    #[test]
+    fn tolerance() {
+        let config = Config {
+            capacity: 2,
+            tolerance: Some(Tolerance::new(0.001)),
+            ..Config::default()
+        };
+        let mut qt = Q::with_config(config, (0.0, 1.0, 0.0, 1.0));
+        qt.insert((0.2, 0.2));
+        qt.insert((0.2, 0.2005));
+        qt.insert((0.2 + 1e-9, 0.2));
+        assert_eq!(qt.size(), 1);
+
+        // Both sides of the seam at 0.5 once the root has been split.
+        qt.insert((0.8, 0.8));
+        qt.insert((0.4999, 0.1));
+        qt.insert((0.5, 0.1));
+        qt.insert((0.5005, 0.1));
+        assert_eq!(qt.size(), 3);
+        qt.insert((0.51, 0.1));
+        assert_eq!(qt.size(), 4);
+    }
+
+    #[test]
+    fn grid() {
+        let config = Config {
+            capacity: 4,
+            grid: Some(Grid::new(0.5)),
+            ..Config::default()
+        };
+        let mut qt = Q::with_config(config, (0.0, 10.0, 0.0, 10.0));
+        for i in 0..100 {
+            let noise = i as f64 * 0.001;
+            qt.insert((1.1 + noise, 2.3 - noise));
+        }
+        assert_eq!(qt.search(&(0.0, 10.0, 0.0, 10.0)), vec![(1.0, 2.0)]);
+
+        assert_eq!((-3).snap(4), -4);
+        assert_eq!(7u32.snap(4), 4);
+        assert_eq!((-0.1f32).snap(0.5), -0.5);
+
+        assert_eq!(Grid::try_new(0).unwrap_err(), BuildError::BadResolution);
+        assert!(Grid::try_new(0.0).is_err());
+        assert!(Grid::try_new(f64::NAN).is_err());
+        assert!(Grid::try_new(-1.0).is_err());
+        assert!(Grid::try_new(1u32).is_ok());
+    }
+
+    pub(crate) struct XorShift64 {
         a: u64,
     }
 
@@ -357,7 +1512,7 @@ mod tests {
         }
     }
 
-    fn get_rng() -> XorShift64 {
+    pub(crate) fn get_rng() -> XorShift64 {
         use std::time::{SystemTime, UNIX_EPOCH};
 
         // Pseudo random generator
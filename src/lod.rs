@@ -0,0 +1,84 @@
+use crate::nearest::distance_to_boundary_sq;
+use crate::{CellInfo, Distance, Midpoint, Point, QuadTree};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The cells to draw terrain chunks for, seen from `camera`: fine near it and coarser further
+    /// away. A node is drawn as one chunk once its width divided by how far it is from the camera
+    /// is at most `screen_error_threshold`, which is roughly how big it looks. Otherwise its
+    /// children are looked at instead, down to the leaves. The cells cover the whole tree without
+    /// overlapping, and `size` is the number of points below each one.
+    pub fn select_lod(&self, camera: &Point<T>, screen_error_threshold: f64) -> Vec<CellInfo<T>> {
+        let mut cells = vec![];
+        self.select_lod_into(camera, screen_error_threshold, 0, &mut cells);
+        cells
+    }
+
+    fn select_lod_into(
+        &self,
+        camera: &Point<T>,
+        threshold: f64,
+        depth: usize,
+        cells: &mut Vec<CellInfo<T>>,
+    ) {
+        let boundary = *self.get_boundary();
+        let (x1, x2, y1, y2) = boundary;
+        let width = x1.distance(x2).max(y1.distance(y2));
+        let distance = distance_to_boundary_sq(&boundary, camera).sqrt();
+        match self {
+            QuadTree::Node(_, _, _, children) if width > threshold * distance => {
+                for child in children.iter() {
+                    child.select_lod_into(camera, threshold, depth + 1, cells);
+                }
+            }
+            _ => cells.push(CellInfo {
+                boundary,
+                depth,
+                size: self.size(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn select_lod() {
+        // Terrain samples all over, so the tree is deep everywhere.
+        let mut qt = Q::with_node_capacity(1, (0.0, 1024.0, 0.0, 1024.0));
+        for i in 0..64 {
+            for j in 0..64 {
+                qt.insert((i as f64 * 16.0 + 1.0, j as f64 * 16.0 + 1.0));
+            }
+        }
+        let cells = qt.select_lod(&(10.0, 10.0), 0.5);
+        let area: f64 = cells
+            .iter()
+            .map(|c| (c.boundary.1 - c.boundary.0) * (c.boundary.3 - c.boundary.2))
+            .sum();
+        assert_eq!(area, 1024.0 * 1024.0);
+        assert_eq!(cells.iter().map(|c| c.size).sum::<usize>(), 64 * 64);
+        assert!(cells.len() < qt.leaves().count() / 10);
+
+        let depth_at = |x: f64, y: f64| {
+            cells
+                .iter()
+                .find(|c| Q::contains(&c.boundary, &(x, y)))
+                .unwrap()
+                .depth
+        };
+        assert!(depth_at(10.0, 10.0) > depth_at(1000.0, 1000.0));
+        assert!(depth_at(10.0, 10.0) > depth_at(10.0, 600.0));
+        assert_eq!(
+            qt.locate(&(10.0, 10.0)).unwrap().depth,
+            depth_at(10.0, 10.0)
+        );
+
+        // Everything fits in one chunk from far enough away.
+        assert_eq!(qt.select_lod(&(1e6, 1e6), 0.5).len(), 1);
+    }
+}
@@ -0,0 +1,802 @@
+use crate::nearest::{distance_sq, distance_to_boundary_sq};
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+use slotmap::{new_key_type, DenseSlotMap};
+
+new_key_type! {
+    /// A handle on a value in a `QuadMap`, given out when it's inserted. It stays the same for as
+    /// long as the value is in the map, however the tree around it changes. Once the value is
+    /// removed the handle finds nothing, even after its slot is used again for another value.
+    pub struct ItemId;
+}
+
+/// A quadtree that keeps values at its points, e.g. the readings of sensors by where they are.
+/// Several values can sit at exactly the same point, as two sensors can be in the same spot:
+/// each point holds a small bucket of values in the order they were inserted, and the bucket as
+/// a whole counts as one point towards the capacity of its leaf.
+///
+/// The values themselves are kept together in a slotmap outside the tree, which only holds their
+/// `ItemId`s. Iterating over everything doesn't have to walk the tree, and the ids can be held on
+/// to elsewhere to get at or remove a value later.
+#[derive(Debug, Clone)]
+pub struct QuadMap<T: PartialOrd + Copy + Midpoint, D> {
+    capacity: usize,
+    items: DenseSlotMap<ItemId, (Point<T>, D)>,
+    root: Cell<T>,
+}
+
+#[derive(Debug, Clone)]
+enum Cell<T> {
+    Leaf(Boundary<T>, Vec<(Point<T>, Vec<ItemId>)>),
+    Node(Boundary<T>, Box<[Cell<T>; 4]>),
+}
+
+impl<T, D> QuadMap<T, D>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Self::with_node_capacity(64, boundary)
+    }
+
+    /// `capacity` is how many different points a leaf holds. Panics if it's 0.
+    pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
+        assert!(capacity > 0, "capacity must be at least 1");
+        QuadMap {
+            capacity,
+            items: DenseSlotMap::with_key(),
+            root: Cell::Leaf(boundary, vec![]),
+        }
+    }
+
+    pub fn get_boundary(&self) -> Boundary<T> {
+        *self.root.boundary()
+    }
+
+    /// Adds `value` at `point`, after any values already there. None if the point is outside the
+    /// map.
+    pub fn insert(&mut self, point: Point<T>, value: D) -> Option<ItemId> {
+        if !QuadTree::contains(self.root.boundary(), &point) {
+            return None;
+        }
+        let id = self.items.insert((point, value));
+        self.root.insert(self.capacity, point, id);
+        Some(id)
+    }
+
+    /// The ids of the values at `point`, oldest first.
+    pub fn ids(&self, point: &Point<T>) -> &[ItemId] {
+        let mut cell = &self.root;
+        loop {
+            match cell {
+                Cell::Leaf(_, buckets) => {
+                    return match buckets.iter().find(|(p, _)| p == point) {
+                        Some((_, ids)) => ids,
+                        None => &[],
+                    }
+                }
+                Cell::Node(boundary, children) => {
+                    if !QuadTree::contains(boundary, point) {
+                        return &[];
+                    }
+                    cell = &children[QuadTree::quadrant_of(boundary, point)];
+                }
+            }
+        }
+    }
+
+    /// The values at `point`, oldest first.
+    pub fn get(&self, point: &Point<T>) -> Vec<&D> {
+        self.ids(point)
+            .iter()
+            .filter_map(|id| self.get_by_id(*id))
+            .collect()
+    }
+
+    /// None once the value has been removed, even if another has taken its place since.
+    pub fn get_by_id(&self, id: ItemId) -> Option<&D> {
+        self.items.get(id).map(|(_, value)| value)
+    }
+
+    pub fn get_by_id_mut(&mut self, id: ItemId) -> Option<&mut D> {
+        self.items.get_mut(id).map(|(_, value)| value)
+    }
+
+    pub fn point_of(&self, id: ItemId) -> Option<Point<T>> {
+        self.items.get(id).map(|(point, _)| *point)
+    }
+
+    /// The place for `point` in the map, to look at or add to without going down the tree twice.
+    /// None if the point is outside the map.
+    pub fn entry(&mut self, point: Point<T>) -> Option<Entry<'_, T, D>> {
+        if !QuadTree::contains(self.root.boundary(), &point) {
+            return None;
+        }
+        let mut cell = &mut self.root;
+        while let Cell::Node(boundary, children) = cell {
+            cell = &mut children[QuadTree::quadrant_of(boundary, &point)];
+        }
+        let found = match cell {
+            Cell::Leaf(_, buckets) => buckets.iter().position(|(p, _)| *p == point),
+            Cell::Node(_, _) => panic!("We should be at a leaf by now"),
+        };
+        Some(match (found, cell) {
+            (Some(at), Cell::Leaf(_, buckets)) => Entry::Occupied(OccupiedEntry {
+                ids: &mut buckets[at].1,
+                items: &mut self.items,
+            }),
+            (_, cell) => Entry::Vacant(VacantEntry {
+                point,
+                capacity: self.capacity,
+                cell,
+                items: &mut self.items,
+            }),
+        })
+    }
+
+    /// Takes every value at `point` out of the map. Nodes left with no more points than
+    /// `capacity` are merged back into a leaf.
+    pub fn remove(&mut self, point: &Point<T>) -> Vec<D> {
+        if !QuadTree::contains(self.root.boundary(), point) {
+            return vec![];
+        }
+        let ids = self.root.remove(self.capacity, point, None);
+        ids.into_iter()
+            .filter_map(|id| self.items.remove(id))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Takes the value with `id` out of the map, leaving any others at its point.
+    pub fn remove_by_id(&mut self, id: ItemId) -> Option<(Point<T>, D)> {
+        let (point, value) = self.items.remove(id)?;
+        self.root.remove(self.capacity, &point, Some(id));
+        Some((point, value))
+    }
+
+    /// Calls `f` on every value at a point inside `boundary`, which can change the value and move
+    /// its point. `f` says whether it moved the point. Values moved out of their leaf are filed
+    /// again where they now belong, and ones moved out of the map are taken out and given back.
+    pub fn update_where<F>(&mut self, boundary: &Boundary<T>, mut f: F) -> Vec<(Point<T>, D)>
+    where
+        F: FnMut(&mut Point<T>, &mut D) -> bool,
+    {
+        let mut moved = vec![];
+        self.root
+            .update(self.capacity, boundary, &mut self.items, &mut f, &mut moved);
+
+        let mut gone = vec![];
+        for id in moved {
+            let point = self.point_of(id).expect("ids in the tree are in use");
+            if QuadTree::contains(self.root.boundary(), &point) {
+                self.root.insert(self.capacity, point, id);
+            } else {
+                gone.extend(self.items.remove(id));
+            }
+        }
+        gone
+    }
+
+    /// Moves values to new points inside the leaf that holds `at`, going down the tree once for
+    /// all of them. Gives back the leaf's boundary and the moves that couldn't be made there:
+    /// the value isn't in that leaf, its new point is outside it, or there's no room for it.
+    pub(crate) fn move_in_leaf(
+        &mut self,
+        at: &Point<T>,
+        moves: Vec<(ItemId, Point<T>)>,
+    ) -> (Boundary<T>, Vec<(ItemId, Point<T>)>) {
+        let mut cell = &mut self.root;
+        while let Cell::Node(boundary, children) = cell {
+            cell = &mut children[QuadTree::quadrant_of(boundary, at)];
+        }
+        let (leaf, buckets) = match cell {
+            Cell::Leaf(leaf, buckets) => (leaf, buckets),
+            Cell::Node(_, _) => panic!("We should be at a leaf by now"),
+        };
+
+        let mut rest = vec![];
+        for (id, to) in moves {
+            let from = match self.items.get(id) {
+                Some((from, _)) => *from,
+                None => continue,
+            };
+            let found = buckets
+                .iter()
+                .position(|(p, ids)| *p == from && ids.contains(&id));
+            let from_bucket = match found {
+                Some(at) if QuadTree::contains(leaf, &to) => at,
+                _ => {
+                    rest.push((id, to));
+                    continue;
+                }
+            };
+            if from == to {
+                continue;
+            }
+            let to_bucket = buckets.iter().position(|(p, _)| *p == to);
+            let empties = buckets[from_bucket].1.len() == 1;
+            if to_bucket.is_none() && buckets.len() == self.capacity && !empties {
+                rest.push((id, to));
+                continue;
+            }
+
+            buckets[from_bucket].1.retain(|i| *i != id);
+            match to_bucket {
+                Some(at) => buckets[at].1.push(id),
+                None => buckets.push((to, vec![id])),
+            }
+            if empties {
+                buckets.swap_remove(from_bucket);
+            }
+            self.items.get_mut(id).expect("just found").0 = to;
+        }
+        (*leaf, rest)
+    }
+
+    /// Moves a value anywhere, by taking it out of the tree and filing it again. If `to` is
+    /// outside the map the value is taken out for good and given back.
+    pub(crate) fn relocate(&mut self, id: ItemId, to: Point<T>) -> Option<D> {
+        let from = self.point_of(id)?;
+        if !QuadTree::contains(self.root.boundary(), &to) {
+            return self.remove_by_id(id).map(|(_, value)| value);
+        }
+        self.root.remove(self.capacity, &from, Some(id));
+        self.items.get_mut(id).expect("just found").0 = to;
+        self.root.insert(self.capacity, to, id);
+        None
+    }
+
+    /// The boundary of the leaf `point` goes in.
+    pub(crate) fn leaf_of(&self, point: &Point<T>) -> Boundary<T> {
+        let mut cell = &self.root;
+        while let Cell::Node(boundary, children) = cell {
+            cell = &children[QuadTree::quadrant_of(boundary, point)];
+        }
+        *cell.boundary()
+    }
+
+    /// The number of values, counting every one in a bucket.
+    pub fn size(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Every value with its id and point, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (ItemId, Point<T>, &D)> {
+        self.items
+            .iter()
+            .map(|(id, (point, value))| (id, *point, value))
+    }
+
+    /// Every value at a point inside `boundary`, with its point.
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<(Point<T>, &D)> {
+        let mut ids = vec![];
+        self.root.search_into(boundary, &mut ids);
+        ids.into_iter()
+            .filter_map(|id| self.items.get(id))
+            .map(|(point, value)| (*point, value))
+            .collect()
+    }
+}
+
+impl<T, D> QuadMap<T, D>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+    D: Copy + Into<f64>,
+{
+    /// Inverse distance weighting: a guess at the value at `point` from the `k` values nearest to
+    /// it, each weighted by one over its distance to the power of `power` (2 is the usual). For
+    /// filling in a field measured at a few places, like temperatures from weather stations. A
+    /// value right at `point` is taken as it is, or the mean of them if there are several. None
+    /// if the map is empty or `k` is 0.
+    pub fn idw(&self, point: &Point<T>, k: usize, power: f64) -> Option<f64> {
+        if k == 0 {
+            return None;
+        }
+        let mut best = Vec::with_capacity(k + 1);
+        self.root.knn_into(point, k, &mut best);
+        let value = |id: ItemId| (*self.get_by_id(id).expect("ids in the tree are in use")).into();
+
+        let exact: Vec<f64> = best
+            .iter()
+            .take_while(|(d, _)| *d == 0.0)
+            .map(|(_, id)| value(*id))
+            .collect();
+        if !exact.is_empty() {
+            return Some(exact.iter().sum::<f64>() / exact.len() as f64);
+        }
+        if best.is_empty() {
+            return None;
+        }
+        let (mut sum, mut weights) = (0.0, 0.0);
+        for (d_sq, id) in best {
+            let weight = d_sq.powf(-power / 2.0);
+            sum += weight * value(id);
+            weights += weight;
+        }
+        Some(sum / weights)
+    }
+}
+
+/// What `QuadMap::entry` finds at a point.
+pub enum Entry<'a, T: PartialOrd + Copy + Midpoint, D> {
+    Occupied(OccupiedEntry<'a, T, D>),
+    Vacant(VacantEntry<'a, T, D>),
+}
+
+/// A point with values at it already.
+pub struct OccupiedEntry<'a, T, D> {
+    /// Never empty.
+    ids: &'a mut Vec<ItemId>,
+    items: &'a mut DenseSlotMap<ItemId, (Point<T>, D)>,
+}
+
+/// A point with nothing at it yet.
+pub struct VacantEntry<'a, T, D> {
+    point: Point<T>,
+    capacity: usize,
+    /// The leaf the point goes in.
+    cell: &'a mut Cell<T>,
+    items: &'a mut DenseSlotMap<ItemId, (Point<T>, D)>,
+}
+
+impl<'a, T, D> Entry<'a, T, D>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn point(&self) -> Point<T> {
+        match self {
+            Entry::Occupied(entry) => entry.point(),
+            Entry::Vacant(entry) => entry.point,
+        }
+    }
+
+    /// The oldest value at the point, after adding `value` if there wasn't one.
+    pub fn or_insert(self, value: D) -> &'a mut D {
+        self.or_insert_with(|| value)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> D>(self, f: F) -> &'a mut D {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut D
+    where
+        D: Default,
+    {
+        self.or_insert_with(D::default)
+    }
+
+    /// Changes the oldest value at the point, if there is one.
+    pub fn and_modify<F: FnOnce(&mut D)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, T, D> OccupiedEntry<'a, T, D>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn point(&self) -> Point<T> {
+        self.item(0).0
+    }
+
+    /// The ids of the values at the point, oldest first.
+    pub fn ids(&self) -> &[ItemId] {
+        self.ids
+    }
+
+    /// The oldest value at the point.
+    pub fn get(&self) -> &D {
+        &self.item(0).1
+    }
+
+    pub fn get_mut(&mut self) -> &mut D {
+        let id = self.ids[0];
+        &mut self
+            .items
+            .get_mut(id)
+            .expect("ids in the tree are in use")
+            .1
+    }
+
+    pub fn into_mut(self) -> &'a mut D {
+        let id = self.ids[0];
+        &mut self
+            .items
+            .get_mut(id)
+            .expect("ids in the tree are in use")
+            .1
+    }
+
+    /// Adds another value at the point, after the ones there.
+    pub fn insert(&mut self, value: D) -> ItemId {
+        let id = self.items.insert((self.point(), value));
+        self.ids.push(id);
+        id
+    }
+
+    fn item(&self, i: usize) -> &(Point<T>, D) {
+        self.items
+            .get(self.ids[i])
+            .expect("ids in the tree are in use")
+    }
+}
+
+impl<'a, T, D> VacantEntry<'a, T, D>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn point(&self) -> Point<T> {
+        self.point
+    }
+
+    pub fn insert(self, value: D) -> &'a mut D {
+        let id = self.items.insert((self.point, value));
+        self.cell.insert(self.capacity, self.point, id);
+        &mut self.items.get_mut(id).expect("just inserted").1
+    }
+}
+
+impl<T> Cell<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn boundary(&self) -> &Boundary<T> {
+        match self {
+            Cell::Leaf(boundary, _) => boundary,
+            Cell::Node(boundary, _) => boundary,
+        }
+    }
+
+    fn insert(&mut self, capacity: usize, point: Point<T>, id: ItemId) {
+        if let Cell::Leaf(boundary, buckets) = self {
+            if let Some((_, ids)) = buckets.iter_mut().find(|(p, _)| *p == point) {
+                ids.push(id);
+                return;
+            }
+            if buckets.len() < capacity {
+                buckets.push((point, vec![id]));
+                return;
+            }
+
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let mut children = Box::new([
+                Cell::Leaf(top_lef, vec![]),
+                Cell::Leaf(bot_lef, vec![]),
+                Cell::Leaf(top_rig, vec![]),
+                Cell::Leaf(bot_rig, vec![]),
+            ]);
+            for (p, ids) in buckets.drain(..) {
+                if let Cell::Leaf(_, child) = &mut children[QuadTree::quadrant_of(boundary, &p)] {
+                    child.push((p, ids));
+                }
+            }
+            *self = Cell::Node(*boundary, children);
+        }
+
+        match self {
+            Cell::Leaf(_, _) => panic!("We should never be a leaf at this point"),
+            Cell::Node(boundary, children) => {
+                children[QuadTree::quadrant_of(boundary, &point)].insert(capacity, point, id);
+            }
+        }
+    }
+
+    /// Removes `only` from the bucket at `point`, or the whole bucket if it's None, and gives back
+    /// the ids that went.
+    fn remove(&mut self, capacity: usize, point: &Point<T>, only: Option<ItemId>) -> Vec<ItemId> {
+        match self {
+            Cell::Leaf(_, buckets) => {
+                let at = match buckets.iter().position(|(p, _)| p == point) {
+                    Some(at) => at,
+                    None => return vec![],
+                };
+                match only {
+                    None => buckets.swap_remove(at).1,
+                    Some(id) => {
+                        let ids = &mut buckets[at].1;
+                        let removed = match ids.iter().position(|i| *i == id) {
+                            Some(i) => vec![ids.remove(i)],
+                            None => vec![],
+                        };
+                        if ids.is_empty() {
+                            buckets.swap_remove(at);
+                        }
+                        removed
+                    }
+                }
+            }
+            Cell::Node(boundary, children) => {
+                let removed =
+                    children[QuadTree::quadrant_of(boundary, point)].remove(capacity, point, only);
+                if !removed.is_empty() {
+                    self.merge_if_small(capacity);
+                }
+                removed
+            }
+        }
+    }
+
+    /// Turns a node whose children are leaves with no more than `capacity` points between them
+    /// back into a leaf.
+    fn merge_if_small(&mut self, capacity: usize) {
+        if let Cell::Node(boundary, children) = self {
+            let buckets = children.iter().try_fold(0, |sum, child| match child {
+                Cell::Leaf(_, buckets) => Some(sum + buckets.len()),
+                Cell::Node(_, _) => None,
+            });
+            if matches!(buckets, Some(b) if b <= capacity) {
+                let mut buckets = vec![];
+                for child in children.iter_mut() {
+                    if let Cell::Leaf(_, child_buckets) = child {
+                        buckets.append(child_buckets);
+                    }
+                }
+                *self = Cell::Leaf(*boundary, buckets);
+            }
+        }
+    }
+
+    /// The leaf part of `QuadMap::update_where`. Values that can't stay in their leaf are taken
+    /// out of it and put in `moved`.
+    fn update<D, F>(
+        &mut self,
+        capacity: usize,
+        boundary: &Boundary<T>,
+        items: &mut DenseSlotMap<ItemId, (Point<T>, D)>,
+        f: &mut F,
+        moved: &mut Vec<ItemId>,
+    ) where
+        F: FnMut(&mut Point<T>, &mut D) -> bool,
+    {
+        if !QuadTree::intersects(self.boundary(), boundary) {
+            return;
+        }
+        match self {
+            Cell::Leaf(leaf, buckets) => {
+                let mut arrived = vec![];
+                for (p, ids) in buckets.iter_mut() {
+                    if !QuadTree::contains(boundary, p) {
+                        continue;
+                    }
+                    ids.retain(|id| {
+                        let (point, value) =
+                            items.get_mut(*id).expect("ids in the tree are in use");
+                        if !f(point, value) || point == p {
+                            return true;
+                        }
+                        if QuadTree::contains(leaf, point) {
+                            arrived.push((*point, *id));
+                        } else {
+                            moved.push(*id);
+                        }
+                        false
+                    });
+                }
+                buckets.retain(|(_, ids)| !ids.is_empty());
+                // Moved, but not far enough to leave the leaf.
+                for (point, id) in arrived {
+                    if let Some((_, ids)) = buckets.iter_mut().find(|(p, _)| *p == point) {
+                        ids.push(id);
+                    } else if buckets.len() < capacity {
+                        buckets.push((point, vec![id]));
+                    } else {
+                        moved.push(id);
+                    }
+                }
+            }
+            Cell::Node(_, children) => {
+                for child in children.iter_mut() {
+                    child.update(capacity, boundary, items, f, moved);
+                }
+                self.merge_if_small(capacity);
+            }
+        }
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<ItemId>) {
+        if !QuadTree::intersects(self.boundary(), boundary) {
+            return;
+        }
+        match self {
+            Cell::Leaf(_, buckets) => {
+                for (p, ids) in buckets {
+                    if QuadTree::contains(boundary, p) {
+                        found.extend_from_slice(ids);
+                    }
+                }
+            }
+            Cell::Node(_, children) => {
+                for child in children.iter() {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Cell<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The `k` values closest to `point`, as their squared distance and id, nearest first. Like
+    /// `QuadTree::knn`, but every value in a bucket counts.
+    fn knn_into(&self, point: &Point<T>, k: usize, best: &mut Vec<(f64, ItemId)>) {
+        if best.len() == k && distance_to_boundary_sq(self.boundary(), point) > best[k - 1].0 {
+            return;
+        }
+        match self {
+            Cell::Leaf(_, buckets) => {
+                for (p, ids) in buckets {
+                    let d = distance_sq(point, p);
+                    for id in ids {
+                        if best.len() == k && d >= best[k - 1].0 {
+                            break;
+                        }
+                        let at = best.partition_point(|(other, _)| *other <= d);
+                        best.insert(at, (d, *id));
+                        best.truncate(k);
+                    }
+                }
+            }
+            Cell::Node(_, children) => {
+                let mut order: Vec<(f64, &Cell<T>)> = children
+                    .iter()
+                    .map(|c| (distance_to_boundary_sq(c.boundary(), point), c))
+                    .collect();
+                order.sort_by(|a, b| a.0.total_cmp(&b.0));
+                for (_, child) in order {
+                    child.knn_into(point, k, best);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, QuadMap};
+
+    #[test]
+    fn same_point_many_values() {
+        let mut map = QuadMap::with_node_capacity(2, (0, 10, 0, 10));
+        assert!(map.insert((3, 3), "thermometer").is_some());
+        assert!(map.insert((3, 3), "barometer").is_some());
+        assert!(map.insert((3, 3), "anemometer").is_some());
+        assert!(map.insert((10, 3), "outside").is_none());
+        // All three share one spot in the leaf, so there's still room.
+        assert!(map.insert((7, 7), "rain gauge").is_some());
+        assert_eq!(map.size(), 4);
+        assert_eq!(
+            map.get(&(3, 3)),
+            [&"thermometer", &"barometer", &"anemometer"]
+        );
+        assert!(map.get(&(4, 4)).is_empty());
+
+        let id = map.insert((8, 8), "hygrometer").unwrap();
+        *map.get_by_id_mut(id).unwrap() = "hygrometer 2";
+        let mut found = map.search(&(5, 10, 5, 10));
+        found.sort();
+        assert_eq!(found, [((7, 7), &"rain gauge"), ((8, 8), &"hygrometer 2")]);
+
+        assert_eq!(map.remove(&(3, 3)).len(), 3);
+        assert!(map.remove(&(3, 3)).is_empty());
+        assert_eq!(map.size(), 2);
+        assert_eq!(map.search(&(0, 10, 0, 10)).len(), 2);
+    }
+
+    #[test]
+    fn by_id() {
+        let mut map = QuadMap::with_node_capacity(1, (0, 10, 0, 10));
+        let ids: Vec<_> = (0..10).map(|i| map.insert((i, i), i).unwrap()).collect();
+        let other = map.insert((4, 4), 40).unwrap();
+        assert_eq!(map.point_of(ids[4]), Some((4, 4)));
+        assert_eq!(map.remove_by_id(ids[4]), Some(((4, 4), 4)));
+        assert_eq!(map.remove_by_id(ids[4]), None);
+        assert_eq!(map.get_by_id(ids[4]), None);
+        assert_eq!(map.ids(&(4, 4)), [other]);
+        // Goes where the removed value was, but the old id still finds nothing.
+        let reused = map.insert((9, 0), 90).unwrap();
+        assert_eq!(map.get_by_id(ids[4]), None);
+        assert_eq!(map.point_of(ids[4]), None);
+        assert_eq!(map.remove_by_id(ids[4]), None);
+        assert_eq!(map.remove_by_id(reused), Some(((9, 0), 90)));
+        for id in &ids[5..] {
+            map.remove_by_id(*id);
+        }
+        assert_eq!(map.size(), 5);
+        let mut values: Vec<_> = map.iter().map(|(_, _, value)| *value).collect();
+        values.sort();
+        assert_eq!(values, [0, 1, 2, 3, 40]);
+        assert_eq!(map.search(&(0, 10, 0, 10)).len(), 5);
+    }
+
+    #[test]
+    fn entry() {
+        let mut map = QuadMap::with_node_capacity(1, (0, 10, 0, 10));
+        for i in 0..5 {
+            *map.entry((i, i)).unwrap().or_insert(0) += 1;
+        }
+        *map.entry((2, 2)).unwrap().or_default() += 10;
+        map.entry((3, 3))
+            .unwrap()
+            .and_modify(|v| *v = 30)
+            .or_insert(0);
+        assert!(map.entry((10, 10)).is_none());
+        assert_eq!(map.size(), 5);
+        assert_eq!(map.get(&(2, 2)), [&11]);
+        assert_eq!(map.get(&(3, 3)), [&30]);
+
+        match map.entry((4, 4)).unwrap() {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.point(), (4, 4));
+                entry.insert(40);
+                assert_eq!(*entry.get(), 1);
+            }
+            Entry::Vacant(_) => panic!("(4, 4) is in the map"),
+        }
+        assert_eq!(map.get(&(4, 4)), [&1, &40]);
+        assert_eq!(map.search(&(0, 10, 0, 10)).len(), 6);
+    }
+
+    #[test]
+    fn update_where() {
+        let mut map = QuadMap::with_node_capacity(2, (0, 10, 0, 10));
+        for i in 0..10 {
+            map.insert((i, i), i);
+            map.insert((i, 9 - i), 10 + i);
+        }
+        // Everything in the top left moves one to the right, and the ones on x = 4 go off the map.
+        let gone = map.update_where(&(0, 5, 0, 5), |point, value| {
+            *value += 100;
+            point.0 = if point.0 == 4 { 20 } else { point.0 + 1 };
+            true
+        });
+        assert_eq!(gone, [((20, 4), 104)]);
+        assert_eq!(map.size(), 19);
+        assert_eq!(map.get(&(1, 0)), [&100]);
+        assert_eq!(map.get(&(4, 3)), [&103]);
+        assert_eq!(map.get(&(9, 0)), [&19]);
+        assert!(map.get(&(0, 0)).is_empty());
+        let mut found: Vec<_> = map.search(&(0, 10, 0, 10));
+        found.sort();
+        let mut everything: Vec<_> = map.iter().map(|(_, p, v)| (p, v)).collect();
+        everything.sort();
+        assert_eq!(found, everything);
+
+        // Nothing moved, nothing changes place.
+        let gone = map.update_where(&(0, 10, 0, 10), |_, value| {
+            *value += 1;
+            false
+        });
+        assert!(gone.is_empty());
+        assert_eq!(map.get(&(1, 0)), [&101]);
+    }
+
+    #[test]
+    fn idw() {
+        let mut stations = QuadMap::with_node_capacity(2, (0.0, 100.0, 0.0, 100.0));
+        stations.insert((10.0, 10.0), 10.0);
+        stations.insert((30.0, 10.0), 20.0);
+        stations.insert((90.0, 90.0), 1000.0);
+        let halfway = stations.idw(&(20.0, 10.0), 2, 2.0).unwrap();
+        assert!((halfway - 15.0).abs() < 1e-9);
+        // A quarter of the distance to the first, so 9 times its weight.
+        let guess = stations.idw(&(15.0, 10.0), 2, 2.0).unwrap();
+        assert!((guess - (9.0 * 10.0 + 20.0) / 10.0).abs() < 1e-9);
+        // The far station hardly counts.
+        assert!(stations.idw(&(20.0, 10.0), 3, 2.0).unwrap() < 20.0);
+
+        // Two sensors in the same spot.
+        stations.insert((10.0, 10.0), 14.0);
+        assert_eq!(stations.idw(&(10.0, 10.0), 3, 2.0), Some(12.0));
+        assert_eq!(stations.idw(&(10.0, 10.0), 0, 2.0), None);
+        let empty: QuadMap<f64, f32> = QuadMap::new((0.0, 1.0, 0.0, 1.0));
+        assert_eq!(empty.idw(&(0.5, 0.5), 3, 2.0), None);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{Boundary, CellInfo, Midpoint, Point, QuadTree};
+use std::cmp::Ordering;
+
+/// Triangles covering the leaves of a tree. Every three entries of `indices` point into
+/// `vertices` and make up one triangle, wound the same way for all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh<T> {
+    pub vertices: Vec<Point<T>>,
+    pub indices: Vec<u32>,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Turns every leaf into triangles. Plain leaves become two triangles. With `stitch` set, a
+    /// leaf that has smaller neighbours gets a fan around its centre instead, which takes in the
+    /// corners of those neighbours, so there are no T-junctions (cracks, once the vertices are
+    /// displaced). Run `balance` first to get evenly graded triangles.
+    pub fn mesh(&self, stitch: bool) -> Mesh<T> {
+        let mut triangles: Vec<[Point<T>; 3]> = vec![];
+        self.visit_cells(&|_| true, &mut |cell| {
+            let ring = if stitch {
+                self.cell_ring(&cell)
+            } else {
+                corners(&cell.boundary).to_vec()
+            };
+            let (x1, x2, y1, y2) = cell.boundary;
+            if ring.len() == 4 {
+                triangles.push([ring[0], ring[1], ring[2]]);
+                triangles.push([ring[0], ring[2], ring[3]]);
+            } else {
+                let center = (x1.midpoint(x2), y1.midpoint(y2));
+                for i in 0..ring.len() {
+                    triangles.push([center, ring[i], ring[(i + 1) % ring.len()]]);
+                }
+            }
+        });
+
+        let mut vertices: Vec<Point<T>> = triangles.iter().flatten().copied().collect();
+        vertices.sort_by(compare);
+        vertices.dedup();
+        let indices = triangles
+            .iter()
+            .flatten()
+            .map(|p| vertices.binary_search_by(|v| compare(v, p)).unwrap() as u32)
+            .collect();
+        Mesh { vertices, indices }
+    }
+
+    /// The corners of a cell plus the corners of smaller neighbours that sit on its edges, going
+    /// around the cell in the same direction as `corners`.
+    fn cell_ring(&self, cell: &CellInfo<T>) -> Vec<Point<T>> {
+        let (x1, x2, y1, y2) = cell.boundary;
+        let [top, bottom, left, right] = self.cell_neighbors(cell);
+        let inside = |v: &T, a: &T, b: &T| *v > *a && *v < *b;
+
+        let mut top: Vec<T> = top.iter().map(|c| c.boundary.0).collect();
+        top.retain(|x| inside(x, &x1, &x2));
+        top.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut right: Vec<T> = right.iter().map(|c| c.boundary.2).collect();
+        right.retain(|y| inside(y, &y1, &y2));
+        right.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let mut bottom: Vec<T> = bottom.iter().map(|c| c.boundary.0).collect();
+        bottom.retain(|x| inside(x, &x1, &x2));
+        bottom.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let mut left: Vec<T> = left.iter().map(|c| c.boundary.2).collect();
+        left.retain(|y| inside(y, &y1, &y2));
+        left.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+        let mut ring = vec![(x1, y1)];
+        ring.extend(top.into_iter().map(|x| (x, y1)));
+        ring.push((x2, y1));
+        ring.extend(right.into_iter().map(|y| (x2, y)));
+        ring.push((x2, y2));
+        ring.extend(bottom.into_iter().map(|x| (x, y2)));
+        ring.push((x1, y2));
+        ring.extend(left.into_iter().map(|y| (x1, y)));
+        ring
+    }
+}
+
+fn corners<T: Copy>((x1, x2, y1, y2): &Boundary<T>) -> [Point<T>; 4] {
+    [(*x1, *y1), (*x2, *y1), (*x2, *y2), (*x1, *y2)]
+}
+
+fn compare<T: PartialOrd>(a: &Point<T>, b: &Point<T>) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mesh;
+    use crate::QuadTree as Q;
+
+    fn area(mesh: &Mesh<f64>) -> f64 {
+        mesh.indices
+            .chunks(3)
+            .map(|t| {
+                let (ax, ay) = mesh.vertices[t[0] as usize];
+                let (bx, by) = mesh.vertices[t[1] as usize];
+                let (cx, cy) = mesh.vertices[t[2] as usize];
+                ((bx - ax) * (cy - ay) - (cx - ax) * (by - ay)) / 2.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn plain_mesh() {
+        let mut qt = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        qt.insert((1.0, 1.0));
+        qt.insert((7.0, 7.0));
+        let mesh = qt.mesh(false);
+        assert_eq!(mesh.vertices.len(), 9);
+        assert_eq!(mesh.indices.len(), 4 * 2 * 3);
+        assert_eq!(area(&mesh), 64.0);
+        assert_eq!(qt.mesh(true), mesh);
+    }
+
+    #[test]
+    fn stitched_mesh() {
+        let mut qt = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        qt.insert((1.0, 1.0));
+        qt.insert((3.0, 3.0));
+        qt.balance();
+
+        // The right and bottom quadrants each touch the two small cells next to them, so they
+        // get a fan of five triangles instead of two.
+        let mesh = qt.mesh(true);
+        assert_eq!(mesh.indices.len() / 3, 4 * 2 + 2 * 5 + 2);
+        assert_eq!(area(&mesh), 64.0);
+
+        // Every vertex on an edge of a triangle must be one of its corners.
+        let vertices = &mesh.vertices;
+        for t in mesh.indices.chunks(3) {
+            for i in 0..3 {
+                let (ax, ay) = vertices[t[i] as usize];
+                let (bx, by) = vertices[t[(i + 1) % 3] as usize];
+                for (vx, vy) in vertices {
+                    let cross = (bx - ax) * (vy - ay) - (by - ay) * (vx - ax);
+                    let dot = (vx - ax) * (bx - ax) + (vy - ay) * (by - ay);
+                    let len = (bx - ax) * (bx - ax) + (by - ay) * (by - ay);
+                    assert!(cross != 0.0 || dot <= 0.0 || dot >= len);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{Boundary, Midpoint, Point, QuadTree, SpatialIndex};
+
+/// Brute force index that keeps every point in a single `Vec` and scans all of them on search.
+/// It's slow, but it's obviously correct, which makes it a good thing to compare against.
+#[derive(Debug)]
+pub struct Naive<T: PartialOrd + Copy + Midpoint> {
+    boundary: Boundary<T>,
+    points: Vec<Point<T>>,
+}
+
+impl<T> Naive<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Naive {
+            boundary,
+            points: vec![],
+        }
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> bool {
+        if !QuadTree::contains(&self.boundary, &point) {
+            return false;
+        }
+        if !self.points.contains(&point) {
+            self.points.push(point);
+        }
+        true
+    }
+
+    /// Returns whether the point was there.
+    pub fn remove(&mut self, point: &Point<T>) -> bool {
+        match self.points.iter().position(|p| p == point) {
+            Some(i) => {
+                self.points.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        self.points
+            .iter()
+            .filter(|point| QuadTree::contains(boundary, point))
+            .copied()
+            .collect()
+    }
+}
+
+impl<T> SpatialIndex<T> for Naive<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn insert(&mut self, point: Point<T>) -> bool {
+        Naive::insert(self, point)
+    }
+
+    fn search(&self, boundary: &Boundary<T>) -> Vec<Point<T>> {
+        Naive::search(self, boundary)
+    }
+
+    fn size(&self) -> usize {
+        Naive::size(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Naive;
+    use crate::{QuadTree, SpatialIndex};
+
+    fn fill<I: SpatialIndex<i32>>(index: &mut I) {
+        for i in -5..25 {
+            for j in 0..20 {
+                index.insert((i, j));
+            }
+        }
+        index.insert((3, 3));
+    }
+
+    #[test]
+    fn agrees_with_quadtree() {
+        let mut naive = Naive::new((0, 20, 0, 20));
+        let mut qt = QuadTree::with_node_capacity(4, (0, 20, 0, 20));
+        fill(&mut naive);
+        fill(&mut qt);
+        assert_eq!(naive.size(), 400);
+        assert_eq!(naive.size(), SpatialIndex::size(&qt));
+
+        let boundary = (3, 9, 12, 30);
+        let mut expected = SpatialIndex::search(&qt, &boundary);
+        let mut found = naive.search(&boundary);
+        expected.sort();
+        found.sort();
+        assert_eq!(found, expected);
+    }
+}
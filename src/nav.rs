@@ -0,0 +1,138 @@
+use crate::geom::contains;
+use crate::nearest::distance_sq;
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+#[cfg(feature = "petgraph")]
+use petgraph::graph::UnGraph;
+use std::cmp::Ordering;
+
+/// The empty leaves of a tree as a graph to run A* or Dijkstra over. Node `i` is the leaf
+/// `cells[i]`, and is reached through its centre `nodes[i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavGraph<T> {
+    pub nodes: Vec<Point<T>>,
+    pub cells: Vec<Boundary<T>>,
+    /// Leaves sharing an edge, once each with the lower index first, and how far apart their
+    /// centres are.
+    pub edges: Vec<(usize, usize, f64)>,
+}
+
+fn compare<T: PartialOrd>(a: &Boundary<T>, b: &Boundary<T>) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// A graph of the leaves that hold no points, with edges between the ones that share an edge.
+    /// Points are walls, like for `flood_fill`, so big open areas are a few big nodes.
+    pub fn nav_graph(&self) -> NavGraph<T> {
+        let mut cells = vec![];
+        self.visit_cells(&|_| true, &mut |cell| {
+            if cell.size == 0 {
+                cells.push(cell.boundary)
+            }
+        });
+        cells.sort_by(compare);
+        let nodes: Vec<_> = cells
+            .iter()
+            .map(|(x1, x2, y1, y2)| (x1.midpoint(*x2), y1.midpoint(*y2)))
+            .collect();
+
+        let mut edges = vec![];
+        for (i, boundary) in cells.iter().enumerate() {
+            let cell = self.locate(&(boundary.0, boundary.2)).expect("a leaf");
+            for neighbor in self.cell_neighbors(&cell).iter().flatten() {
+                if let Ok(j) = cells.binary_search_by(|c| compare(c, &neighbor.boundary)) {
+                    if i < j {
+                        edges.push((i, j, distance_sq(&nodes[i], &nodes[j]).sqrt()));
+                    }
+                }
+            }
+        }
+        NavGraph {
+            nodes,
+            cells,
+            edges,
+        }
+    }
+}
+
+impl<T: PartialOrd + Copy> NavGraph<T> {
+    /// The node whose cell `point` is in, to start or end a path at. `None` if it's in a leaf
+    /// with points, or outside the tree. Goes through all the cells.
+    pub fn node_at(&self, point: &Point<T>) -> Option<usize> {
+        self.cells.iter().position(|cell| contains(cell, point))
+    }
+}
+
+#[cfg(feature = "petgraph")]
+impl<T: Copy> NavGraph<T> {
+    /// The same graph for petgraph's algorithms. Node indices are the same as here. Needs the
+    /// `petgraph` feature.
+    pub fn to_petgraph(&self) -> UnGraph<Point<T>, f64> {
+        use petgraph::graph::NodeIndex;
+        let mut graph = UnGraph::with_capacity(self.nodes.len(), self.edges.len());
+        for node in &self.nodes {
+            graph.add_node(*node);
+        }
+        for (i, j, cost) in &self.edges {
+            graph.add_edge(NodeIndex::new(*i), NodeIndex::new(*j), *cost);
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn nav_graph() {
+        // The same two rooms as for flood_fill: a wall of points down x = 5.
+        let mut qt = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        for y in [1.0, 3.0, 5.0, 7.0].iter() {
+            qt.insert((5.0, *y));
+        }
+        let graph = qt.nav_graph();
+        assert_eq!(graph.nodes.len(), 6);
+        assert!(graph.nodes.contains(&(2.0, 2.0)) && graph.nodes.contains(&(7.0, 5.0)));
+        // Two on the left, and four in a column on the right.
+        assert_eq!(graph.edges.len(), 1 + 3);
+        for (i, j, cost) in &graph.edges {
+            let (a, b): ((f64, f64), _) = (graph.nodes[*i], graph.nodes[*j]);
+            assert_eq!(*cost, (a.0 - b.0).hypot(a.1 - b.1));
+            assert!((a.0 < 4.0) == (b.0 < 4.0));
+        }
+
+        let left = graph.node_at(&(1.0, 7.5)).unwrap();
+        assert_eq!(graph.cells[left], (0.0, 4.0, 4.0, 8.0));
+        let right = graph.node_at(&(6.0, 0.0)).unwrap();
+        assert_eq!(graph.cells[right], (6.0, 8.0, 0.0, 2.0));
+        assert_eq!(graph.node_at(&(5.0, 5.0)), None);
+        assert_eq!(graph.node_at(&(9.0, 1.0)), None);
+        for (i, cell) in graph.cells.iter().enumerate() {
+            assert_eq!(graph.node_at(&(cell.0, cell.2)), Some(i));
+        }
+    }
+
+    #[cfg(feature = "petgraph")]
+    #[test]
+    fn to_petgraph() {
+        use petgraph::algo::dijkstra;
+        use petgraph::graph::NodeIndex;
+
+        let mut qt = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        qt.insert((5.0, 1.0));
+        qt.insert((5.0, 3.0));
+        let graph = qt.nav_graph();
+        let pg = graph.to_petgraph();
+        assert_eq!(pg.node_count(), graph.nodes.len());
+        assert_eq!(pg.edge_count(), graph.edges.len());
+        let from = graph.node_at(&(1.0, 1.0)).unwrap();
+        let to = graph.node_at(&(7.0, 1.0)).unwrap();
+        let costs = dijkstra(&pg, NodeIndex::new(from), None, |e| *e.weight());
+        // Round the end of the wall.
+        assert!(costs[&NodeIndex::new(to)] > 6.0);
+    }
+}
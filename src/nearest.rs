@@ -0,0 +1,496 @@
+use crate::cells::{segment_enters, signed};
+use crate::{Boundary, Distance, Midpoint, Point, QuadTree};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// Returns all points within `radius` (inclusive) of `center`.
+    pub fn search_radius(&self, center: &Point<T>, radius: f64) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.search_radius_into(center, radius * radius, &mut found);
+        found
+    }
+
+    fn search_radius_into(&self, center: &Point<T>, radius_sq: f64, found: &mut Vec<Point<T>>) {
+        if distance_to_boundary_sq(self.get_boundary(), center) > radius_sq {
+            return;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => found.extend(
+                points
+                    .iter()
+                    .filter(|p| distance_sq(center, p) <= radius_sq),
+            ),
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.search_radius_into(center, radius_sq, found);
+                }
+            }
+        }
+    }
+
+    /// Returns the `k` points closest to `point`, nearest first.
+    pub fn knn(&self, point: &Point<T>, k: usize) -> Vec<Point<T>> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.knn_into(point, k, &mut best);
+        }
+        best.into_iter().map(|(_, p)| p).collect()
+    }
+
+    /// The closest point to `point`, if the tree isn't empty.
+    pub fn nearest(&self, point: &Point<T>) -> Option<Point<T>> {
+        self.knn(point, 1).pop()
+    }
+
+    /// Takes the closest point to `point` out of the tree and returns it. Handy for greedy
+    /// matching, e.g. handing each request the closest free worker.
+    pub fn pop_nearest(&mut self, point: &Point<T>) -> Option<Point<T>> {
+        let nearest = self.nearest(point)?;
+        self.remove(&nearest);
+        Some(nearest)
+    }
+
+    /// The closest point to the segment from `a` to `b`, if the tree isn't empty. Think snapping a
+    /// click on a road to the nearest point of interest along it.
+    pub fn nearest_to_segment(&self, a: &Point<T>, b: &Point<T>) -> Option<Point<T>> {
+        let mut best = None;
+        self.nearest_to_segment_into(a, b, &mut best);
+        best.map(|(_, p)| p)
+    }
+
+    fn nearest_to_segment_into(
+        &self,
+        a: &Point<T>,
+        b: &Point<T>,
+        best: &mut Option<(f64, Point<T>)>,
+    ) {
+        if let Some((worst, _)) = best {
+            if segment_to_boundary_sq(self.get_boundary(), a, b) > *worst {
+                return;
+            }
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                for p in points {
+                    let d = segment_to_point_sq(a, b, p);
+                    if best.is_none_or(|(worst, _)| d < worst) {
+                        *best = Some((d, *p));
+                    }
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                let mut order: Vec<(f64, &QuadTree<T>)> = children
+                    .iter()
+                    .map(|c| (segment_to_boundary_sq(c.get_boundary(), a, b), c))
+                    .collect();
+                order.sort_by(|x, y| x.0.total_cmp(&y.0));
+                for (_, child) in order {
+                    child.nearest_to_segment_into(a, b, best);
+                }
+            }
+        }
+    }
+
+    /// Returns all points within `radius` (inclusive) of the segment from `from` to `to`, i.e.
+    /// inside the capsule it sweeps out. For something that moves fast, so it doesn't skip over
+    /// what it passes between two frames.
+    pub fn sweep_query(&self, from: &Point<T>, to: &Point<T>, radius: f64) -> Vec<Point<T>> {
+        let mut found = vec![];
+        self.sweep_query_into(from, to, radius * radius, &mut found);
+        found
+    }
+
+    fn sweep_query_into(
+        &self,
+        from: &Point<T>,
+        to: &Point<T>,
+        radius_sq: f64,
+        found: &mut Vec<Point<T>>,
+    ) {
+        if segment_to_boundary_sq(self.get_boundary(), from, to) > radius_sq {
+            return;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => found.extend(
+                points
+                    .iter()
+                    .filter(|p| segment_to_point_sq(from, to, p) <= radius_sq),
+            ),
+            QuadTree::Node(_, _, _, children) => {
+                for child in children.iter() {
+                    child.sweep_query_into(from, to, radius_sq, found);
+                }
+            }
+        }
+    }
+
+    /// Calls `f` once for every unordered pair of points within `radius` (inclusive) of each
+    /// other. The tree is walked against itself, so pairs of nodes too far apart are skipped
+    /// together instead of point by point. This is the neighbour list of particle simulations.
+    pub fn pairs_within<F>(&self, radius: f64, mut f: F)
+    where
+        F: FnMut(&Point<T>, &Point<T>),
+    {
+        self.pairs_within_self(radius * radius, &mut f);
+    }
+
+    fn pairs_within_self<F>(&self, radius_sq: f64, f: &mut F)
+    where
+        F: FnMut(&Point<T>, &Point<T>),
+    {
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                for (i, p) in points.iter().enumerate() {
+                    for q in &points[i + 1..] {
+                        if distance_sq(p, q) <= radius_sq {
+                            f(p, q);
+                        }
+                    }
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                for (i, child) in children.iter().enumerate() {
+                    child.pairs_within_self(radius_sq, f);
+                    for other in &children[i + 1..] {
+                        child.pairs_within_other(other, radius_sq, f);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pairs with one point in `self` and the other in `other`, which don't overlap.
+    fn pairs_within_other<F>(&self, other: &Self, radius_sq: f64, f: &mut F)
+    where
+        F: FnMut(&Point<T>, &Point<T>),
+    {
+        if boundary_gap_sq(self.get_boundary(), other.get_boundary()) > radius_sq {
+            return;
+        }
+        match (self, other) {
+            (QuadTree::Leaf(_, _, points), QuadTree::Leaf(_, _, others)) => {
+                for p in points {
+                    for q in others {
+                        if distance_sq(p, q) <= radius_sq {
+                            f(p, q);
+                        }
+                    }
+                }
+            }
+            (QuadTree::Node(_, _, _, children), _) => {
+                for child in children.iter() {
+                    child.pairs_within_other(other, radius_sq, f);
+                }
+            }
+            (_, QuadTree::Node(_, _, _, children)) => {
+                for child in children.iter() {
+                    self.pairs_within_other(child, radius_sq, f);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn knn_into(&self, point: &Point<T>, k: usize, best: &mut Vec<(f64, Point<T>)>) {
+        if best.len() == k {
+            let worst = best[k - 1].0;
+            if distance_to_boundary_sq(self.get_boundary(), point) > worst {
+                return;
+            }
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                for p in points {
+                    let d = distance_sq(point, p);
+                    if best.len() == k && d >= best[k - 1].0 {
+                        continue;
+                    }
+                    let at = best.partition_point(|(other, _)| *other <= d);
+                    best.insert(at, (d, *p));
+                    best.truncate(k);
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                // Visiting the closest child first tightens `best` as early as possible.
+                let mut order: Vec<(f64, &QuadTree<T>)> = children
+                    .iter()
+                    .map(|c| (distance_to_boundary_sq(c.get_boundary(), point), c))
+                    .collect();
+                order.sort_by(|a, b| a.0.total_cmp(&b.0));
+                for (_, child) in order {
+                    child.knn_into(point, k, best);
+                }
+            }
+        }
+    }
+}
+
+/// Squared distance from `p` to the segment from `a` to `b`, worked out with `a` at the origin.
+fn segment_to_point_sq<T>(a: &Point<T>, b: &Point<T>, p: &Point<T>) -> f64
+where
+    T: PartialOrd + Copy + Distance,
+{
+    let local = |q: &Point<T>| (signed(&q.0, &a.0), signed(&q.1, &a.1));
+    point_to_origin_segment_sq(local(b), local(p))
+}
+
+fn point_to_origin_segment_sq((bx, by): (f64, f64), (px, py): (f64, f64)) -> f64 {
+    let length_sq = bx * bx + by * by;
+    let t = if length_sq > 0.0 {
+        ((px * bx + py * by) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (dx, dy) = (px - t * bx, py - t * by);
+    dx * dx + dy * dy
+}
+
+/// Squared distance between the segment from `a` to `b` and `boundary`. 0 if it passes through.
+fn segment_to_boundary_sq<T>(boundary: &Boundary<T>, a: &Point<T>, b: &Point<T>) -> f64
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    if segment_enters(boundary, a, b).is_some() {
+        return 0.0;
+    }
+    // Otherwise the closest spot is an end of the segment or a corner of the boundary.
+    let (x1, x2, y1, y2) = *boundary;
+    let corners = [(x1, y1), (x1, y2), (x2, y1), (x2, y2)];
+    corners
+        .iter()
+        .map(|corner| segment_to_point_sq(a, b, corner))
+        .chain(
+            [a, b]
+                .iter()
+                .map(|end| distance_to_boundary_sq(boundary, end)),
+        )
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Squared distance between the closest parts of two boundaries. 0 if they overlap or touch.
+fn boundary_gap_sq<T>(a: &Boundary<T>, b: &Boundary<T>) -> f64
+where
+    T: PartialOrd + Copy + Distance,
+{
+    let gap = |a1: &T, a2: &T, b1: &T, b2: &T| {
+        if b1 > a2 {
+            b1.distance(*a2)
+        } else if a1 > b2 {
+            a1.distance(*b2)
+        } else {
+            0.0
+        }
+    };
+    let dx = gap(&a.0, &a.1, &b.0, &b.1);
+    let dy = gap(&a.2, &a.3, &b.2, &b.3);
+    dx * dx + dy * dy
+}
+
+pub(crate) fn distance_sq<T: Copy + Distance>(a: &Point<T>, b: &Point<T>) -> f64 {
+    distance_sq_by(a, b, &T::distance)
+}
+
+/// Squared distance from a point to the closest point of a boundary. Zero if it's inside.
+pub(crate) fn distance_to_boundary_sq<T>(boundary: &Boundary<T>, point: &Point<T>) -> f64
+where
+    T: PartialOrd + Copy + Distance,
+{
+    distance_to_boundary_sq_by(boundary, point, &T::distance)
+}
+
+/// `distance_sq` for when `T: Distance` isn't known, only its `distance` function.
+pub(crate) fn distance_sq_by<T: Clone>(
+    (ax, ay): &Point<T>,
+    (bx, by): &Point<T>,
+    distance: &impl Fn(&T, T) -> f64,
+) -> f64 {
+    let dx = distance(ax, bx.clone());
+    let dy = distance(ay, by.clone());
+    dx * dx + dy * dy
+}
+
+pub(crate) fn distance_to_boundary_sq_by<T: PartialOrd + Clone>(
+    (x1, x2, y1, y2): &Boundary<T>,
+    (x, y): &Point<T>,
+    distance: &impl Fn(&T, T) -> f64,
+) -> f64 {
+    let dx = if x < x1 {
+        distance(x1, x.clone())
+    } else if x > x2 {
+        distance(x, x2.clone())
+    } else {
+        0.0
+    };
+    let dy = if y < y1 {
+        distance(y1, y.clone())
+    } else if y > y2 {
+        distance(y, y2.clone())
+    } else {
+        0.0
+    };
+    dx * dx + dy * dy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::distance_sq;
+    use crate::QuadTree as Q;
+
+    fn grid() -> Q<i32> {
+        let mut qt = Q::with_node_capacity(4, (0, 50, 0, 50));
+        for i in 0..50 {
+            for j in 0..50 {
+                qt.insert((i, j));
+            }
+        }
+        qt
+    }
+
+    #[test]
+    fn search_radius() {
+        let qt = grid();
+        let mut found = qt.search_radius(&(10, 10), 1.0);
+        found.sort();
+        assert_eq!(found, vec![(9, 10), (10, 9), (10, 10), (10, 11), (11, 10)]);
+
+        let all = qt.search(&(0, 50, 0, 50));
+        let expected = all
+            .iter()
+            .filter(|p| distance_sq(&(25, 3), p) <= 49.0)
+            .count();
+        assert_eq!(qt.search_radius(&(25, 3), 7.0).len(), expected);
+    }
+
+    #[test]
+    fn knn() {
+        let qt = grid();
+        let found = qt.knn(&(0, 0), 3);
+        assert_eq!(found[0], (0, 0));
+        assert_eq!(found.len(), 3);
+        assert!(found[1..].contains(&(0, 1)) && found[1..].contains(&(1, 0)));
+
+        // Points outside of the tree still get their closest neighbours.
+        assert_eq!(qt.nearest(&(-10, 70)), Some((0, 49)));
+        assert_eq!(qt.knn(&(5, 5), 0), vec![]);
+        assert_eq!(
+            Q::<f32>::new((0.0, 1.0, 0.0, 1.0)).nearest(&(0.5, 0.5)),
+            None
+        );
+
+        // A NaN query has no real neighbours, but it mustn't panic.
+        let mut floats = Q::with_node_capacity(1, (0.0, 8.0, 0.0, 8.0));
+        floats.insert((1.0, 1.0));
+        floats.insert((6.0, 6.0));
+        assert_eq!(floats.knn(&(f64::NAN, 2.0), 1).len(), 1);
+    }
+
+    #[test]
+    fn pop_nearest() {
+        let mut taxis = Q::with_node_capacity(2, (0, 100, 0, 100));
+        for taxi in [(10, 10), (12, 10), (50, 50), (90, 5)].iter() {
+            taxis.insert(*taxi);
+        }
+        // Both riders are closest to the same taxi, the second one gets the next best.
+        assert_eq!(taxis.pop_nearest(&(11, 11)), Some((10, 10)));
+        assert_eq!(taxis.pop_nearest(&(10, 11)), Some((12, 10)));
+        assert_eq!(taxis.size(), 2);
+        assert_eq!(taxis.pop_nearest(&(0, 0)), Some((50, 50)));
+        assert_eq!(taxis.pop_nearest(&(0, 0)), Some((90, 5)));
+        assert_eq!(taxis.pop_nearest(&(0, 0)), None);
+    }
+
+    #[test]
+    fn nearest_to_segment() {
+        let mut qt = Q::with_node_capacity(2, (0.0, 100.0, 0.0, 100.0));
+        let pois = [
+            (10.0, 12.0),
+            (50.0, 47.0),
+            (52.0, 60.0),
+            (90.0, 90.0),
+            (5.0, 95.0),
+        ];
+        for poi in pois.iter() {
+            qt.insert(*poi);
+        }
+        // A road along y = 50 from x = 0 to 60.
+        assert_eq!(
+            qt.nearest_to_segment(&(0.0, 50.0), &(60.0, 50.0)),
+            Some((50.0, 47.0))
+        );
+        // Past the end of a short road, the end is what's measured from.
+        assert_eq!(
+            qt.nearest_to_segment(&(95.0, 70.0), &(95.0, 80.0)),
+            Some((90.0, 90.0))
+        );
+        // A road of one point is just `nearest`.
+        assert_eq!(
+            qt.nearest_to_segment(&(6.0, 90.0), &(6.0, 90.0)),
+            qt.nearest(&(6.0, 90.0))
+        );
+
+        let mut rng = crate::tests::get_rng();
+        let mut many = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..500 {
+            many.insert((rng.next() as i32, rng.next() as i32));
+        }
+        let (a, b) = ((100, 800), (700, 300));
+        let brute = many
+            .search(&(0, 1000, 0, 1000))
+            .into_iter()
+            .map(|p| super::segment_to_point_sq(&a, &b, &p))
+            .fold(f64::INFINITY, f64::min);
+        let found = many.nearest_to_segment(&a, &b).unwrap();
+        assert_eq!(super::segment_to_point_sq(&a, &b, &found), brute);
+    }
+
+    #[test]
+    fn sweep_query() {
+        let qt = grid();
+        // A shot along y = 10 that passes between the grid points it would skip in one frame.
+        let mut found = qt.sweep_query(&(2, 10), &(5, 10), 0.5);
+        found.sort();
+        assert_eq!(found, vec![(2, 10), (3, 10), (4, 10), (5, 10)]);
+        assert_eq!(
+            qt.sweep_query(&(7, 7), &(7, 7), 1.0),
+            qt.search_radius(&(7, 7), 1.0)
+        );
+
+        let (a, b) = ((3, 40), (45, 2));
+        let mut found = qt.sweep_query(&a, &b, 2.5);
+        found.sort();
+        let mut expected: Vec<_> = qt
+            .search(&(0, 50, 0, 50))
+            .into_iter()
+            .filter(|p| super::segment_to_point_sq(&a, &b, p) <= 2.5 * 2.5)
+            .collect();
+        expected.sort();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn pairs_within() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..600 {
+            qt.insert((rng.next() as i32, rng.next() as i32));
+        }
+        let points = qt.search(&(0, 1000, 0, 1000));
+        let mut expected = vec![];
+        for (i, p) in points.iter().enumerate() {
+            for q in &points[i + 1..] {
+                if distance_sq(p, q) <= 30.0 * 30.0 {
+                    expected.push(if p < q { (*p, *q) } else { (*q, *p) });
+                }
+            }
+        }
+        let mut found = vec![];
+        qt.pairs_within(30.0, |p, q| {
+            found.push(if p < q { (*p, *q) } else { (*q, *p) })
+        });
+        expected.sort();
+        found.sort();
+        assert!(!found.is_empty());
+        assert_eq!(found, expected);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{Boundary, Point};
+
+/// Gets told about changes to a tree. Pass one to `insert_observed` or `remove_observed`. Every
+/// method does nothing by default, so only implement the ones you care about. `()` is the observer
+/// that doesn't care about anything.
+///
+/// `boundary` and `depth` are those of the node the change happened in, with the root at depth 0.
+pub trait Observer<T> {
+    /// A point was added to a leaf.
+    fn inserted(&mut self, _point: &Point<T>, _boundary: &Boundary<T>, _depth: usize) {}
+
+    /// A point was taken out of a leaf.
+    fn removed(&mut self, _point: &Point<T>, _boundary: &Boundary<T>, _depth: usize) {}
+
+    /// A full leaf was split into four.
+    fn subdivided(&mut self, _boundary: &Boundary<T>, _depth: usize) {}
+
+    /// A node with few enough points left was turned back into a leaf.
+    fn merged(&mut self, _boundary: &Boundary<T>, _depth: usize) {}
+}
+
+impl<T> Observer<T> for () {}
+
+#[cfg(test)]
+mod tests {
+    use super::Observer;
+    use crate::{Boundary, Point, QuadTree};
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Observer<i32> for Log {
+        fn inserted(&mut self, p: &Point<i32>, _: &Boundary<i32>, depth: usize) {
+            self.0.push(format!("insert {:?} at {}", p, depth));
+        }
+
+        fn removed(&mut self, p: &Point<i32>, _: &Boundary<i32>, depth: usize) {
+            self.0.push(format!("remove {:?} at {}", p, depth));
+        }
+
+        fn subdivided(&mut self, b: &Boundary<i32>, depth: usize) {
+            self.0.push(format!("subdivide {:?} at {}", b, depth));
+        }
+
+        fn merged(&mut self, b: &Boundary<i32>, depth: usize) {
+            self.0.push(format!("merge {:?} at {}", b, depth));
+        }
+    }
+
+    #[test]
+    fn events() {
+        let mut log = Log::default();
+        let mut qt = QuadTree::with_node_capacity(2, (0, 8, 0, 8));
+        qt.insert_observed((1, 1), &mut log);
+        qt.insert_observed((1, 1), &mut log);
+        qt.insert_observed((7, 7), &mut log);
+        qt.insert_observed((9, 9), &mut log);
+        qt.insert_observed((7, 6), &mut log);
+        qt.remove_observed(&(7, 6), &mut log);
+        assert_eq!(
+            log.0,
+            vec![
+                "insert (1, 1) at 0",
+                "insert (7, 7) at 0",
+                "subdivide (0, 8, 0, 8) at 0",
+                "insert (7, 6) at 1",
+                "remove (7, 6) at 1",
+                "merge (0, 8, 0, 8) at 0",
+            ]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use crate::geom::{contains, covers, intersects};
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// An occupancy grid for robots and SLAM, kept as a quadtree. Every cell holds the log-odds that
+/// it's occupied: 0 is unknown, above 0 likely occupied, below 0 likely free. Sensor readings are
+/// added to the cells they cover. Cells are only split where a reading covers part of them, down
+/// to `max_depth`, and merged again once their quarters agree, so big open areas stay one cell.
+#[derive(Debug, Clone)]
+pub struct OccupancyMap<T: PartialOrd + Copy + Midpoint> {
+    root: Cell<T>,
+    max_depth: usize,
+    hit: f64,
+    miss: f64,
+    /// Log-odds are kept between these, so a cell seen occupied a thousand times can still be
+    /// cleared again by a few readings.
+    min: f64,
+    max: f64,
+}
+
+#[derive(Debug, Clone)]
+enum Cell<T> {
+    Leaf(Boundary<T>, f64),
+    Node(Boundary<T>, Box<[Cell<T>; 4]>),
+}
+
+/// Probability to log-odds.
+fn logit(p: f64) -> f64 {
+    (p / (1.0 - p)).ln()
+}
+
+impl<T> OccupancyMap<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// An unknown map over `boundary`. The smallest cells are `max_depth` splits down from it. A
+    /// reading makes a cell 70% likely occupied, or 40%, and cells stay between 12% and 97%.
+    pub fn new(boundary: Boundary<T>, max_depth: usize) -> Self {
+        OccupancyMap {
+            root: Cell::Leaf(boundary, 0.0),
+            max_depth,
+            hit: logit(0.7),
+            miss: logit(0.4),
+            min: logit(0.12),
+            max: logit(0.97),
+        }
+    }
+
+    /// Changes how much a single reading counts, as the probability of occupied that one `hit`
+    /// and one `miss` on an unknown cell give.
+    pub fn with_sensor_model(mut self, hit: f64, miss: f64) -> Self {
+        assert!(hit > 0.5 && hit < 1.0, "hit must be between 0.5 and 1");
+        assert!(miss > 0.0 && miss < 0.5, "miss must be between 0 and 0.5");
+        self.hit = logit(hit);
+        self.miss = logit(miss);
+        self
+    }
+
+    /// Keeps the probability of every cell between `min` and `max`.
+    pub fn with_clamping(mut self, min: f64, max: f64) -> Self {
+        assert!(
+            min > 0.0 && min < max && max < 1.0,
+            "need 0 < min < max < 1"
+        );
+        self.min = logit(min);
+        self.max = logit(max);
+        self
+    }
+
+    pub fn get_boundary(&self) -> &Boundary<T> {
+        match &self.root {
+            Cell::Leaf(boundary, _) => boundary,
+            Cell::Node(boundary, _) => boundary,
+        }
+    }
+
+    /// A hit on every cell overlapping `boundary`.
+    pub fn mark_occupied(&mut self, boundary: &Boundary<T>) {
+        self.update(boundary, self.hit);
+    }
+
+    /// A miss on every cell overlapping `boundary`, e.g. the cells a laser beam passed through.
+    pub fn mark_free(&mut self, boundary: &Boundary<T>) {
+        self.update(boundary, self.miss);
+    }
+
+    /// Adds `log_odds` to every cell overlapping `boundary`, for sensors with a model of their
+    /// own.
+    pub fn update(&mut self, boundary: &Boundary<T>, log_odds: f64) {
+        let (min, max) = (self.min, self.max);
+        self.root.update(
+            boundary,
+            &|l| (l + log_odds).max(min).min(max),
+            self.max_depth,
+        );
+    }
+
+    /// The log-odds of the cell `point` is in. `None` outside the map.
+    pub fn log_odds(&self, point: &Point<T>) -> Option<f64> {
+        if !contains(self.get_boundary(), point) {
+            return None;
+        }
+        let mut cell = &self.root;
+        loop {
+            match cell {
+                Cell::Leaf(_, log_odds) => return Some(*log_odds),
+                Cell::Node(boundary, children) => {
+                    cell = &children[QuadTree::quadrant_of(boundary, point)];
+                }
+            }
+        }
+    }
+
+    /// How likely the cell `point` is in is to be occupied. `None` outside the map.
+    pub fn probability(&self, point: &Point<T>) -> Option<f64> {
+        self.log_odds(point).map(|l| 1.0 - 1.0 / (1.0 + l.exp()))
+    }
+
+    /// Whether `point` is more likely free than not. Unknown cells and anywhere outside the map
+    /// aren't.
+    pub fn is_free(&self, point: &Point<T>) -> bool {
+        self.log_odds(point).is_some_and(|l| l < 0.0)
+    }
+
+    /// Whether `point` is more likely occupied than not.
+    pub fn is_occupied(&self, point: &Point<T>) -> bool {
+        self.log_odds(point).is_some_and(|l| l > 0.0)
+    }
+
+    /// Every cell with its log-odds, top left first.
+    pub fn cells(&self) -> Vec<(Boundary<T>, f64)> {
+        let mut cells = vec![];
+        let mut stack = vec![&self.root];
+        while let Some(cell) = stack.pop() {
+            match cell {
+                Cell::Leaf(boundary, log_odds) => cells.push((*boundary, *log_odds)),
+                Cell::Node(_, children) => stack.extend(children.iter().rev()),
+            }
+        }
+        cells
+    }
+}
+
+impl<T> Cell<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn update<F: Fn(f64) -> f64>(&mut self, area: &Boundary<T>, f: &F, depth: usize) {
+        match self {
+            Cell::Leaf(boundary, log_odds) => {
+                if !intersects(boundary, area) {
+                    return;
+                }
+                if depth == 0 || covers(area, boundary) {
+                    *log_odds = f(*log_odds);
+                    return;
+                }
+                let leaf = |b| Cell::Leaf(b, *log_odds);
+                let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+                let children = [leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)];
+                *self = Cell::Node(*boundary, Box::new(children));
+                self.update(area, f, depth);
+            }
+            Cell::Node(boundary, children) => {
+                if !intersects(boundary, area) {
+                    return;
+                }
+                for child in children.iter_mut() {
+                    child.update(area, f, depth - 1);
+                }
+                // Quarters that ended up the same go back to being one cell.
+                let first = match &children[0] {
+                    Cell::Leaf(_, l) => *l,
+                    Cell::Node(_, _) => return,
+                };
+                if children
+                    .iter()
+                    .all(|c| matches!(c, Cell::Leaf(_, l) if *l == first))
+                {
+                    *self = Cell::Leaf(*boundary, first);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OccupancyMap;
+
+    #[test]
+    fn occupancy() {
+        let mut map = OccupancyMap::new((0, 16, 0, 16), 4);
+        assert_eq!(map.cells().len(), 1);
+        map.mark_occupied(&(4, 8, 4, 8));
+        map.mark_occupied(&(4, 8, 4, 8));
+        map.mark_free(&(0, 16, 0, 4));
+        map.mark_free(&(5, 6, 5, 6));
+
+        assert!(map.is_occupied(&(4, 4)) && !map.is_free(&(4, 4)));
+        assert!(map.is_free(&(15, 0)) && map.is_free(&(0, 3)));
+        assert!(!map.is_free(&(12, 12)) && !map.is_occupied(&(12, 12)));
+        assert!(!map.is_free(&(16, 0)));
+        assert_eq!(map.log_odds(&(12, 12)), Some(0.0));
+        let twice = map.probability(&(7, 7)).unwrap();
+        assert!((twice - 0.7 * 0.7 / (0.7 * 0.7 + 0.3 * 0.3)).abs() < 1e-12);
+        assert!(map.probability(&(5, 5)).unwrap() < twice);
+
+        // However often a cell is seen, it's clamped.
+        for _ in 0..100 {
+            map.mark_occupied(&(4, 8, 4, 8));
+        }
+        assert!((map.probability(&(7, 7)).unwrap() - 0.97).abs() < 1e-12);
+
+        // Clearing it all makes it one cell again.
+        for _ in 0..100 {
+            map.mark_free(&(0, 16, 0, 16));
+        }
+        assert_eq!(map.cells().len(), 1);
+        assert!(map.is_free(&(5, 5)));
+    }
+}
@@ -0,0 +1,116 @@
+//! Coordinate traits for the `ordered-float` wrappers, so they can be used as coordinates as they
+//! are. Needs the `ordered-float` feature.
+use crate::format::FixedBytes;
+use crate::{Distance, Midpoint, NextUp, Snap};
+use ordered_float::{NotNan, OrderedFloat};
+
+macro_rules! ordered_float_coordinates {
+    ($f:ty) => {
+        impl Midpoint for OrderedFloat<$f> {
+            fn midpoint(&self, a: Self) -> Self {
+                OrderedFloat(self.0.midpoint(a.0))
+            }
+        }
+
+        impl Distance for OrderedFloat<$f> {
+            fn distance(&self, a: Self) -> f64 {
+                self.0.distance(a.0)
+            }
+        }
+
+        impl Snap for OrderedFloat<$f> {
+            fn snap(&self, resolution: Self) -> Self {
+                OrderedFloat(self.0.snap(resolution.0))
+            }
+        }
+
+        impl NextUp for OrderedFloat<$f> {
+            fn next_up(&self) -> Self {
+                OrderedFloat(NextUp::next_up(&self.0))
+            }
+        }
+
+        /// Written the same as the plain float, so the two can read each other's files.
+        impl FixedBytes for OrderedFloat<$f> {
+            const TYPE_TAG: u64 = <$f as FixedBytes>::TYPE_TAG;
+
+            fn to_bytes(&self) -> [u8; 8] {
+                self.0.to_bytes()
+            }
+
+            fn from_bytes(bytes: [u8; 8]) -> Self {
+                OrderedFloat(<$f>::from_bytes(bytes))
+            }
+        }
+
+        /// The midpoint of two infinities of opposite sign is NaN, so this panics for those.
+        impl Midpoint for NotNan<$f> {
+            fn midpoint(&self, a: Self) -> Self {
+                NotNan::new(Midpoint::midpoint(&self.into_inner(), a.into_inner()))
+                    .expect("midpoint of opposite infinities")
+            }
+        }
+
+        impl Distance for NotNan<$f> {
+            fn distance(&self, a: Self) -> f64 {
+                self.into_inner().distance(a.into_inner())
+            }
+        }
+
+        impl Snap for NotNan<$f> {
+            fn snap(&self, resolution: Self) -> Self {
+                NotNan::new(self.into_inner().snap(resolution.into_inner()))
+                    .expect("snapping to an infinite or zero resolution")
+            }
+        }
+
+        impl NextUp for NotNan<$f> {
+            fn next_up(&self) -> Self {
+                NotNan::new(NextUp::next_up(&self.into_inner())).expect("next_up is never NaN")
+            }
+        }
+
+        /// Written the same as the plain float. Reading a NaN panics.
+        impl FixedBytes for NotNan<$f> {
+            const TYPE_TAG: u64 = <$f as FixedBytes>::TYPE_TAG;
+
+            fn to_bytes(&self) -> [u8; 8] {
+                self.into_inner().to_bytes()
+            }
+
+            fn from_bytes(bytes: [u8; 8]) -> Self {
+                NotNan::new(<$f>::from_bytes(bytes)).expect("NaN coordinate")
+            }
+        }
+    };
+}
+
+ordered_float_coordinates!(f32);
+ordered_float_coordinates!(f64);
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+    use ordered_float::{NotNan, OrderedFloat};
+
+    #[test]
+    fn ordered_float() {
+        let o = |n: f64| OrderedFloat(n);
+        let mut qt = Q::with_node_capacity(2, (o(0.0), o(10.0), o(0.0), o(10.0)));
+        for i in 0..10 {
+            qt.insert((o(i as f64), o(i as f64 + 0.5)));
+        }
+        assert_eq!(qt.size(), 10);
+        assert_eq!(qt.nearest(&(o(3.1), o(3.1))), Some((o(3.0), o(3.5))));
+        let back = Q::<f64>::from_bytes(&qt.to_bytes()).unwrap();
+        assert_eq!(back.size(), 10);
+
+        let n = |n: f32| NotNan::new(n).unwrap();
+        let mut qt = Q::with_node_capacity(2, (n(-1.0), n(1.0), n(-1.0), n(1.0)));
+        for i in 0..10 {
+            qt.insert((n(i as f32 / 10.0), n(-(i as f32) / 10.0)));
+        }
+        let found = qt.search(&(n(0.0), n(0.35), n(-0.35), n(0.0)));
+        assert_eq!(found.len(), 3);
+    }
+}
@@ -0,0 +1,355 @@
+use crate::format::FixedBytes;
+use crate::{Boundary, Midpoint, Point, QuadTree};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_PAGE: AtomicUsize = AtomicUsize::new(0);
+
+/// A tree cut into pages that live in files and are only read when something needs them, so the
+/// whole thing doesn't have to fit in memory. Made with `PagedQuadTree::create`.
+///
+/// Pages are the subtrees a few levels down. Searching loads the pages it touches and keeps them,
+/// until `unload` lets go of them again or the memory budget (see `set_memory_budget`) pushes out
+/// the ones used least recently. Changed pages are written back when they're dropped from memory
+/// and on `flush`.
+/// Like `to_bytes`, pages don't keep tolerance, grid and maximum depth settings. The page files
+/// are removed when the tree is dropped.
+#[derive(Debug)]
+pub struct PagedQuadTree<T: PartialOrd + Copy + Midpoint> {
+    boundary: Boundary<T>,
+    pub(crate) pages: Vec<Page<T>>,
+    pub(crate) budget: Option<usize>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct Page<T: PartialOrd + Copy + Midpoint> {
+    boundary: Boundary<T>,
+    pub(crate) path: PathBuf,
+    pub(crate) size: usize,
+    pub(crate) tree: Option<QuadTree<T>>,
+    pub(crate) dirty: bool,
+    last_used: u64,
+}
+
+impl<T> PagedQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Cuts `tree` into pages `depth` levels below its root (or higher up, where it has leaves)
+    /// and writes them to files in `dir`. Nothing stays in memory. Every page is checked
+    /// separately when searching, so keep `depth` small; 4 gives at most 256 pages.
+    pub fn create(tree: QuadTree<T>, depth: usize, dir: &Path) -> io::Result<Self> {
+        let mut paged = Self::cut_into_pages(tree, depth, dir);
+        paged.unload()?;
+        Ok(paged)
+    }
+
+    /// The pages for `create`, all still in memory and waiting to be written.
+    pub(crate) fn cut_into_pages(tree: QuadTree<T>, depth: usize, dir: &Path) -> Self {
+        let mut paged = PagedQuadTree {
+            boundary: *tree.get_boundary(),
+            pages: vec![],
+            budget: None,
+            clock: 0,
+        };
+        let mut subtrees = vec![];
+        cut(tree, depth, &mut subtrees);
+        for tree in subtrees {
+            let n = NEXT_PAGE.fetch_add(1, Ordering::Relaxed);
+            let page = Page {
+                boundary: *tree.get_boundary(),
+                path: dir.join(format!("quadtree-page-{}-{}", std::process::id(), n)),
+                size: tree.size(),
+                tree: Some(tree),
+                dirty: true,
+                last_used: 0,
+            };
+            paged.pages.push(page);
+        }
+        paged
+    }
+
+    pub fn get_boundary(&self) -> Boundary<T> {
+        self.boundary
+    }
+
+    pub fn size(&self) -> usize {
+        self.pages.iter().map(|page| page.size).sum()
+    }
+
+    /// How many pages are in memory right now.
+    pub fn loaded(&self) -> usize {
+        self.pages.iter().filter(|page| page.tree.is_some()).count()
+    }
+
+    /// Keeps at most about `points` points in memory, counting every point of a loaded page.
+    /// When a query goes over, the pages used least recently are written back if they changed and
+    /// dropped, until it fits again. The page in use always stays, even if it's bigger than the
+    /// budget on its own. `None`, the default, keeps everything that's been loaded.
+    pub fn set_memory_budget(&mut self, points: Option<usize>) -> io::Result<()> {
+        self.budget = points;
+        self.evict()
+    }
+
+    /// Same as `QuadTree::search`, loading pages as needed.
+    pub fn search(&mut self, boundary: &Boundary<T>) -> io::Result<Vec<Point<T>>> {
+        let mut found = vec![];
+        for i in self.pages_in(boundary) {
+            found.append(&mut self.load(i)?.search(boundary));
+            self.evict()?;
+        }
+        Ok(found)
+    }
+
+    /// Same as `QuadTree::insert`, loading the page the point goes in.
+    pub fn insert(&mut self, point: Point<T>) -> io::Result<bool> {
+        let i = match self.page_for(&point) {
+            Some(i) => i,
+            None => return Ok(false),
+        };
+        self.load(i)?;
+        let inserted = self.insert_loaded(i, point);
+        self.evict()?;
+        Ok(inserted)
+    }
+
+    /// Same as `QuadTree::remove`, loading the page the point would be in.
+    pub fn remove(&mut self, point: &Point<T>) -> io::Result<bool> {
+        let i = match self.page_for(point) {
+            Some(i) if self.pages[i].size > 0 => i,
+            _ => return Ok(false),
+        };
+        self.load(i)?;
+        let removed = self.remove_loaded(i, point);
+        self.evict()?;
+        Ok(removed)
+    }
+
+    /// The pages with points that `boundary` touches.
+    pub(crate) fn pages_in(&self, boundary: &Boundary<T>) -> Vec<usize> {
+        (0..self.pages.len())
+            .filter(|&i| {
+                let page = &self.pages[i];
+                page.size > 0 && QuadTree::intersects(&page.boundary, boundary)
+            })
+            .collect()
+    }
+
+    pub(crate) fn page_for(&self, point: &Point<T>) -> Option<usize> {
+        self.pages
+            .iter()
+            .position(|page| QuadTree::contains(&page.boundary, point))
+    }
+
+    /// Inserts into page `i`, which has to be loaded. Its size is worked out from the tree, as
+    /// inserting a point that's already there changes nothing.
+    pub(crate) fn insert_loaded(&mut self, i: usize, point: Point<T>) -> bool {
+        let page = &mut self.pages[i];
+        let tree = page.tree.as_mut().expect("A loaded page");
+        let inserted = tree.insert(point);
+        if tree.size() != page.size {
+            page.size = tree.size();
+            page.dirty = true;
+        }
+        inserted
+    }
+
+    pub(crate) fn remove_loaded(&mut self, i: usize, point: &Point<T>) -> bool {
+        let page = &mut self.pages[i];
+        let tree = page.tree.as_mut().expect("A loaded page");
+        let removed = tree.remove(point);
+        if tree.size() != page.size {
+            page.size = tree.size();
+            page.dirty = true;
+        }
+        removed
+    }
+
+    fn load(&mut self, i: usize) -> io::Result<&mut QuadTree<T>> {
+        self.touch(i);
+        self.pages[i].load()
+    }
+
+    pub(crate) fn touch(&mut self, i: usize) {
+        self.clock += 1;
+        self.pages[i].last_used = self.clock;
+    }
+
+    /// Drops the least recently used pages until what's loaded fits in the budget.
+    fn evict(&mut self) -> io::Result<()> {
+        for i in self.to_evict() {
+            self.pages[i].flush()?;
+            self.pages[i].tree = None;
+        }
+        Ok(())
+    }
+
+    /// The pages `evict` drops, oldest first.
+    pub(crate) fn to_evict(&self) -> Vec<usize> {
+        let budget = match self.budget {
+            Some(budget) => budget,
+            None => return vec![],
+        };
+        let mut loaded: Vec<usize> = (0..self.pages.len())
+            .filter(|&i| self.pages[i].tree.is_some())
+            .collect();
+        loaded.sort_by_key(|&i| self.pages[i].last_used);
+        let mut points: usize = loaded.iter().map(|&i| self.pages[i].size).sum();
+        let newest = loaded.len().saturating_sub(1);
+        let mut out = vec![];
+        for i in loaded.into_iter().take(newest) {
+            if points <= budget {
+                break;
+            }
+            points -= self.pages[i].size;
+            out.push(i);
+        }
+        out
+    }
+
+    /// Writes changed pages back to their files. They stay in memory.
+    pub fn flush(&mut self) -> io::Result<()> {
+        for page in self.pages.iter_mut() {
+            page.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Writes changed pages back and drops every page from memory.
+    pub fn unload(&mut self) -> io::Result<()> {
+        for page in self.pages.iter_mut() {
+            page.flush()?;
+            page.tree = None;
+        }
+        Ok(())
+    }
+}
+
+impl<T> Page<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    fn load(&mut self) -> io::Result<&mut QuadTree<T>> {
+        if self.tree.is_none() {
+            let bytes = fs::read(&self.path)?;
+            self.tree = Some(read_page(&bytes)?);
+        }
+        Ok(self.tree.as_mut().expect("It was just loaded"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let (true, Some(tree)) = (self.dirty, &self.tree) {
+            fs::write(&self.path, tree.to_bytes())?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialOrd + Copy + Midpoint> Drop for PagedQuadTree<T> {
+    fn drop(&mut self) {
+        for page in self.pages.iter() {
+            let _ = fs::remove_file(&page.path);
+        }
+    }
+}
+
+pub(crate) fn read_page<T>(bytes: &[u8]) -> io::Result<QuadTree<T>>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    QuadTree::from_bytes(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn cut<T>(tree: QuadTree<T>, depth: usize, out: &mut Vec<QuadTree<T>>)
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    match tree {
+        QuadTree::Node(_, _, _, children) if depth > 0 => {
+            for child in *children {
+                cut(child, depth - 1, out);
+            }
+        }
+        tree => out.push(tree),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PagedQuadTree;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn loads_pages_on_demand() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 2000, 0, 2000));
+        for _ in 0..2000 {
+            qt.insert((rng.next() as u32, rng.next() as u32));
+        }
+        let dir = std::env::temp_dir();
+        let mut paged = PagedQuadTree::create(qt.clone(), 2, &dir).unwrap();
+        assert_eq!(paged.size(), qt.size());
+        assert_eq!(paged.loaded(), 0);
+
+        let query = (0, 200, 0, 200);
+        let mut a = paged.search(&query).unwrap();
+        let mut b = qt.search(&query);
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert_eq!(paged.loaded(), 1);
+
+        // Nothing from the generator lands out here.
+        assert!(paged.insert((1500, 1500)).unwrap());
+        assert!(paged.insert((1500, 1500)).unwrap());
+        assert!(paged.remove(&b[0]).unwrap());
+        paged.unload().unwrap();
+        assert_eq!(paged.loaded(), 0);
+        qt.insert((1500, 1500));
+        qt.remove(&b[0]);
+        let mut a = paged.search(&(0, 2000, 0, 2000)).unwrap();
+        let mut b = qt.search(&(0, 2000, 0, 2000));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert_eq!(paged.size(), qt.size());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut qt = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for x in 0..100 {
+            for y in 0..100 {
+                qt.insert((x, y));
+            }
+        }
+        let dir = std::env::temp_dir();
+        let mut paged = PagedQuadTree::create(qt, 1, &dir).unwrap();
+        paged.set_memory_budget(Some(5000)).unwrap();
+
+        // Each quadrant holds 2500 points, so two fit.
+        let top_left = (0, 10, 0, 10);
+        let bot_left = (0, 10, 60, 70);
+        let top_right = (60, 70, 0, 10);
+        paged.search(&top_left).unwrap();
+        paged.search(&bot_left).unwrap();
+        assert!(paged.remove(&(1, 1)).unwrap());
+        assert_eq!(paged.loaded(), 2);
+
+        // Bottom left is the least recently used one now.
+        paged.search(&top_right).unwrap();
+        assert_eq!(paged.loaded(), 2);
+        assert!(paged.pages[1].tree.is_none());
+        assert!(paged.pages[0].tree.is_some());
+
+        // The removal made it to disk when the top left page was pushed out.
+        paged.set_memory_budget(Some(0)).unwrap();
+        assert_eq!(paged.loaded(), 1);
+        assert_eq!(paged.search(&top_left).unwrap().len(), 99);
+        assert_eq!(paged.size(), 9999);
+    }
+}
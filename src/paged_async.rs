@@ -0,0 +1,158 @@
+use crate::format::FixedBytes;
+use crate::paged::{read_page, PagedQuadTree};
+use crate::{Boundary, Midpoint, Point, QuadTree};
+use std::io;
+use std::path::Path;
+
+/// `PagedQuadTree` for async code, like a web service backed by an index bigger than its memory.
+/// Pages are read and written with tokio's file functions, so a query waiting on the disk lets
+/// other tasks get on. Needs the `tokio` feature, and has to be used inside a tokio runtime.
+#[derive(Debug)]
+pub struct AsyncPagedQuadTree<T: PartialOrd + Copy + Midpoint>(PagedQuadTree<T>);
+
+impl<T> AsyncPagedQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Same as `PagedQuadTree::create`.
+    pub async fn create(tree: QuadTree<T>, depth: usize, dir: &Path) -> io::Result<Self> {
+        let mut paged = AsyncPagedQuadTree(PagedQuadTree::cut_into_pages(tree, depth, dir));
+        paged.unload().await?;
+        Ok(paged)
+    }
+
+    pub fn get_boundary(&self) -> Boundary<T> {
+        self.0.get_boundary()
+    }
+
+    pub fn size(&self) -> usize {
+        self.0.size()
+    }
+
+    /// How many pages are in memory right now.
+    pub fn loaded(&self) -> usize {
+        self.0.loaded()
+    }
+
+    /// Same as `PagedQuadTree::set_memory_budget`.
+    pub async fn set_memory_budget(&mut self, points: Option<usize>) -> io::Result<()> {
+        self.0.budget = points;
+        self.evict().await
+    }
+
+    pub async fn search(&mut self, boundary: &Boundary<T>) -> io::Result<Vec<Point<T>>> {
+        let mut found = vec![];
+        for i in self.0.pages_in(boundary) {
+            found.append(&mut self.load(i).await?.search(boundary));
+            self.evict().await?;
+        }
+        Ok(found)
+    }
+
+    pub async fn insert(&mut self, point: Point<T>) -> io::Result<bool> {
+        let i = match self.0.page_for(&point) {
+            Some(i) => i,
+            None => return Ok(false),
+        };
+        self.load(i).await?;
+        let inserted = self.0.insert_loaded(i, point);
+        self.evict().await?;
+        Ok(inserted)
+    }
+
+    pub async fn remove(&mut self, point: &Point<T>) -> io::Result<bool> {
+        let i = match self.0.page_for(point) {
+            Some(i) if self.0.pages[i].size > 0 => i,
+            _ => return Ok(false),
+        };
+        self.load(i).await?;
+        let removed = self.0.remove_loaded(i, point);
+        self.evict().await?;
+        Ok(removed)
+    }
+
+    /// Writes changed pages back to their files. They stay in memory.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        for i in 0..self.0.pages.len() {
+            self.flush_page(i).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes changed pages back and drops every page from memory.
+    pub async fn unload(&mut self) -> io::Result<()> {
+        for i in 0..self.0.pages.len() {
+            self.flush_page(i).await?;
+            self.0.pages[i].tree = None;
+        }
+        Ok(())
+    }
+
+    async fn load(&mut self, i: usize) -> io::Result<&mut QuadTree<T>> {
+        self.0.touch(i);
+        let page = &mut self.0.pages[i];
+        if page.tree.is_none() {
+            let bytes = tokio::fs::read(&page.path).await?;
+            page.tree = Some(read_page(&bytes)?);
+        }
+        Ok(page.tree.as_mut().expect("It was just loaded"))
+    }
+
+    async fn evict(&mut self) -> io::Result<()> {
+        for i in self.0.to_evict() {
+            self.flush_page(i).await?;
+            self.0.pages[i].tree = None;
+        }
+        Ok(())
+    }
+
+    async fn flush_page(&mut self, i: usize) -> io::Result<()> {
+        let page = &mut self.0.pages[i];
+        if let (true, Some(tree)) = (page.dirty, &page.tree) {
+            tokio::fs::write(&page.path, tree.to_bytes()).await?;
+            page.dirty = false;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsyncPagedQuadTree;
+    use crate::QuadTree as Q;
+
+    #[tokio::test]
+    async fn async_paged() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 2000, 0, 2000));
+        for _ in 0..2000 {
+            qt.insert((rng.next() as u32, rng.next() as u32));
+        }
+        let dir = std::env::temp_dir();
+        let mut paged = AsyncPagedQuadTree::create(qt.clone(), 2, &dir)
+            .await
+            .unwrap();
+        assert_eq!(paged.loaded(), 0);
+        paged.set_memory_budget(Some(0)).await.unwrap();
+
+        let query = (0, 200, 0, 200);
+        let mut a = paged.search(&query).await.unwrap();
+        let mut b = qt.search(&query);
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        assert!(paged.insert((1500, 1500)).await.unwrap());
+        assert!(paged.insert((1500, 1500)).await.unwrap());
+        assert!(paged.remove(&b[0]).await.unwrap());
+        qt.insert((1500, 1500));
+        qt.remove(&b[0]);
+        let mut a = paged.search(&(0, 2000, 0, 2000)).await.unwrap();
+        let mut b = qt.search(&(0, 2000, 0, 2000));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert_eq!(paged.size(), qt.size());
+        assert_eq!(paged.loaded(), 1);
+    }
+}
@@ -0,0 +1,294 @@
+use crate::geom::BoundaryExt;
+use crate::{Boundary, Distance, Midpoint, NextUp, Point, QuadTree};
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::iter::{
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+    IntoParallelRefMutIterator, ParallelExtend, ParallelIterator,
+};
+
+/// Every leaf with the points in it, in parallel. Made with `QuadTree::par_leaves`.
+#[derive(Debug, Clone)]
+pub struct ParLeaves<'a, T: PartialOrd + Copy + Midpoint> {
+    root: &'a QuadTree<T>,
+}
+
+/// Every point in the tree, in parallel. Made with `QuadTree::par_points`.
+#[derive(Debug, Clone)]
+pub struct ParPoints<'a, T: PartialOrd + Copy + Midpoint> {
+    leaves: ParLeaves<'a, T>,
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Send + Sync,
+{
+    /// Same as `leaves`, but as a rayon `ParallelIterator`. The work is split up along the
+    /// tree's nodes, so a tree with only a root leaf won't be spread over threads.
+    pub fn par_leaves(&self) -> ParLeaves<'_, T> {
+        ParLeaves { root: self }
+    }
+
+    pub fn par_points(&self) -> ParPoints<'_, T> {
+        ParPoints {
+            leaves: self.par_leaves(),
+        }
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance + Send + Sync,
+{
+    /// `knn` for each of `queries`, run in parallel. The answers are in the same order as the
+    /// queries. The tree is shared between the threads and each reuses its scratch space from one
+    /// query to the next.
+    pub fn knn_batch(&self, queries: &[Point<T>], k: usize) -> Vec<Vec<Point<T>>> {
+        queries
+            .par_iter()
+            .map_init(
+                || Vec::with_capacity(k + 1),
+                |best, query| {
+                    best.clear();
+                    if k > 0 {
+                        self.knn_into(query, k, best);
+                    }
+                    best.iter().map(|(_, p)| *p).collect()
+                },
+            )
+            .collect()
+    }
+}
+
+/// Adds the points the way `insert` would, each quadrant of the root on its own thread. Points
+/// outside the tree are left out.
+impl<T> ParallelExtend<Point<T>> for QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Send + Sync,
+{
+    fn par_extend<I>(&mut self, points: I)
+    where
+        I: IntoParallelIterator<Item = Point<T>>,
+    {
+        let points: Vec<_> = points
+            .into_par_iter()
+            .map(|point| self.snap(point))
+            .filter(|point| Self::contains(self.get_boundary(), point))
+            .collect();
+
+        let config = self.get_config();
+        let fits = match &*self {
+            QuadTree::Leaf(_, _, leaf) => leaf.len() + points.len() <= config.capacity,
+            QuadTree::Node(_, _, _, _) => false,
+        };
+        // A near duplicate can be in the quadrant next door, so with a tolerance every point has
+        // to look at the whole tree.
+        if fits || config.tolerance.is_some() || config.max_depth == Some(0) {
+            for point in points {
+                self.insert(point);
+            }
+            return;
+        }
+
+        self.subdivide(0);
+        if let QuadTree::Node(_, boundary, size, children) = self {
+            let mut shards: [Vec<Point<T>>; 4] = Default::default();
+            for point in points {
+                shards[Self::quadrant_of(boundary, &point)].push(point);
+            }
+            children
+                .par_iter_mut()
+                .zip(shards)
+                .for_each(|(child, shard)| {
+                    for point in shard {
+                        child.insert_contained(point, true, 1, &mut ());
+                    }
+                });
+            *size = children.iter().map(|child| child.size()).sum();
+        }
+        // Duplicates can leave it with too few points to be split up.
+        self.merge_if_small(0, &mut ());
+    }
+}
+
+/// Builds a tree just big enough to hold the points, like `from_points_autofit`. With no points
+/// the tree is as small as it can be, around the origin.
+impl<T> FromParallelIterator<Point<T>> for QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + NextUp + Default + Send + Sync,
+{
+    fn from_par_iter<I>(points: I) -> Self
+    where
+        I: IntoParallelIterator<Item = Point<T>>,
+    {
+        let points: Vec<_> = points.into_par_iter().collect();
+        // The smallest boundary there is when there's nothing to fit.
+        let boundary = Boundary::bounding(points.iter().copied())
+            .or_else(|| Boundary::bounding(Some((T::default(), T::default()))))
+            .expect("one point");
+        let mut qt = QuadTree::new(boundary);
+        qt.par_extend(points);
+        qt
+    }
+}
+
+impl<'a, T> ParallelIterator for ParLeaves<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint + Send + Sync,
+{
+    type Item = (Boundary<T>, &'a [Point<T>]);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(
+            LeafProducer {
+                nodes: vec![self.root],
+            },
+            consumer,
+        )
+    }
+}
+
+impl<'a, T> ParallelIterator for ParPoints<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint + Send + Sync,
+{
+    type Item = &'a Point<T>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        self.leaves
+            .flat_map_iter(|(_, points)| points.iter())
+            .drive_unindexed(consumer)
+    }
+}
+
+/// Some subtrees, in order. Splits by handing over half of them, or a node's children when it's
+/// down to one.
+struct LeafProducer<'a, T: PartialOrd + Copy + Midpoint> {
+    nodes: Vec<&'a QuadTree<T>>,
+}
+
+impl<'a, T> UnindexedProducer for LeafProducer<'a, T>
+where
+    T: PartialOrd + Copy + Midpoint + Send + Sync,
+{
+    type Item = (Boundary<T>, &'a [Point<T>]);
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if let [QuadTree::Node(_, _, _, children)] = self.nodes[..] {
+            self.nodes = children.iter().collect();
+        }
+        if self.nodes.len() < 2 {
+            return (self, None);
+        }
+        let other = self.nodes.split_off(self.nodes.len() / 2);
+        (self, Some(LeafProducer { nodes: other }))
+    }
+
+    fn fold_with<F>(self, mut folder: F) -> F
+    where
+        F: Folder<Self::Item>,
+    {
+        for node in self.nodes {
+            folder = folder.consume_iter(node.leaves());
+            if folder.full() {
+                break;
+            }
+        }
+        folder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+    use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+    #[test]
+    fn par_points() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..5000 {
+            qt.insert((rng.next(), rng.next()));
+        }
+        let mut points: Vec<_> = qt.par_points().copied().collect();
+        let mut expected = qt.search(&(0, 1000, 0, 1000));
+        points.sort();
+        expected.sort();
+        assert_eq!(points, expected);
+        let sum: u64 = qt.par_points().map(|p| p.0).sum();
+        assert_eq!(sum, expected.iter().map(|p| p.0).sum::<u64>());
+    }
+
+    #[test]
+    fn knn_batch() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0.0, 1000.0, 0.0, 1000.0));
+        for _ in 0..2000 {
+            qt.insert((rng.next() as f64, rng.next() as f64));
+        }
+        let queries: Vec<_> = (0..200)
+            .map(|_| (rng.next() as f64, rng.next() as f64))
+            .collect();
+        let answers = qt.knn_batch(&queries, 5);
+        assert_eq!(answers.len(), queries.len());
+        for (query, answer) in queries.iter().zip(answers) {
+            assert_eq!(answer, qt.knn(query, 5));
+        }
+        assert!(qt.knn_batch(&queries, 0).iter().all(|a| a.is_empty()));
+    }
+
+    #[test]
+    fn par_leaves() {
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        qt.insert((1, 1));
+        qt.insert((3, 3));
+        qt.insert((7, 7));
+        let leaves: Vec<_> = qt.par_leaves().map(|(b, p)| (b, p.to_vec())).collect();
+        let expected: Vec<_> = qt.leaves().map(|(b, p)| (b, p.to_vec())).collect();
+        assert_eq!(leaves, expected);
+    }
+
+    #[test]
+    fn par_extend() {
+        let mut rng = crate::tests::get_rng();
+        let points: Vec<_> = (0..5000).map(|_| (rng.next(), rng.next())).collect();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut expected = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        qt.insert((1, 1));
+        expected.insert((1, 1));
+        qt.par_extend(
+            points
+                .clone()
+                .into_par_iter()
+                .chain(vec![(1000, 0), (1, 1)]),
+        );
+        for point in &points {
+            expected.insert(*point);
+        }
+        assert_eq!(qt.size(), expected.size());
+        let mut found = qt.search(&(0, 1000, 0, 1000));
+        let mut wanted = expected.search(&(0, 1000, 0, 1000));
+        found.sort();
+        wanted.sort();
+        assert_eq!(found, wanted);
+
+        let collected: Q<u64> = points.clone().into_par_iter().collect();
+        assert_eq!(collected.size(), expected.size() - 1);
+        assert!(points
+            .iter()
+            .all(|p| Q::contains(collected.get_boundary(), p)));
+
+        // All duplicates, so the root splits and merges again.
+        let mut qt = Q::with_node_capacity(4, (0, 10, 0, 10));
+        qt.par_extend(vec![(5, 5); 100]);
+        assert!(matches!(qt, Q::Leaf(_, _, _)));
+        assert_eq!(qt.size(), 1);
+        let empty: Q<u64> = Vec::new().into_par_iter().collect();
+        assert_eq!(empty.size(), 0);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{Distance, Midpoint, Point, QuadTree};
+use plotters::coord::Shift;
+use plotters::drawing::{DrawingArea, DrawingAreaErrorKind};
+use plotters::prelude::{Circle, DrawingBackend, Rectangle, BLACK, RED};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// Draws the outline of every leaf, and every point as a dot, stretched to fill `area`. Low
+    /// `y` is at the top, same as in the tree. Needs the `plotters` feature.
+    pub fn draw<DB: DrawingBackend>(
+        &self,
+        area: &DrawingArea<DB, Shift>,
+    ) -> Result<(), DrawingAreaErrorKind<DB::ErrorType>> {
+        let (x1, x2, y1, y2) = *self.get_boundary();
+        let (width, height) = area.dim_in_pixel();
+        let (width, height) = (
+            width.saturating_sub(1) as f64,
+            height.saturating_sub(1) as f64,
+        );
+        let scale_x = width / x1.distance(x2);
+        let scale_y = height / y1.distance(y2);
+        let pixel = |(x, y): &Point<T>| {
+            (
+                (x.distance(x1) * scale_x).round() as i32,
+                (y.distance(y1) * scale_y).round() as i32,
+            )
+        };
+
+        for (boundary, points) in self.leaves() {
+            let (bx1, bx2, by1, by2) = boundary;
+            let corners = [pixel(&(bx1, by1)), pixel(&(bx2, by2))];
+            area.draw(&Rectangle::new(corners, BLACK))?;
+            for point in points {
+                area.draw(&Circle::new(pixel(point), 1, RED))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+    use plotters::prelude::*;
+    use plotters_backend::{BackendColor, BackendCoord, DrawingErrorKind};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    type Colours = HashMap<BackendCoord, (u8, u8, u8)>;
+
+    /// Remembers the colour of every pixel drawn.
+    #[derive(Clone, Default)]
+    struct Pixels(Rc<RefCell<Colours>>);
+
+    impl DrawingBackend for Pixels {
+        type ErrorType = std::io::Error;
+
+        fn get_size(&self) -> (u32, u32) {
+            (101, 101)
+        }
+
+        fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<std::io::Error>> {
+            Ok(())
+        }
+
+        fn present(&mut self) -> Result<(), DrawingErrorKind<std::io::Error>> {
+            Ok(())
+        }
+
+        fn draw_pixel(
+            &mut self,
+            point: BackendCoord,
+            color: BackendColor,
+        ) -> Result<(), DrawingErrorKind<std::io::Error>> {
+            self.0.borrow_mut().insert(point, color.rgb);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn draw() {
+        let mut qt = Q::with_node_capacity(1, (0, 1000, 0, 1000));
+        qt.insert((100, 700));
+        qt.insert((900, 900));
+
+        let pixels = Pixels::default();
+        qt.draw(&pixels.clone().into_drawing_area()).unwrap();
+        let pixels = pixels.0.borrow();
+        let black = (0, 0, 0);
+        let red = (255, 0, 0);
+        // The outline of the root, and the seams between its children.
+        assert_eq!(pixels.get(&(0, 37)), Some(&black));
+        assert_eq!(pixels.get(&(50, 12)), Some(&black));
+        assert_eq!(pixels.get(&(12, 50)), Some(&black));
+        assert_eq!(pixels.get(&(10, 70)), Some(&red));
+        assert_eq!(pixels.get(&(90, 90)), Some(&red));
+        assert_eq!(pixels.get(&(30, 30)), None);
+    }
+}
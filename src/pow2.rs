@@ -0,0 +1,221 @@
+use crate::{Boundary, Point, QuadTree};
+
+/// A quadtree over `u32` points whose world is `0..2^bits` on both axes. Every cell is a power of
+/// two wide and starts at a multiple of its width, so a cell's boundary never has to be stored or
+/// computed with a midpoint: the child a point goes into is just one bit of each coordinate. Cells
+/// are split exactly in half, with none of the rounding `(a + b) / 2` does on odd spans.
+///
+/// A cell one unit wide holds a single point, so the tree is never more than `bits` deep.
+#[derive(Debug, Clone)]
+pub struct Pow2QuadTree {
+    capacity: usize,
+    bits: u32,
+    root: Cell,
+}
+
+#[derive(Debug, Clone)]
+enum Cell {
+    Leaf(Vec<Point<u32>>),
+    Node(usize, [Box<Cell>; 4]),
+}
+
+impl Pow2QuadTree {
+    /// Panics if `bits` is more than 31.
+    pub fn new(bits: u32) -> Self {
+        Self::with_node_capacity(64, bits)
+    }
+
+    /// Panics if `bits` is more than 31 or `capacity` is 0.
+    pub fn with_node_capacity(capacity: usize, bits: u32) -> Self {
+        assert!(bits <= 31, "the world can be at most 2^31 wide");
+        assert!(capacity > 0, "capacity must be at least 1");
+        Pow2QuadTree {
+            capacity,
+            bits,
+            root: Cell::Leaf(vec![]),
+        }
+    }
+
+    pub fn get_boundary(&self) -> Boundary<u32> {
+        let side = 1 << self.bits;
+        (0, side, 0, side)
+    }
+
+    pub fn insert(&mut self, point: Point<u32>) -> bool {
+        if !self.holds(&point) {
+            return false;
+        }
+        self.root.insert(self.capacity, self.bits, point);
+        true
+    }
+
+    /// Removes a point. Returns whether it was there. Like `QuadTree`, nodes left with no more than
+    /// `capacity` points are merged back into a leaf.
+    pub fn remove(&mut self, point: &Point<u32>) -> bool {
+        self.holds(point) && self.root.remove(self.capacity, self.bits, point)
+    }
+
+    pub fn size(&self) -> usize {
+        match &self.root {
+            Cell::Leaf(points) => points.len(),
+            Cell::Node(size, _) => *size,
+        }
+    }
+
+    pub fn search(&self, boundary: &Boundary<u32>) -> Vec<Point<u32>> {
+        let mut found = vec![];
+        self.root
+            .search_into(self.bits, (0, 0), boundary, &mut found);
+        found
+    }
+
+    fn holds(&self, (x, y): &Point<u32>) -> bool {
+        *x >> self.bits == 0 && *y >> self.bits == 0
+    }
+}
+
+impl Cell {
+    /// Returns true if the point wasn't there yet.
+    fn insert(&mut self, capacity: usize, bits: u32, point: Point<u32>) -> bool {
+        if let Cell::Leaf(points) = self {
+            if points.contains(&point) {
+                return false;
+            }
+            // A cell one unit wide can only ever hold one point, so it never has to split.
+            if points.len() < capacity || bits == 0 {
+                points.push(point);
+                return true;
+            }
+
+            let leaf = || Box::new(Cell::Leaf(vec![]));
+            let mut children = [leaf(), leaf(), leaf(), leaf()];
+            let size = points.len();
+            for p in points.drain(..) {
+                children[child_index(&p, bits)].insert(capacity, bits - 1, p);
+            }
+            *self = Cell::Node(size, children);
+        }
+
+        match self {
+            Cell::Leaf(_) => panic!("We should never be a leaf at this point"),
+            Cell::Node(size, children) => {
+                let pushed = children[child_index(&point, bits)].insert(capacity, bits - 1, point);
+                if pushed {
+                    *size += 1;
+                }
+                pushed
+            }
+        }
+    }
+
+    fn remove(&mut self, capacity: usize, bits: u32, point: &Point<u32>) -> bool {
+        match self {
+            Cell::Leaf(points) => match points.iter().position(|p| p == point) {
+                Some(at) => {
+                    points.swap_remove(at);
+                    true
+                }
+                None => false,
+            },
+            Cell::Node(size, children) => {
+                if !children[child_index(point, bits)].remove(capacity, bits - 1, point) {
+                    return false;
+                }
+                *size -= 1;
+                let all_leaves = children.iter().all(|c| matches!(**c, Cell::Leaf(_)));
+                if all_leaves && *size <= capacity {
+                    let mut points = vec![];
+                    for child in children.iter_mut() {
+                        if let Cell::Leaf(child_points) = &mut **child {
+                            points.append(child_points);
+                        }
+                    }
+                    *self = Cell::Leaf(points);
+                }
+                true
+            }
+        }
+    }
+
+    /// `(x, y)` is the corner of this cell, which is `2^bits` wide.
+    fn search_into(
+        &self,
+        bits: u32,
+        (x, y): Point<u32>,
+        boundary: &Boundary<u32>,
+        found: &mut Vec<Point<u32>>,
+    ) {
+        let (x1, x2, y1, y2) = *boundary;
+        let side = 1u64 << bits;
+        let overlaps =
+            x < x2 && x as u64 + side > x1 as u64 && y < y2 && y as u64 + side > y1 as u64;
+        if !overlaps {
+            return;
+        }
+        match self {
+            Cell::Leaf(points) => {
+                found.extend(points.iter().filter(|p| QuadTree::contains(boundary, p)))
+            }
+            Cell::Node(_, children) => {
+                let half = 1 << (bits - 1);
+                let origins = [(x, y), (x, y + half), (x + half, y), (x + half, y + half)];
+                for (child, origin) in children.iter().zip(origins) {
+                    child.search_into(bits - 1, origin, boundary, found);
+                }
+            }
+        }
+    }
+}
+
+/// Which child of a cell `2^bits` wide the point goes into, in the same order as the children of
+/// a `QuadTree` node.
+fn child_index((x, y): &Point<u32>, bits: u32) -> usize {
+    let bit = 1 << (bits - 1);
+    ((x & bit != 0) as usize) << 1 | (y & bit != 0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pow2QuadTree;
+    use crate::QuadTree;
+
+    #[test]
+    fn same_as_quadtree() {
+        let mut qt = Pow2QuadTree::with_node_capacity(4, 10);
+        let mut reference = QuadTree::with_node_capacity(4, qt.get_boundary());
+        let mut rng = crate::tests::get_rng();
+        for _ in 0..2000 {
+            let p = (rng.next() as u32, rng.next() as u32);
+            assert_eq!(qt.insert(p), reference.insert(p));
+        }
+        assert!(!qt.insert((1024, 0)));
+        assert_eq!(qt.size(), reference.size());
+
+        for query in [(0, 1024, 0, 1024), (3, 517, 100, 101), (999, 1000, 0, 1000)] {
+            let mut a = qt.search(&query);
+            let mut b = reference.search(&query);
+            a.sort();
+            b.sort();
+            assert_eq!(a, b);
+        }
+
+        for p in reference.search(&(0, 512, 0, 1024)) {
+            assert!(qt.remove(&p));
+        }
+        assert!(!qt.remove(&(1, 1)));
+        assert_eq!(qt.search(&(0, 512, 0, 1024)), vec![]);
+    }
+
+    #[test]
+    fn unit_cells() {
+        // With a capacity of 1 the points end up in cells one unit wide.
+        let mut qt = Pow2QuadTree::with_node_capacity(1, 3);
+        for x in 0..8 {
+            for y in 0..8 {
+                qt.insert((x, y));
+            }
+        }
+        assert_eq!(qt.size(), 64);
+        assert_eq!(qt.search(&(3, 5, 7, 8)), vec![(3, 7), (4, 7)]);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{Boundary, CellInfo, Midpoint, Point, QuadTree};
+use std::collections::VecDeque;
+
+/// One step of `QuadTree::progressive`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Refinement<T> {
+    /// A node or leaf, with the number of points below it. Its children come later.
+    Cell(CellInfo<T>),
+    /// A point, once the leaf it's in has been handed out.
+    Point(Point<T>),
+}
+
+enum Pending<'a, T: PartialOrd + Copy + Midpoint> {
+    Tree(&'a QuadTree<T>, usize),
+    Point(Point<T>),
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Goes over the part of the tree inside `boundary` coarse to fine: the root first, then the
+    /// nodes one level down, and so on, breadth first. A leaf's points come right after everything
+    /// at its depth. A UI can draw the cells as shaded blocks straight away and fill in the
+    /// details for as long as it has time, stopping whenever it likes.
+    pub fn progressive<'a>(
+        &'a self,
+        boundary: &'a Boundary<T>,
+    ) -> impl Iterator<Item = Refinement<T>> + 'a {
+        let mut queue = VecDeque::new();
+        queue.push_back(Pending::Tree(self, 0));
+        std::iter::from_fn(move || loop {
+            match queue.pop_front()? {
+                Pending::Point(p) => return Some(Refinement::Point(p)),
+                Pending::Tree(tree, depth) => {
+                    let own = tree.get_boundary();
+                    if tree.size() == 0 || !Self::intersects(own, boundary) {
+                        continue;
+                    }
+                    match tree {
+                        QuadTree::Leaf(_, _, points) => queue.extend(
+                            points
+                                .iter()
+                                .filter(|p| Self::contains(boundary, p))
+                                .map(|p| Pending::Point(*p)),
+                        ),
+                        QuadTree::Node(_, _, _, children) => queue
+                            .extend(children.iter().map(|child| Pending::Tree(child, depth + 1))),
+                    }
+                    return Some(Refinement::Cell(CellInfo {
+                        boundary: *own,
+                        depth,
+                        size: tree.size(),
+                    }));
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Refinement;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn progressive() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        for _ in 0..500 {
+            qt.insert((rng.next(), rng.next()));
+        }
+        let view = (0, 500, 0, 1000);
+        let steps: Vec<_> = qt.progressive(&view).collect();
+        assert_eq!(
+            steps[0],
+            Refinement::Cell(crate::CellInfo {
+                boundary: (0, 1000, 0, 1000),
+                depth: 0,
+                size: qt.size(),
+            })
+        );
+
+        // Every point in view comes out once.
+        let mut points: Vec<_> = steps
+            .iter()
+            .filter_map(|step| match step {
+                Refinement::Point(p) => Some(*p),
+                Refinement::Cell(_) => None,
+            })
+            .collect();
+        points.sort();
+        let mut expected = qt.search(&view);
+        expected.sort();
+        assert_eq!(points, expected);
+
+        // Cells only ever get deeper, and the first point comes after the top levels.
+        let depths: Vec<_> = steps
+            .iter()
+            .filter_map(|step| match step {
+                Refinement::Cell(cell) => Some(cell.depth),
+                Refinement::Point(_) => None,
+            })
+            .collect();
+        assert!(depths.windows(2).all(|w| w[0] <= w[1]));
+        let first_point = steps
+            .iter()
+            .position(|step| matches!(step, Refinement::Point(_)))
+            .unwrap();
+        assert!(steps[..first_point].len() > 3);
+        assert!(qt.progressive(&(2000, 3000, 0, 10)).next().is_none());
+    }
+}
@@ -0,0 +1,86 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// Bing style quadkey digits are 0 for top left, 1 for top right, 2 for bottom left and 3 for
+/// bottom right, where "top" is the low `y` half. Our children are stored top left, bottom left,
+/// top right, bottom right, so this maps a digit to a child index.
+const DIGIT_TO_CHILD: [usize; 4] = [0, 2, 1, 3];
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// The quadkey of the cell at `level` that holds `point`, found by splitting the boundary of the
+    /// tree `level` times. This doesn't depend on how the tree happens to be subdivided, so keys
+    /// line up with tiles in other systems that split the same extent. `None` if the point is
+    /// outside the tree.
+    pub fn quadkey_of(&self, point: &Point<T>, level: usize) -> Option<String> {
+        let mut boundary = *self.get_boundary();
+        if !Self::contains(&boundary, point) {
+            return None;
+        }
+        let mut key = String::with_capacity(level);
+        for _ in 0..level {
+            let children = Self::quadrants(&boundary);
+            let digit = (0..4)
+                .find(|d| Self::contains(&children[DIGIT_TO_CHILD[*d]], point))
+                .expect("A point inside a boundary is inside one of its quadrants");
+            key.push((b'0' + digit as u8) as char);
+            boundary = children[DIGIT_TO_CHILD[digit]];
+        }
+        Some(key)
+    }
+
+    /// The quadkey of the leaf that `point` falls in. Its length is the depth of the leaf.
+    pub fn cell_quadkey(&self, point: &Point<T>) -> Option<String> {
+        if !Self::contains(self.get_boundary(), point) {
+            return None;
+        }
+        let mut key = String::new();
+        let mut node = self;
+        while let QuadTree::Node(_, _, _, children) = node {
+            let digit = (0..4)
+                .find(|d| Self::contains(children[DIGIT_TO_CHILD[*d]].get_boundary(), point))
+                .expect("A point inside a node is inside one of its children");
+            key.push((b'0' + digit as u8) as char);
+            node = &children[DIGIT_TO_CHILD[digit]];
+        }
+        Some(key)
+    }
+
+    /// The boundary of the cell a quadkey points to. `None` if the key has anything but the
+    /// digits 0 to 3 in it.
+    pub fn quadkey_boundary(&self, key: &str) -> Option<Boundary<T>> {
+        let mut boundary = *self.get_boundary();
+        for c in key.chars() {
+            let digit = c.to_digit(4)? as usize;
+            boundary = Self::quadrants(&boundary)[DIGIT_TO_CHILD[digit]];
+        }
+        Some(boundary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn quadkeys() {
+        let mut qt = Q::with_node_capacity(1, (0, 8, 0, 8));
+        assert_eq!(qt.quadkey_of(&(1, 1), 0), Some("".to_string()));
+        assert_eq!(qt.quadkey_of(&(1, 1), 3), Some("003".to_string()));
+        assert_eq!(qt.quadkey_of(&(7, 0), 2), Some("11".to_string()));
+        assert_eq!(qt.quadkey_of(&(0, 7), 2), Some("22".to_string()));
+        assert_eq!(qt.quadkey_of(&(5, 6), 3), Some("321".to_string()));
+        assert_eq!(qt.quadkey_of(&(8, 6), 3), None);
+
+        assert_eq!(qt.quadkey_boundary("321"), Some((5, 6, 6, 7)));
+        assert_eq!(qt.quadkey_boundary(""), Some((0, 8, 0, 8)));
+        assert_eq!(qt.quadkey_boundary("4"), None);
+
+        assert_eq!(qt.cell_quadkey(&(1, 1)), Some("".to_string()));
+        qt.insert((1, 1));
+        qt.insert((7, 1));
+        assert_eq!(qt.cell_quadkey(&(1, 1)), Some("0".to_string()));
+        assert_eq!(qt.cell_quadkey(&(6, 6)), Some("3".to_string()));
+    }
+}
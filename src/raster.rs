@@ -0,0 +1,200 @@
+use crate::{Boundary, QuadTree};
+use image::{GrayImage, Luma};
+
+/// A region quadtree over the pixels of a grayscale image. Blocks that are close enough to one
+/// colour are kept as a single cell with their average, so big flat areas cost next to nothing.
+/// Needs the `image` feature.
+#[derive(Debug, Clone)]
+pub struct RegionQuadTree {
+    root: Cell,
+}
+
+#[derive(Debug, Clone)]
+enum Cell {
+    Leaf(Boundary<u32>, u8),
+    Node(Boundary<u32>, Box<[Cell; 4]>),
+}
+
+/// The darkest and lightest pixel of a block, and the sum and number of its pixels.
+#[derive(Clone, Copy)]
+struct Stats {
+    min: u8,
+    max: u8,
+    sum: u64,
+    count: u64,
+}
+
+impl RegionQuadTree {
+    /// Splits the image into quarters until each block has no two pixels more than `threshold`
+    /// apart. 0 only merges blocks of exactly one colour, so `to_image` gives the image back as
+    /// it was. Higher is lossy, but takes fewer cells.
+    pub fn from_image(image: &GrayImage, threshold: u8) -> Self {
+        let (width, height) = image.dimensions();
+        let (root, _) = Cell::build(image, (0, width, 0, height), threshold);
+        RegionQuadTree { root }
+    }
+
+    /// `(0, width, 0, height)` of the image.
+    pub fn get_boundary(&self) -> &Boundary<u32> {
+        self.root.boundary()
+    }
+
+    /// The value of the cell that pixel `(x, y)` is in. `None` outside the image.
+    pub fn get(&self, x: u32, y: u32) -> Option<u8> {
+        if !QuadTree::contains(self.get_boundary(), &(x, y)) {
+            return None;
+        }
+        let mut cell = &self.root;
+        loop {
+            match cell {
+                Cell::Leaf(_, value) => return Some(*value),
+                Cell::Node(boundary, children) => {
+                    cell = &children[QuadTree::quadrant_of(boundary, &(x, y))];
+                }
+            }
+        }
+    }
+
+    /// Every cell with its value, top left first. Images whose sides aren't a power of two split
+    /// into some blocks with no pixels, and those are left out.
+    pub fn cells(&self) -> Vec<(Boundary<u32>, u8)> {
+        let mut cells = vec![];
+        let mut stack = vec![&self.root];
+        while let Some(cell) = stack.pop() {
+            match cell {
+                Cell::Leaf((x1, x2, y1, y2), value) => {
+                    if x1 < x2 && y1 < y2 {
+                        cells.push(((*x1, *x2, *y1, *y2), *value));
+                    }
+                }
+                Cell::Node(_, children) => stack.extend(children.iter().rev()),
+            }
+        }
+        cells
+    }
+
+    /// Draws every cell in its value.
+    pub fn to_image(&self) -> GrayImage {
+        let (_, width, _, height) = *self.get_boundary();
+        let mut image = GrayImage::new(width, height);
+        for ((x1, x2, y1, y2), value) in self.cells() {
+            for y in y1..y2 {
+                for x in x1..x2 {
+                    image.put_pixel(x, y, Luma([value]));
+                }
+            }
+        }
+        image
+    }
+}
+
+impl Cell {
+    fn build(image: &GrayImage, boundary: Boundary<u32>, threshold: u8) -> (Self, Stats) {
+        let (x1, x2, y1, y2) = boundary;
+        // Sides that aren't a power of two split into some blocks with no pixels.
+        if x1 == x2 || y1 == y2 {
+            let stats = Stats {
+                min: u8::MAX,
+                max: 0,
+                sum: 0,
+                count: 0,
+            };
+            return (Cell::Leaf(boundary, stats.min), stats);
+        }
+        let count = (x2 - x1) as u64 * (y2 - y1) as u64;
+        if count <= 1 {
+            let value = image.get_pixel(x1, y1)[0];
+            let stats = Stats {
+                min: value,
+                max: value,
+                sum: value as u64,
+                count,
+            };
+            return (Cell::Leaf(boundary, value), stats);
+        }
+
+        let [a, b, c, d] =
+            QuadTree::quadrants(&boundary).map(|quarter| Cell::build(image, quarter, threshold));
+        let stats = [a.1, b.1, c.1, d.1]
+            .iter()
+            .fold(None, |total: Option<Stats>, s| match total {
+                _ if s.count == 0 => total,
+                None => Some(*s),
+                Some(t) => Some(Stats {
+                    min: t.min.min(s.min),
+                    max: t.max.max(s.max),
+                    sum: t.sum + s.sum,
+                    count: t.count + s.count,
+                }),
+            })
+            .expect("a block this big has pixels");
+        let children = [a.0, b.0, c.0, d.0];
+        if stats.max - stats.min <= threshold && children.iter().all(Cell::is_leaf) {
+            let mean = (stats.sum + stats.count / 2) / stats.count;
+            (Cell::Leaf(boundary, mean as u8), stats)
+        } else {
+            (Cell::Node(boundary, Box::new(children)), stats)
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        matches!(self, Cell::Leaf(_, _))
+    }
+
+    fn boundary(&self) -> &Boundary<u32> {
+        match self {
+            Cell::Leaf(boundary, _) => boundary,
+            Cell::Node(boundary, _) => boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RegionQuadTree;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn from_image() {
+        // Black with a white square in the top left quarter, and a bit of noise on the right.
+        let mut image = GrayImage::from_pixel(16, 12, Luma([0]));
+        for y in 0..4 {
+            for x in 0..4 {
+                image.put_pixel(x, y, Luma([255]));
+            }
+        }
+        image.put_pixel(12, 9, Luma([3]));
+
+        let exact = RegionQuadTree::from_image(&image, 0);
+        assert_eq!(exact.to_image(), image);
+        assert_eq!(exact.get(1, 1), Some(255));
+        assert_eq!(exact.get(12, 9), Some(3));
+        assert_eq!(exact.get(16, 0), None);
+        let area: u32 = exact
+            .cells()
+            .iter()
+            .map(|((x1, x2, y1, y2), _)| (x2 - x1) * (y2 - y1))
+            .sum();
+        assert_eq!(area, 16 * 12);
+        assert!(exact.cells().len() < 16 * 12 / 4);
+
+        // Letting the noise through takes fewer cells, and it averages out.
+        let lossy = RegionQuadTree::from_image(&image, 4);
+        assert!(lossy.cells().len() < exact.cells().len());
+        assert_eq!(lossy.get(12, 9), Some(0));
+        assert_eq!(lossy.get(1, 1), Some(255));
+    }
+
+    #[test]
+    fn odd_sizes() {
+        let mut rng = crate::tests::get_rng();
+        for (width, height) in [(4, 1), (1, 4), (5, 9), (3, 8), (1, 1), (1, 7), (0, 3)] {
+            let mut image = GrayImage::new(width, height);
+            for pixel in image.pixels_mut() {
+                *pixel = Luma([rng.next() as u8 % 3]);
+            }
+            let tree = RegionQuadTree::from_image(&image, 0);
+            assert_eq!(tree.to_image(), image);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Float coordinates that are careful about where cells are split, for trees whose boundaries go
+//! up to huge values or to infinity.
+use crate::format::FixedBytes;
+use crate::{Distance, Midpoint, NextUp, Snap};
+
+/// A float coordinate with a careful `Midpoint`. The plain one is `(a + b) / 2`, which overflows
+/// to infinity for big enough `a` and `b`. The seam then ends up outside the cell, points keep
+/// going to the same child, and inserting recurses until the stack runs out. This one never
+/// overflows, treats infinite edges as the biggest finite float, and always puts the seam inside
+/// the cell, so every point is classified the same way by inserts and searches. Otherwise it's
+/// the plain float, and is written to files the same way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Robust<F>(pub F);
+
+macro_rules! robust_coordinates {
+    ($f:ty) => {
+        impl Midpoint for Robust<$f> {
+            fn midpoint(&self, a: Self) -> Self {
+                let (low, high) = if self.0 <= a.0 {
+                    (self.0, a.0)
+                } else {
+                    (a.0, self.0)
+                };
+                let (l, h) = (low.max(<$f>::MIN), high.min(<$f>::MAX));
+                let sum = l + h;
+                let middle = if sum.is_finite() {
+                    sum / 2.0
+                } else {
+                    l / 2.0 + h / 2.0
+                };
+                Robust(middle.max(low).min(high))
+            }
+        }
+
+        impl Distance for Robust<$f> {
+            fn distance(&self, a: Self) -> f64 {
+                self.0.distance(a.0)
+            }
+        }
+
+        impl Snap for Robust<$f> {
+            fn snap(&self, resolution: Self) -> Self {
+                Robust(self.0.snap(resolution.0))
+            }
+        }
+
+        impl NextUp for Robust<$f> {
+            fn next_up(&self) -> Self {
+                Robust(NextUp::next_up(&self.0))
+            }
+        }
+
+        impl FixedBytes for Robust<$f> {
+            const TYPE_TAG: u64 = <$f as FixedBytes>::TYPE_TAG;
+
+            fn to_bytes(&self) -> [u8; 8] {
+                self.0.to_bytes()
+            }
+
+            fn from_bytes(bytes: [u8; 8]) -> Self {
+                Robust(<$f>::from_bytes(bytes))
+            }
+        }
+    };
+}
+
+robust_coordinates!(f32);
+robust_coordinates!(f64);
+
+#[cfg(test)]
+mod tests {
+    use super::Robust as R;
+    use crate::{Midpoint, QuadTree as Q};
+
+    #[test]
+    fn robust() {
+        assert_eq!(R(1.0).midpoint(R(3.0)), R(2.0));
+        let huge = R(f64::MAX).midpoint(R(f64::MAX / 2.0));
+        assert!(huge.0.is_finite() && huge > R(f64::MAX / 2.0));
+        assert_eq!(R(f64::NEG_INFINITY).midpoint(R(f64::INFINITY)), R(0.0));
+        let one = R(1.0f32);
+        let next = R(f32::from_bits(1.0f32.to_bits() + 1));
+        let middle = one.midpoint(next);
+        assert!(middle == one || middle == next);
+
+        // The plain floats overflow the stack on this one.
+        let mut qt = Q::with_node_capacity(1, (R(1e308), R(1.7e308), R(0.0), R(1.0)));
+        for i in 0..20 {
+            assert!(qt.insert((R(1e308 + i as f64 * 3e306), R(0.5))));
+        }
+        let found = qt.search(&(R(1.3e308), R(1.7e308), R(0.0), R(1.0)));
+        assert_eq!(found.len(), 10);
+
+        let mut open = Q::with_node_capacity(1, (R(0.0), R(f64::INFINITY), R(0.0), R(1.0)));
+        for x in [1.0, 2.0, 1e200, f64::MAX].iter() {
+            assert!(open.insert((R(*x), R(0.5))));
+        }
+        assert_eq!(open.search(&(R(1.5), R(1e201), R(0.0), R(1.0))).len(), 2);
+        assert_eq!(
+            Q::from_bytes(&open.to_bytes())
+                .unwrap()
+                .search(open.get_boundary()),
+            open.search(open.get_boundary())
+        );
+    }
+}
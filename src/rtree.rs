@@ -0,0 +1,61 @@
+use crate::{Boundary, Midpoint, QuadTree};
+use rstar::{RTree, RTreeNum};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + RTreeNum,
+{
+    /// Bulk loads every point in the tree into an `rstar::RTree`.
+    pub fn to_rtree(&self) -> RTree<[T; 2]> {
+        let points = self.search(self.get_boundary());
+        RTree::bulk_load(points.into_iter().map(|(x, y)| [x, y]).collect())
+    }
+
+    /// Builds a quadtree from the points of an `rstar::RTree`. Points outside `boundary` are
+    /// dropped, the same way `insert` would drop them.
+    pub fn from_rtree(boundary: Boundary<T>, rtree: &RTree<[T; 2]>) -> Self {
+        let mut qt = QuadTree::new(boundary);
+        for [x, y] in rtree.iter() {
+            qt.insert((*x, *y));
+        }
+        qt
+    }
+}
+
+impl<T> From<&QuadTree<T>> for RTree<[T; 2]>
+where
+    T: PartialOrd + Copy + Midpoint + RTreeNum,
+{
+    fn from(qt: &QuadTree<T>) -> Self {
+        qt.to_rtree()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+    use rstar::{RTree, AABB};
+
+    #[test]
+    fn round_trip() {
+        let mut qt = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            qt.insert((i, (i * 7) % 100));
+        }
+
+        let rtree: RTree<[i32; 2]> = (&qt).into();
+        assert_eq!(rtree.size(), qt.size());
+        let envelope = AABB::from_corners([10, 10], [29, 29]);
+        let mut from_rtree: Vec<_> = rtree
+            .locate_in_envelope(envelope)
+            .map(|[x, y]| (*x, *y))
+            .collect();
+        let mut from_qt = qt.search(&(10, 30, 10, 30));
+        from_rtree.sort();
+        from_qt.sort();
+        assert_eq!(from_rtree, from_qt);
+
+        let back = Q::from_rtree((0, 50, 0, 100), &rtree);
+        assert_eq!(back.size(), 50);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Midpoint, Point, QuadTree};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Picks `n` points at random, every point being equally likely each time, so the same point
+    /// can come up more than once. Each pick walks down a single path of the tree, using the sizes
+    /// nodes keep to choose a child, so it's quick no matter how big the tree is. Empty if the
+    /// tree is.
+    ///
+    /// `rng` should return uniformly random `u64`s, e.g. `|| rng.next_u64()` with the `rand`
+    /// crate.
+    pub fn sample<R: FnMut() -> u64>(&self, n: usize, rng: &mut R) -> Vec<Point<T>> {
+        let size = self.size();
+        if size == 0 {
+            return vec![];
+        }
+        (0..n)
+            .map(|_| self.nth_point((rng() % size as u64) as usize))
+            .collect()
+    }
+
+    /// The `i`th point, counting leaves from the first child on.
+    fn nth_point(&self, mut i: usize) -> Point<T> {
+        let mut node = self;
+        loop {
+            match node {
+                QuadTree::Leaf(_, _, points) => return points[i],
+                QuadTree::Node(_, _, _, children) => {
+                    for child in children.iter() {
+                        let size = child.size();
+                        if i < size {
+                            node = child;
+                            break;
+                        }
+                        i -= size;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn sample() {
+        let mut qt = Q::with_node_capacity(4, (0, 100, 0, 100));
+        // Most points are on the left, most leaves on the right.
+        for i in 0..900 {
+            qt.insert((i % 30, i / 30));
+        }
+        for i in 0..100 {
+            qt.insert((50 + i % 50, i / 2));
+        }
+
+        let mut x = 88_172_645_463_325_252u64;
+        let mut next = || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        let picks = qt.sample(10_000, &mut next);
+        assert_eq!(picks.len(), 10_000);
+        let left = picks.iter().filter(|(x, _)| *x < 50).count();
+        assert!((8500..9500).contains(&left), "{}", left);
+        for p in picks.iter().take(100) {
+            assert_eq!(qt.search(&(p.0, p.0 + 1, p.1, p.1 + 1)), vec![*p]);
+        }
+
+        assert!(Q::<i32>::new((0, 1, 0, 1)).sample(5, &mut next).is_empty());
+    }
+}
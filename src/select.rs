@@ -0,0 +1,211 @@
+use crate::geom::covers;
+use crate::{Boundary, Midpoint, Point, QuadTree};
+use std::cmp::Ordering;
+
+/// One of the two coordinates of a point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    fn of<T: Copy>(self, (x, y): &Point<T>) -> T {
+        match self {
+            Axis::X => *x,
+            Axis::Y => *y,
+        }
+    }
+
+    fn range<T: Copy>(self, (x1, x2, y1, y2): &Boundary<T>) -> (T, T) {
+        match self {
+            Axis::X => (*x1, *x2),
+            Axis::Y => (*y1, *y2),
+        }
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// The point with the `k`th smallest x, counting from 0. Points with the same x are ordered
+    /// by y. `None` if there aren't more than `k` points.
+    pub fn select_by_x(&self, k: usize) -> Option<Point<T>> {
+        self.select_in(self.get_boundary(), Axis::X, k)
+    }
+
+    /// The point with the `k`th smallest y, counting from 0. Points with the same y are ordered
+    /// by x. `None` if there aren't more than `k` points.
+    pub fn select_by_y(&self, k: usize) -> Option<Point<T>> {
+        self.select_in(self.get_boundary(), Axis::Y, k)
+    }
+
+    /// The `q` quantile along `axis` of the points in `region`, so 0.5 is the median. `q` is
+    /// clamped to between 0 and 1, and there's no interpolating: it's the coordinate of the point
+    /// whose rank is closest to `q` of the way through. `None` if there are no points in `region`.
+    pub fn quantile_in(&self, region: &Boundary<T>, axis: Axis, q: f64) -> Option<T> {
+        let n = self.count_in(region);
+        if n == 0 {
+            return None;
+        }
+        let k = ((n - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        self.select_in(region, axis, k).map(|p| axis.of(&p))
+    }
+
+    /// The number of points in `region`, using the sizes nodes keep wherever they're entirely
+    /// inside it.
+    fn count_in(&self, region: &Boundary<T>) -> usize {
+        let boundary = self.get_boundary();
+        if !Self::intersects(boundary, region) {
+            return 0;
+        }
+        if covers(region, boundary) {
+            return self.size();
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                points.iter().filter(|p| Self::contains(region, p)).count()
+            }
+            QuadTree::Node(_, _, _, children) => children.iter().map(|c| c.count_in(region)).sum(),
+        }
+    }
+
+    /// The `k`th point in `region` along `axis`. It narrows down the slab of the tree the answer
+    /// is in, half at a time, the same way the tree was split. Nodes inside the region and one
+    /// half of the slab are counted by the size they keep, and only leaves are looked into.
+    pub(crate) fn select_in(
+        &self,
+        region: &Boundary<T>,
+        axis: Axis,
+        mut k: usize,
+    ) -> Option<Point<T>> {
+        let mut slab = axis.range(self.get_boundary());
+        let mut pending = vec![self];
+        loop {
+            // Split nodes until every one left is inside the region and inside one half of the
+            // slab. Leaves are kept as they are, and dropped once they're outside the slab.
+            let mut nodes = vec![];
+            let mut leaves = vec![];
+            while let Some(node) = pending.pop() {
+                let boundary = node.get_boundary();
+                let (low, high) = axis.range(boundary);
+                if !Self::intersects(boundary, region) || high <= slab.0 || low >= slab.1 {
+                    continue;
+                }
+                match node {
+                    QuadTree::Leaf(_, _, points) => leaves.push((node, points)),
+                    QuadTree::Node(_, _, 0, _) => {}
+                    QuadTree::Node(_, _, _, children) => {
+                        if (low, high) == slab || !covers(region, boundary) {
+                            pending.extend(children.iter());
+                        } else {
+                            nodes.push(node);
+                        }
+                    }
+                }
+            }
+            let in_slab = |p: &Point<T>, (low, high): (T, T)| {
+                let c = axis.of(p);
+                low <= c && c < high && Self::contains(region, p)
+            };
+
+            if nodes.is_empty() {
+                let mut points: Vec<_> = leaves
+                    .iter()
+                    .flat_map(|(_, points)| points.iter().filter(|p| in_slab(p, slab)))
+                    .copied()
+                    .collect();
+                if k >= points.len() {
+                    return None;
+                }
+                let other = match axis {
+                    Axis::X => Axis::Y,
+                    Axis::Y => Axis::X,
+                };
+                let by = |axis: Axis, a: &Point<T>, b: &Point<T>| {
+                    axis.of(a)
+                        .partial_cmp(&axis.of(b))
+                        .unwrap_or(Ordering::Equal)
+                };
+                points.sort_by(|a, b| by(axis, a, b).then_with(|| by(other, a, b)));
+                return Some(points[k]);
+            }
+
+            let mid = slab.0.midpoint(slab.1);
+            let lower = (slab.0, mid);
+            let in_lower = |node: &QuadTree<T>| axis.range(node.get_boundary()).1 <= mid;
+            let count = nodes
+                .iter()
+                .filter(|n| in_lower(n))
+                .map(|n| n.size())
+                .sum::<usize>()
+                + leaves
+                    .iter()
+                    .map(|(_, points)| points.iter().filter(|p| in_slab(p, lower)).count())
+                    .sum::<usize>();
+            let keep_lower = k < count;
+            if keep_lower {
+                slab = lower;
+            } else {
+                k -= count;
+                slab = (mid, slab.1);
+            }
+            pending = nodes
+                .into_iter()
+                .filter(|n| in_lower(n) == keep_lower)
+                .chain(leaves.into_iter().map(|(node, _)| node))
+                .collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Axis;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn select() {
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        let mut rng = crate::tests::get_rng();
+        for _ in 0..3000 {
+            qt.insert((rng.next() as i32, rng.next() as i32));
+        }
+        // A column, so that lots of points share an x.
+        for y in 0..100 {
+            qt.insert((500, y * 10));
+        }
+        let mut by_x = qt.search(&(0, 1000, 0, 1000));
+        by_x.sort();
+        let mut by_y = by_x.clone();
+        by_y.sort_by_key(|&(x, y)| (y, x));
+
+        for k in (0..by_x.len()).step_by(37).chain(Some(by_x.len() - 1)) {
+            assert_eq!(qt.select_by_x(k), Some(by_x[k]));
+            assert_eq!(qt.select_by_y(k), Some(by_y[k]));
+        }
+        assert_eq!(qt.select_by_x(by_x.len()), None);
+        assert_eq!(Q::<i32>::new((0, 1, 0, 1)).select_by_y(0), None);
+    }
+
+    #[test]
+    fn quantile_in() {
+        let mut qt = Q::with_node_capacity(4, (0, 100, 0, 100));
+        for x in 0..100 {
+            for y in 0..10 {
+                qt.insert((x, y * x % 100));
+            }
+        }
+        let region = (20, 61, 0, 50);
+        let mut xs: Vec<_> = qt.search(&region).iter().map(|p| p.0).collect();
+        xs.sort();
+        let median = xs[((xs.len() - 1) as f64 * 0.5).round() as usize];
+        assert_eq!(qt.quantile_in(&region, Axis::X, 0.5), Some(median));
+        assert_eq!(qt.quantile_in(&region, Axis::X, 0.0), Some(20));
+        assert_eq!(qt.quantile_in(&region, Axis::X, 7.0), Some(60));
+        assert_eq!(qt.quantile_in(&region, Axis::Y, 1.0), Some(49));
+        assert_eq!(qt.quantile_in(&(0, 100, 60, 61), Axis::Y, 0.5), Some(60));
+        assert_eq!(qt.quantile_in(&(200, 300, 0, 100), Axis::X, 0.5), None);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{Boundary, Config, Midpoint, Point, QuadTree};
+use std::thread;
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Send,
+{
+    /// Builds a tree on four threads, one per quadrant of the root. Points are snapped and
+    /// partitioned up front, so the threads never touch each other's data, and each builds its
+    /// subtree in place under the root. Points outside `boundary` are dropped.
+    ///
+    /// With a `tolerance` set, near duplicates on opposite sides of the root's seams aren't
+    /// caught, since they end up on different threads.
+    pub fn build_sharded(config: Config<T>, boundary: Boundary<T>, points: Vec<Point<T>>) -> Self {
+        let mut root = QuadTree::with_config(config, boundary);
+        let points: Vec<_> = points
+            .into_iter()
+            .map(|point| root.snap(point))
+            .filter(|point| Self::contains(&boundary, point))
+            .collect();
+        if points.len() <= config.capacity || config.max_depth == Some(0) {
+            for point in points {
+                root.insert(point);
+            }
+            return root;
+        }
+
+        root.subdivide(0);
+        if let QuadTree::Node(_, boundary, size, children) = &mut root {
+            let mut shards: [Vec<Point<T>>; 4] = Default::default();
+            for point in points {
+                shards[Self::quadrant_of(boundary, &point)].push(point);
+            }
+            thread::scope(|scope| {
+                for (child, shard) in children.iter_mut().zip(shards) {
+                    scope.spawn(move || {
+                        for point in shard {
+                            child.insert_contained(point, true, 1, &mut ());
+                        }
+                    });
+                }
+            });
+            *size = children.iter().map(|child| child.size()).sum();
+        }
+        // Duplicates can leave it with too few points to be split up.
+        root.merge_if_small(0, &mut ());
+        root
+    }
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// Puts four trees together under a new root. They have to cover `quadrants(&boundary)`, in
+    /// that order, or you get `None`. This is how you'd assemble trees built separately, e.g. on
+    /// different machines. The root uses the config of the first tree.
+    pub fn from_quadrants(boundary: Boundary<T>, trees: [QuadTree<T>; 4]) -> Option<Self> {
+        let quadrants = Self::quadrants(&boundary);
+        if trees
+            .iter()
+            .zip(quadrants.iter())
+            .any(|(tree, quadrant)| tree.get_boundary() != quadrant)
+        {
+            return None;
+        }
+        let config = *trees[0].get_config();
+        let [a, b, c, d] = trees;
+        let size = a.size() + b.size() + c.size() + d.size();
+        Some(QuadTree::Node(
+            config,
+            boundary,
+            size,
+            Box::new([a, b, c, d]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Grid, QuadTree as Q};
+
+    #[test]
+    fn build_sharded() {
+        let config = Config {
+            capacity: 8,
+            ..Config::default()
+        };
+        let mut points = vec![];
+        for i in 0..100 {
+            for j in 0..100 {
+                points.push((i * 3, j * 7));
+            }
+        }
+        points.push((1, 1));
+        points.push((1000, 1000));
+
+        let sharded = Q::build_sharded(config, (0, 500, 0, 500), points.clone());
+        let mut sequential = Q::with_config(config, (0, 500, 0, 500));
+        for p in points {
+            sequential.insert(p);
+        }
+        assert_eq!(sharded.size(), sequential.size());
+        let mut a = sharded.search(&(100, 250, 30, 400));
+        let mut b = sequential.search(&(100, 250, 30, 400));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+
+        let few = Q::build_sharded(config, (0, 10, 0, 10), vec![(1, 1), (2, 2)]);
+        assert!(matches!(few, Q::Leaf(_, _, _)));
+        let same = Q::build_sharded(config, (0, 10, 0, 10), vec![(1, 1); 20]);
+        assert!(matches!(same, Q::Leaf(_, _, _)));
+
+        // (52, 10) snaps across the seam to (30, 0).
+        let grid = Config {
+            capacity: 1,
+            grid: Some(Grid::new(30)),
+            ..Config::default()
+        };
+        let points = vec![(52, 10), (5, 5), (90, 90)];
+        let sharded = Q::build_sharded(grid, (0, 100, 0, 100), points);
+        assert_eq!(sharded.size(), 3);
+        assert_eq!(sharded.search(&(30, 31, 0, 1)), vec![(30, 0)]);
+
+        // The root's children are a level down, so this is as deep as it goes.
+        let shallow = Config {
+            capacity: 1,
+            max_depth: Some(1),
+            ..Config::default()
+        };
+        let points: Vec<_> = (0..20).map(|i| (i, i)).collect();
+        let sharded = Q::build_sharded(shallow, (0, 100, 0, 100), points);
+        let mut deepest = 0;
+        sharded.visit_cells(&|_| true, &mut |cell| deepest = deepest.max(cell.depth));
+        assert_eq!(deepest, 1);
+        assert_eq!(sharded.size(), 20);
+    }
+
+    #[test]
+    fn from_quadrants() {
+        let quadrants = Q::<i32>::quadrants(&(0, 10, 0, 10));
+        let trees = quadrants.map(Q::new);
+        assert!(Q::from_quadrants((0, 10, 0, 10), trees).is_some());
+
+        let trees = quadrants.map(Q::new);
+        assert!(Q::from_quadrants((0, 12, 0, 10), trees).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Midpoint, QuadTree};
+use arc_swap::{ArcSwap, Guard};
+use std::sync::Arc;
+
+/// Hands out immutable snapshots of a tree to any number of reader threads without locking,
+/// while a writer prepares new versions on the side and swaps them in.
+///
+/// Readers that grabbed a snapshot keep seeing it until they drop it, even if a new version has
+/// been published in the meantime. There's meant to be a single writer. Two threads calling
+/// `update` at the same time won't corrupt anything, but one of their changes will be lost.
+#[derive(Debug)]
+pub struct SharedQuadTree<T: PartialOrd + Copy + Midpoint> {
+    current: ArcSwap<QuadTree<T>>,
+}
+
+impl<T> SharedQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(tree: QuadTree<T>) -> Self {
+        SharedQuadTree {
+            current: ArcSwap::from_pointee(tree),
+        }
+    }
+
+    /// A cheap, short lived handle to the current tree. Good for answering one query.
+    pub fn load(&self) -> Guard<Arc<QuadTree<T>>> {
+        self.current.load()
+    }
+
+    /// The current tree, for holding on to for a while.
+    pub fn snapshot(&self) -> Arc<QuadTree<T>> {
+        self.current.load_full()
+    }
+
+    /// Replaces the tree with one built elsewhere.
+    pub fn publish(&self, tree: QuadTree<T>) {
+        self.current.store(Arc::new(tree));
+    }
+
+    /// Copies the current tree, lets `f` change the copy, and publishes it.
+    pub fn update<F: FnOnce(&mut QuadTree<T>)>(&self, f: F) {
+        let mut tree = QuadTree::clone(&self.current.load());
+        f(&mut tree);
+        self.publish(tree);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedQuadTree;
+    use crate::QuadTree;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn readers_and_a_writer() {
+        let shared = Arc::new(SharedQuadTree::new(QuadTree::new((0, 100, 0, 100))));
+        let before = shared.snapshot();
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..1000 {
+                        // Versions only ever grow, and each one is complete.
+                        let size = shared.load().size();
+                        assert!(size >= last);
+                        assert_eq!(size % 10, 0);
+                        last = size;
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..10 {
+            shared.update(|tree| {
+                for j in 0..10 {
+                    tree.insert((i, j));
+                }
+            });
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        assert_eq!(shared.load().size(), 100);
+        assert_eq!(before.size(), 0);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::format::{self, Crc32, FixedBytes, NODE, NODE_SIZE};
+use crate::{Boundary, Config, Midpoint, Point, QuadTree};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Past this depth buckets are built in memory no matter how many points they hold. Only happens
+/// when a huge number of duplicates keeps landing in the same spot.
+const MAX_SPILL_DEPTH: usize = 32;
+
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// Builds a tree from more points than fit in memory and writes it to `out` in the format of the
+/// `format` module, ready to be memory mapped and queried with `MappedQuadTree`.
+///
+/// At most `max_in_memory` points are held at once. When there are more, they're spilled into
+/// one temporary file per quadrant under `temp_dir`, and each quadrant is built the same way, one
+/// after the other. A quadrant that fits is built as a normal tree with `config` and written out
+/// before the next one is started. Points outside `boundary` are dropped.
+pub fn write_streaming<T, I, W>(
+    config: Config<T>,
+    boundary: Boundary<T>,
+    points: I,
+    max_in_memory: usize,
+    temp_dir: &Path,
+    out: &mut W,
+) -> io::Result<()>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+    I: IntoIterator<Item = Point<T>>,
+    W: Write,
+{
+    let nodes_path = temp_path(temp_dir);
+    let points_path = temp_path(temp_dir);
+    let result = (|| {
+        let mut loader = Loader {
+            config,
+            max_in_memory: max_in_memory.max(1),
+            temp_dir,
+            nodes: OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&nodes_path)?,
+            points: BufWriter::new(File::create(&points_path)?),
+            next_node: 1,
+            next_point: 0,
+        };
+        let mut source = points.into_iter().map(Ok);
+        loader.build(boundary, &mut source, 0, 0)?;
+        loader.points.flush()?;
+
+        let mut summed = Summed {
+            out: &mut *out,
+            crc: Crc32::new(),
+            length: 0,
+        };
+        summed.write_all(&format::header(
+            &config,
+            loader.next_node,
+            loader.next_point,
+        ))?;
+        loader.nodes.seek(SeekFrom::Start(0))?;
+        io::copy(&mut loader.nodes, &mut summed)?;
+        io::copy(&mut File::open(&points_path)?, &mut summed)?;
+        let (length, crc) = (summed.length, summed.crc);
+        out.write_all(&format::trailer_for(length, crc))
+    })();
+    let _ = fs::remove_file(&nodes_path);
+    let _ = fs::remove_file(&points_path);
+    result
+}
+
+struct Loader<'a, T> {
+    config: Config<T>,
+    max_in_memory: usize,
+    temp_dir: &'a Path,
+    nodes: File,
+    points: BufWriter<File>,
+    next_node: usize,
+    next_point: usize,
+}
+
+impl<T> Loader<'_, T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Builds the subtree for `boundary` out of `source`, with its root at node `index`.
+    fn build<S>(
+        &mut self,
+        boundary: Boundary<T>,
+        source: &mut S,
+        index: usize,
+        depth: usize,
+    ) -> io::Result<()>
+    where
+        S: Iterator<Item = io::Result<Point<T>>>,
+    {
+        let mut buffer = Vec::with_capacity(self.max_in_memory.min(1 << 16));
+        for point in source.by_ref() {
+            let point = point?;
+            if QuadTree::contains(&boundary, &point) {
+                buffer.push(point);
+                if buffer.len() > self.max_in_memory && depth < MAX_SPILL_DEPTH {
+                    break;
+                }
+            }
+        }
+
+        if buffer.len() <= self.max_in_memory || depth >= MAX_SPILL_DEPTH {
+            // Deep down a quadrant can end up with no area, which `with_config` won't take.
+            let mut tree = QuadTree::Leaf(self.config, boundary, vec![]);
+            for point in buffer {
+                tree.insert(point);
+            }
+            return self.write_subtree(&tree, index);
+        }
+
+        // Too many. Spread them (and everything left in the source) over the four quadrants.
+        let quadrants = QuadTree::quadrants(&boundary);
+        let paths: Vec<PathBuf> = (0..4).map(|_| temp_path(self.temp_dir)).collect();
+        let result = (|| {
+            let mut files = paths
+                .iter()
+                .map(|p| File::create(p).map(BufWriter::new))
+                .collect::<io::Result<Vec<_>>>()?;
+            for point in buffer.into_iter().map(Ok).chain(source) {
+                let (x, y) = point?;
+                if let Some(i) = quadrants
+                    .iter()
+                    .position(|q| QuadTree::contains(q, &(x, y)))
+                {
+                    files[i].write_all(&x.to_bytes())?;
+                    files[i].write_all(&y.to_bytes())?;
+                }
+            }
+            for file in files.iter_mut() {
+                file.flush()?;
+            }
+            drop(files);
+
+            let first_child = self.next_node;
+            self.next_node += 4;
+            self.write_record(index, &format::node_record(&boundary, NODE, first_child, 0))?;
+            for (i, (path, quadrant)) in paths.iter().zip(quadrants.iter()).enumerate() {
+                let mut reader = PointReader::new(BufReader::new(File::open(path)?));
+                self.build(*quadrant, &mut reader, first_child + i, depth + 1)?;
+                fs::remove_file(path)?;
+            }
+            Ok(())
+        })();
+        for path in &paths {
+            let _ = fs::remove_file(path);
+        }
+        result
+    }
+
+    fn write_subtree(&mut self, tree: &QuadTree<T>, index: usize) -> io::Result<()> {
+        let mut records = vec![];
+        let mut points = vec![];
+        format::lay_out(
+            tree,
+            index,
+            &mut self.next_node,
+            &mut self.next_point,
+            &mut |i, record| records.push((i, record)),
+            &mut points,
+        );
+        for (i, record) in records {
+            self.write_record(i, &record)?;
+        }
+        self.points.write_all(&points)
+    }
+
+    fn write_record(&mut self, index: usize, record: &[u8; NODE_SIZE]) -> io::Result<()> {
+        self.nodes
+            .seek(SeekFrom::Start((index * NODE_SIZE) as u64))?;
+        self.nodes.write_all(record)
+    }
+}
+
+/// Reads back the points a quadrant was spilled into.
+struct PointReader<R, T> {
+    reader: R,
+    point: std::marker::PhantomData<T>,
+}
+
+impl<R: Read, T: FixedBytes> PointReader<R, T> {
+    fn new(reader: R) -> Self {
+        PointReader {
+            reader,
+            point: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<R: Read, T: FixedBytes> Iterator for PointReader<R, T> {
+    type Item = io::Result<Point<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut bytes = [0; 16];
+        match self.reader.read_exact(&mut bytes) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        }
+        let mut x = [0; 8];
+        let mut y = [0; 8];
+        x.copy_from_slice(&bytes[..8]);
+        y.copy_from_slice(&bytes[8..]);
+        Some(Ok((T::from_bytes(x), T::from_bytes(y))))
+    }
+}
+
+/// Passes everything on to `out`, keeping count and a checksum for the trailer.
+struct Summed<'a, W> {
+    out: &'a mut W,
+    crc: Crc32,
+    length: usize,
+}
+
+impl<W: Write> Write for Summed<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.out.write(buf)?;
+        self.crc.update(&buf[..n]);
+        self.length += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn temp_path(dir: &Path) -> PathBuf {
+    let n = NEXT_FILE.fetch_add(1, Ordering::Relaxed);
+    dir.join(format!("quadtree-spill-{}-{}", std::process::id(), n))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_streaming;
+    use crate::{Config, MappedQuadTree, QuadTree};
+
+    #[test]
+    fn spills_and_matches() {
+        let config = Config {
+            capacity: 8,
+            ..Config::default()
+        };
+        let points = (0..5000u32).map(|i| ((i * 7919) % 1000, (i * 104_729) % 1000));
+        let dir = std::env::temp_dir();
+
+        let mut small = vec![];
+        write_streaming(
+            config,
+            (0, 1000, 0, 1000),
+            points.clone(),
+            300,
+            &dir,
+            &mut small,
+        )
+        .unwrap();
+        let mut big = vec![];
+        write_streaming(
+            config,
+            (0, 1000, 0, 1000),
+            points.clone(),
+            100_000,
+            &dir,
+            &mut big,
+        )
+        .unwrap();
+
+        let mut reference = QuadTree::with_config(config, (0, 1000, 0, 1000));
+        for p in points {
+            reference.insert(p);
+        }
+        assert_eq!(big, reference.to_bytes());
+
+        let mapped = MappedQuadTree::<u32>::new(&small).unwrap();
+        assert_eq!(mapped.size(), reference.size());
+        let mut a = mapped.search(&(100, 400, 250, 900));
+        let mut b = reference.search(&(100, 400, 250, 900));
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert_eq!(
+            QuadTree::<u32>::from_bytes(&small).unwrap().size(),
+            reference.size()
+        );
+
+        // All the same point, way past the memory limit.
+        let mut same = vec![];
+        let dupes = std::iter::repeat_n((5, 5), 1000);
+        write_streaming(config, (0, 1000, 0, 1000), dupes, 10, &dir, &mut same).unwrap();
+        assert_eq!(MappedQuadTree::<u32>::new(&same).unwrap().size(), 1);
+    }
+}
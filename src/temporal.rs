@@ -0,0 +1,262 @@
+use crate::{Boundary, BuildError, Config, Midpoint, Point, QuadTree};
+
+/// The `max_depth` that `new` and `with_node_capacity` use, so lots of events in one spot can't
+/// make the tree subdivide forever.
+const MAX_DEPTH: usize = 32;
+
+/// A quadtree of events: points that each happened at some time. Every node keeps the earliest
+/// and latest time below it, so searches for a time range skip whole parts of the tree that
+/// have nothing in it.
+///
+/// The same point can be inserted any number of times, since it's fine for two things to
+/// happen in the same spot. That's why `Config::duplicates` and `Config::tolerance` are ignored,
+/// and why it needs a `max_depth`. The capacity and grid work like they do for `QuadTree`.
+#[derive(Debug, Clone)]
+pub enum TemporalQuadTree<T: PartialOrd + Copy + Midpoint, Time: PartialOrd + Copy> {
+    Leaf(
+        Config<T>,
+        Boundary<T>,
+        Option<(Time, Time)>,
+        Vec<(Point<T>, Time)>,
+    ),
+    Node(
+        Config<T>,
+        Boundary<T>,
+        Option<(Time, Time)>,
+        [Box<TemporalQuadTree<T, Time>>; 4],
+    ),
+}
+
+impl<T, Time> TemporalQuadTree<T, Time>
+where
+    T: PartialOrd + Copy + Midpoint,
+    Time: PartialOrd + Copy,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Self::with_node_capacity(64, boundary)
+    }
+
+    /// Panics if `capacity` is 0, as every insert would subdivide forever.
+    pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
+        Self::with_config(Self::config(capacity), boundary)
+    }
+
+    /// Same as `with_node_capacity`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_node_capacity(
+        capacity: usize,
+        boundary: Boundary<T>,
+    ) -> Result<Self, BuildError> {
+        Self::try_with_config(Self::config(capacity), boundary)
+    }
+
+    /// Panics if the capacity is 0 or there's no `max_depth`.
+    pub fn with_config(config: Config<T>, boundary: Boundary<T>) -> Self {
+        assert!(config.capacity > 0, "capacity must be at least 1");
+        assert!(
+            config.max_depth.is_some(),
+            "allowing duplicates needs a max depth"
+        );
+        TemporalQuadTree::Leaf(config, boundary, None, vec![])
+    }
+
+    /// Same as `with_config`, but fails instead of panicking.
+    pub fn try_with_config(config: Config<T>, boundary: Boundary<T>) -> Result<Self, BuildError> {
+        if config.capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        if config.max_depth.is_none() {
+            return Err(BuildError::DuplicatesNeedMaxDepth);
+        }
+        Ok(Self::with_config(config, boundary))
+    }
+
+    fn config(capacity: usize) -> Config<T> {
+        Config {
+            capacity,
+            max_depth: Some(MAX_DEPTH),
+            ..Config::default()
+        }
+    }
+
+    /// Adds an event at `point` and `time`. False if the point is outside the tree.
+    pub fn insert(&mut self, point: Point<T>, time: Time) -> bool {
+        let point = match &self.get_config().grid {
+            Some(grid) => grid.snap_point(point),
+            None => point,
+        };
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+        self.insert_contained(point, time, 0);
+        true
+    }
+
+    fn insert_contained(&mut self, point: Point<T>, time: Time, depth: usize) {
+        match self {
+            TemporalQuadTree::Leaf(_, _, times, _) | TemporalQuadTree::Node(_, _, times, _) => {
+                let (first, last) = times.unwrap_or((time, time));
+                let first = if time < first { time } else { first };
+                let last = if time > last { time } else { last };
+                *times = Some((first, last));
+            }
+        }
+
+        if let TemporalQuadTree::Leaf(config, boundary, times, events) = self {
+            let at_max_depth = config.max_depth.is_some_and(|max| depth >= max);
+            if events.len() < config.capacity || at_max_depth {
+                events.push((point, time));
+                return;
+            }
+
+            let leaf = |b| Box::new(TemporalQuadTree::Leaf(*config, b, None, vec![]));
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let mut children = [leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)];
+            for (p, t) in events.drain(..) {
+                children[QuadTree::quadrant_of(boundary, &p)].insert_contained(p, t, depth + 1);
+            }
+            *self = TemporalQuadTree::Node(*config, *boundary, *times, children);
+        }
+
+        if let TemporalQuadTree::Node(_, boundary, _, children) = self {
+            children[QuadTree::quadrant_of(boundary, &point)].insert_contained(
+                point,
+                time,
+                depth + 1,
+            );
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            TemporalQuadTree::Leaf(_, _, _, events) => events.len(),
+            TemporalQuadTree::Node(_, _, _, children) => children.iter().map(|c| c.size()).sum(),
+        }
+    }
+
+    /// The earliest and latest time of any event in the tree, or `None` if it's empty.
+    pub fn time_range(&self) -> Option<(Time, Time)> {
+        match self {
+            TemporalQuadTree::Leaf(_, _, times, _) => *times,
+            TemporalQuadTree::Node(_, _, times, _) => *times,
+        }
+    }
+
+    /// The events inside `boundary` that happened from `t0` up to, but not including, `t1`.
+    pub fn search_in_timerange(
+        &self,
+        boundary: &Boundary<T>,
+        t0: Time,
+        t1: Time,
+    ) -> Vec<(Point<T>, Time)> {
+        let mut found = vec![];
+        self.search_into(boundary, t0, t1, &mut found);
+        found
+    }
+
+    fn search_into(
+        &self,
+        boundary: &Boundary<T>,
+        t0: Time,
+        t1: Time,
+        found: &mut Vec<(Point<T>, Time)>,
+    ) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self.time_range() {
+            Some((first, last)) if first < t1 && last >= t0 => {}
+            _ => return,
+        }
+        match self {
+            TemporalQuadTree::Leaf(_, _, _, events) => found.extend(
+                events
+                    .iter()
+                    .filter(|(p, t)| t0 <= *t && *t < t1 && QuadTree::contains(boundary, p)),
+            ),
+            TemporalQuadTree::Node(_, _, _, children) => {
+                for child in children {
+                    child.search_into(boundary, t0, t1, found);
+                }
+            }
+        }
+    }
+
+    pub fn get_config(&self) -> &Config<T> {
+        match self {
+            TemporalQuadTree::Leaf(config, _, _, _) => config,
+            TemporalQuadTree::Node(config, _, _, _) => config,
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            TemporalQuadTree::Leaf(_, boundary, _, _) => *boundary,
+            TemporalQuadTree::Node(_, boundary, _, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TemporalQuadTree;
+    use crate::{BuildError, Config};
+
+    #[test]
+    fn search_in_timerange() {
+        let mut qt = TemporalQuadTree::with_node_capacity(4, (0, 100, 0, 100));
+        for i in 0..100 {
+            for t in 0..10u64 {
+                qt.insert((i, i), t * 100 + i as u64);
+            }
+        }
+        assert!(!qt.insert((100, 0), 5));
+        assert_eq!(qt.size(), 1000);
+        assert_eq!(qt.time_range(), Some((0, 999)));
+
+        let mut found = qt.search_in_timerange(&(10, 20, 0, 100), 300, 315);
+        found.sort();
+        let expected: Vec<_> = (10..15).map(|i| ((i, i), 300 + i as u64)).collect();
+        assert_eq!(found, expected);
+        assert!(qt
+            .search_in_timerange(&(0, 100, 0, 100), 1000, 2000)
+            .is_empty());
+
+        // Many events in the same spot.
+        let mut same = TemporalQuadTree::with_node_capacity(1, (0, 10, 0, 10));
+        for t in 0..100 {
+            same.insert((3, 3), t);
+        }
+        assert_eq!(same.search_in_timerange(&(3, 4, 3, 4), 10, 20).len(), 10);
+
+        let config = Config {
+            capacity: 1,
+            max_depth: Some(0),
+            ..Config::default()
+        };
+        let mut flat = TemporalQuadTree::with_config(config, (0, 10, 0, 10));
+        for t in 0..10 {
+            flat.insert((t, t), t);
+        }
+        assert!(matches!(flat, TemporalQuadTree::Leaf(_, _, _, _)));
+        assert_eq!(flat.size(), 10);
+        let unbounded = Config {
+            max_depth: None,
+            ..config
+        };
+        assert_eq!(
+            TemporalQuadTree::<u32, u32>::try_with_config(unbounded, (0, 10, 0, 10)).unwrap_err(),
+            BuildError::DuplicatesNeedMaxDepth
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn zero_capacity() {
+        assert_eq!(
+            TemporalQuadTree::<u32, u32>::try_with_node_capacity(0, (0, 10, 0, 10)).unwrap_err(),
+            BuildError::ZeroCapacity
+        );
+        assert!(TemporalQuadTree::<u32, u32>::try_with_node_capacity(1, (0, 10, 0, 10)).is_ok());
+        TemporalQuadTree::<u32, u32>::with_node_capacity(0, (0, 10, 0, 10));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::cells::signed;
+use crate::{Distance, Midpoint, Point, QuadTree};
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + Distance,
+{
+    /// The nearest point to the middle of each cell of a `columns` by `rows` grid over the tree, a
+    /// row at a time from the top. Taking the points as sites, that's a raster of their Voronoi
+    /// cells, for territory or coverage maps. Neighbouring cells mostly have the same site, so
+    /// each search starts out knowing how far the previous cell's site is, and only opens nodes
+    /// that could have something closer. `None` everywhere if the tree is empty.
+    pub fn nearest_site_raster(&self, columns: usize, rows: usize) -> Vec<Option<Point<T>>> {
+        let (x1, x2, y1, y2) = *self.get_boundary();
+        let origin = (x1, y1);
+        let width = signed(&x2, &x1) / columns as f64;
+        let height = signed(&y2, &y1) / rows as f64;
+        let local = |(x, y): &Point<T>| (signed(x, &origin.0), signed(y, &origin.1));
+
+        let mut raster = Vec::with_capacity(columns * rows);
+        let mut previous: Option<Point<T>> = None;
+        for row in 0..rows {
+            for column in 0..columns {
+                let at = ((column as f64 + 0.5) * width, (row as f64 + 0.5) * height);
+                let mut best = previous.map(|site| {
+                    let (sx, sy) = local(&site);
+                    ((sx - at.0).powi(2) + (sy - at.1).powi(2), site)
+                });
+                self.nearest_local(&origin, at, &mut best);
+                previous = best.map(|(_, site)| site);
+                raster.push(previous);
+            }
+        }
+        raster
+    }
+
+    /// Looks for anything closer to `at`, an offset from `origin`, than `best`.
+    fn nearest_local(
+        &self,
+        origin: &Point<T>,
+        (ax, ay): (f64, f64),
+        best: &mut Option<(f64, Point<T>)>,
+    ) {
+        let local = |(x, y): &Point<T>| (signed(x, &origin.0), signed(y, &origin.1));
+        let distance_sq = |tree: &Self| {
+            let (x1, x2, y1, y2) = tree.get_boundary();
+            let (bx1, by1) = local(&(*x1, *y1));
+            let (bx2, by2) = local(&(*x2, *y2));
+            let dx = (bx1 - ax).max(ax - bx2).max(0.0);
+            let dy = (by1 - ay).max(ay - by2).max(0.0);
+            dx * dx + dy * dy
+        };
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                for p in points {
+                    let (px, py) = local(p);
+                    let d = (px - ax).powi(2) + (py - ay).powi(2);
+                    if best.is_none_or(|(worst, _)| d < worst) {
+                        *best = Some((d, *p));
+                    }
+                }
+            }
+            QuadTree::Node(_, _, _, children) => {
+                let mut order: Vec<_> = children
+                    .iter()
+                    .filter(|child| child.size() > 0)
+                    .map(|child| (distance_sq(child), child))
+                    .collect();
+                order.sort_by(|a, b| a.0.total_cmp(&b.0));
+                for (d, child) in order {
+                    if best.is_some_and(|(worst, _)| d >= worst) {
+                        break;
+                    }
+                    child.nearest_local(origin, (ax, ay), best);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nearest::distance_sq;
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn nearest_site_raster() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(4, (0, 1000, 0, 1000));
+        assert_eq!(qt.nearest_site_raster(3, 2), vec![None; 6]);
+        for _ in 0..50 {
+            qt.insert((rng.next(), rng.next()));
+        }
+        let raster = qt.nearest_site_raster(40, 25);
+        assert_eq!(raster.len(), 40 * 25);
+        for (i, site) in raster.iter().enumerate() {
+            let middle = ((i % 40) as u64 * 25 + 12, (i / 40) as u64 * 40 + 20);
+            let expected = qt.nearest(&middle).unwrap();
+            // Ties can go either way, and the middles are half a unit off with integers.
+            let d = |p| distance_sq(&middle, p).sqrt();
+            assert!((d(&site.unwrap()) - d(&expected)).abs() <= 1.0);
+        }
+
+        // Two sites split the map down the middle.
+        let mut two = Q::new((0.0, 100.0, 0.0, 100.0));
+        two.insert((25.0, 50.0));
+        two.insert((75.0, 50.0));
+        let raster = two.nearest_site_raster(4, 1);
+        assert_eq!(
+            raster,
+            [
+                Some((25.0, 50.0)),
+                Some((25.0, 50.0)),
+                Some((75.0, 50.0)),
+                Some((75.0, 50.0))
+            ]
+        );
+    }
+}
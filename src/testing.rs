@@ -0,0 +1,129 @@
+//! Things for checking a tree against something simpler, e.g. in the tests of code built on this
+//! crate. Needs the `test-utils` feature.
+use crate::{Boundary, Point, SpatialIndex};
+use std::cmp::Ordering;
+
+pub use crate::naive::Naive;
+
+/// Two indexes that found different points for the same search. Made by `compare_search`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMismatch<T> {
+    pub boundary: Boundary<T>,
+    /// Found by the first index but not the second. Duplicates count, so a point found twice by
+    /// the first and once by the second is in here once.
+    pub only_in_first: Vec<Point<T>>,
+    pub only_in_second: Vec<Point<T>>,
+}
+
+/// Searches both indexes for `boundary` and checks they found the same points, in any order.
+pub fn compare_search<T, A, B>(
+    first: &A,
+    second: &B,
+    boundary: &Boundary<T>,
+) -> Result<(), SearchMismatch<T>>
+where
+    T: PartialOrd + Copy,
+    A: SpatialIndex<T>,
+    B: SpatialIndex<T>,
+{
+    let mut a = first.search(boundary);
+    let mut b = second.search(boundary);
+    a.sort_by(compare);
+    b.sort_by(compare);
+
+    let mut mismatch = SearchMismatch {
+        boundary: *boundary,
+        only_in_first: vec![],
+        only_in_second: vec![],
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        let order = match (a.get(i), b.get(j)) {
+            (Some(p), Some(q)) => compare(p, q),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match order {
+            Ordering::Less => {
+                mismatch.only_in_first.push(a[i]);
+                i += 1;
+            }
+            Ordering::Greater => {
+                mismatch.only_in_second.push(b[j]);
+                j += 1;
+            }
+            Ordering::Equal => {
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    if mismatch.only_in_first.is_empty() && mismatch.only_in_second.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatch)
+    }
+}
+
+/// `compare_search` for each of `boundaries`, stopping at the first mismatch.
+pub fn compare_searches<'a, T, A, B, I>(
+    first: &A,
+    second: &B,
+    boundaries: I,
+) -> Result<(), SearchMismatch<T>>
+where
+    T: PartialOrd + Copy + 'a,
+    A: SpatialIndex<T>,
+    B: SpatialIndex<T>,
+    I: IntoIterator<Item = &'a Boundary<T>>,
+{
+    boundaries
+        .into_iter()
+        .try_for_each(|boundary| compare_search(first, second, boundary))
+}
+
+/// Panics with what's missing where if the two indexes don't agree on `boundary`, for use in
+/// tests.
+pub fn assert_same_search<T, A, B>(first: &A, second: &B, boundary: &Boundary<T>)
+where
+    T: PartialOrd + Copy + std::fmt::Debug,
+    A: SpatialIndex<T>,
+    B: SpatialIndex<T>,
+{
+    if let Err(mismatch) = compare_search(first, second, boundary) {
+        panic!(
+            "searches for {:?} differ: {:?} only in the first, {:?} only in the second",
+            mismatch.boundary, mismatch.only_in_first, mismatch.only_in_second
+        );
+    }
+}
+
+/// Points with a NaN in them compare equal to everything, so they don't upset the sort.
+fn compare<T: PartialOrd>(a: &Point<T>, b: &Point<T>) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_same_search, compare_search, compare_searches, Naive};
+    use crate::QuadTree;
+
+    #[test]
+    fn finds_mismatches() {
+        let mut naive = Naive::new((0, 10, 0, 10));
+        let mut qt = QuadTree::with_node_capacity(2, (0, 10, 0, 10));
+        for i in 0..10 {
+            naive.insert((i, i));
+            qt.insert((i, i));
+        }
+        qt.insert((2, 7));
+        assert_same_search(&naive, &qt, &(0, 10, 0, 5));
+
+        let everything = (0, 10, 0, 10);
+        let mismatch = compare_searches(&naive, &qt, &[(0, 10, 0, 5), everything]).unwrap_err();
+        assert_eq!(mismatch.boundary, everything);
+        assert_eq!(mismatch.only_in_first, vec![]);
+        assert_eq!(mismatch.only_in_second, vec![(2, 7)]);
+        assert!(compare_search(&qt, &naive, &everything).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Boundary, Midpoint, Point, QuadTree};
+
+/// What's left to draw: a whole subtree, drawn as one of its points, or a single point.
+enum Part<'a, T: PartialOrd + Copy + Midpoint> {
+    Tree(&'a QuadTree<T>),
+    Point(Point<T>),
+}
+
+impl<T> QuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    /// At most `max_points` of the points inside `boundary`, spread out over it, for drawing an
+    /// overview without piling millions of points on top of each other. The tree is split up
+    /// level by level for as long as the pieces overlapping `boundary` fit in `max_points`, and
+    /// each piece is drawn as one point in it. Zoomed in far enough, that's every point.
+    pub fn thin_for_viewport(&self, boundary: &Boundary<T>, max_points: usize) -> Vec<Point<T>> {
+        if max_points == 0 || !Self::intersects(self.get_boundary(), boundary) {
+            return vec![];
+        }
+        let mut parts = vec![Part::Tree(self)];
+        loop {
+            let mut finer = vec![];
+            for part in &parts {
+                match part {
+                    Part::Tree(QuadTree::Node(_, _, _, children)) => finer.extend(
+                        children
+                            .iter()
+                            .filter(|c| {
+                                c.size() > 0 && Self::intersects(c.get_boundary(), boundary)
+                            })
+                            .map(Part::Tree),
+                    ),
+                    Part::Tree(QuadTree::Leaf(_, _, points)) => finer.extend(
+                        points
+                            .iter()
+                            .filter(|p| Self::contains(boundary, p))
+                            .map(|p| Part::Point(*p)),
+                    ),
+                    Part::Point(p) => finer.push(Part::Point(*p)),
+                }
+            }
+            let done = finer.iter().all(|part| matches!(part, Part::Point(_)));
+            if finer.len() > max_points {
+                break;
+            }
+            parts = finer;
+            if done {
+                break;
+            }
+        }
+
+        parts
+            .into_iter()
+            .filter_map(|part| match part {
+                Part::Tree(tree) => tree.any_point_in(boundary),
+                Part::Point(p) => Some(p),
+            })
+            .collect()
+    }
+
+    fn any_point_in(&self, boundary: &Boundary<T>) -> Option<Point<T>> {
+        if !Self::intersects(self.get_boundary(), boundary) {
+            return None;
+        }
+        match self {
+            QuadTree::Leaf(_, _, points) => {
+                points.iter().find(|p| Self::contains(boundary, p)).copied()
+            }
+            QuadTree::Node(_, _, _, children) => children
+                .iter()
+                .find_map(|child| child.any_point_in(boundary)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::QuadTree as Q;
+
+    #[test]
+    fn thin_for_viewport() {
+        let mut rng = crate::tests::get_rng();
+        let mut qt = Q::with_node_capacity(8, (0, 1000, 0, 1000));
+        for _ in 0..10000 {
+            qt.insert((rng.next(), rng.next()));
+        }
+        let view = (100, 700, 200, 900);
+        let thinned = qt.thin_for_viewport(&view, 50);
+        assert!(!thinned.is_empty() && thinned.len() <= 50);
+        assert!(thinned.iter().all(|p| Q::contains(&view, p)));
+        let mut unique = thinned.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), thinned.len());
+
+        // Spread out rather than bunched up in one corner.
+        let right = thinned.iter().filter(|p| p.0 >= 400).count();
+        assert!(right > 0 && right < thinned.len());
+
+        // Zoomed in, everything is drawn.
+        let close = (500, 520, 500, 520);
+        let mut all = qt.thin_for_viewport(&close, 1000);
+        let mut expected = qt.search(&close);
+        all.sort();
+        expected.sort();
+        assert_eq!(all, expected);
+        assert!(qt.thin_for_viewport(&view, 0).is_empty());
+    }
+}
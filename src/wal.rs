@@ -0,0 +1,239 @@
+use crate::format::FixedBytes;
+use crate::{Edit, Midpoint, Point, QuadTree};
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A kind byte and two coordinates.
+const RECORD_SIZE: usize = 1 + 2 * 8;
+
+/// A tree that survives crashes. Every insert and remove that changes it is appended to a log
+/// and synced to disk before it returns, and `recover` replays the log over the last checkpoint.
+///
+/// The checkpoint lives at the path it's made with, and the log next to it with `.wal` added.
+/// Both start with an epoch that `checkpoint` moves on, so a log that was already folded into
+/// the checkpoint is never replayed twice, whenever it crashes. Like `to_bytes`, checkpoints
+/// don't keep tolerance, grid and maximum depth settings.
+#[derive(Debug)]
+pub struct DurableQuadTree<T: PartialOrd + Copy + Midpoint> {
+    tree: QuadTree<T>,
+    path: PathBuf,
+    log: File,
+    epoch: u64,
+}
+
+impl<T> DurableQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    /// Writes `tree` as the first checkpoint at `path`, replacing whatever was there.
+    pub fn create<P: AsRef<Path>>(tree: QuadTree<T>, path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        write_checkpoint(&tree, &path, 0)?;
+        let log = new_log(&path, 0)?;
+        Ok(DurableQuadTree {
+            tree,
+            path,
+            log,
+            epoch: 0,
+        })
+    }
+
+    /// Reads the checkpoint at `path` and replays the log on top of it. A record cut short by a
+    /// crash is dropped.
+    pub fn recover<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let bytes = fs::read(&path)?;
+        let (epoch, bytes) = split_epoch(&bytes)?;
+        let mut tree = QuadTree::from_bytes(bytes).map_err(invalid)?;
+
+        let log_bytes = match fs::read(log_path(&path)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        match split_epoch(&log_bytes) {
+            Ok((log_epoch, records)) if log_epoch == epoch => {
+                let whole = records.len() / RECORD_SIZE * RECORD_SIZE;
+                for record in records[..whole].chunks(RECORD_SIZE) {
+                    match read_record(record)? {
+                        Edit::Inserted(point) => tree.insert(point),
+                        Edit::Removed(point) => tree.remove(&point),
+                    };
+                }
+                let log = OpenOptions::new().write(true).open(log_path(&path))?;
+                log.set_len((8 + whole) as u64)?;
+                drop(log);
+            }
+            // Made before the checkpoint, or never written at all.
+            _ => drop(new_log(&path, epoch)?),
+        }
+        let log = OpenOptions::new().append(true).open(log_path(&path))?;
+        Ok(DurableQuadTree {
+            tree,
+            path,
+            log,
+            epoch,
+        })
+    }
+
+    pub fn tree(&self) -> &QuadTree<T> {
+        &self.tree
+    }
+
+    pub fn insert(&mut self, point: Point<T>) -> io::Result<bool> {
+        let before = self.tree.size();
+        if !self.tree.insert(point) {
+            return Ok(false);
+        }
+        // Already there, so there's nothing to log, and nothing to undo if logging fails.
+        if self.tree.size() == before {
+            return Ok(true);
+        }
+        if let Err(e) = self.append(Edit::Inserted(point)) {
+            // Keep the tree in line with what's on disk.
+            self.tree.remove(&point);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    pub fn remove(&mut self, point: &Point<T>) -> io::Result<bool> {
+        if !self.tree.remove(point) {
+            return Ok(false);
+        }
+        if let Err(e) = self.append(Edit::Removed(*point)) {
+            self.tree.insert(*point);
+            return Err(e);
+        }
+        Ok(true)
+    }
+
+    fn append(&mut self, edit: Edit<T>) -> io::Result<()> {
+        let (kind, (x, y)) = match edit {
+            Edit::Inserted(point) => (0, point),
+            Edit::Removed(point) => (1, point),
+        };
+        let mut record = [0; RECORD_SIZE];
+        record[0] = kind;
+        record[1..9].copy_from_slice(&x.to_bytes());
+        record[9..].copy_from_slice(&y.to_bytes());
+        self.log.write_all(&record)?;
+        self.log.sync_data()
+    }
+
+    /// Writes the whole tree as a new checkpoint and starts an empty log, so recovering doesn't
+    /// have to replay everything since the start.
+    pub fn checkpoint(&mut self) -> io::Result<()> {
+        let epoch = self.epoch + 1;
+        write_checkpoint(&self.tree, &self.path, epoch)?;
+        self.log = new_log(&self.path, epoch)?;
+        self.epoch = epoch;
+        Ok(())
+    }
+}
+
+fn log_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".wal");
+    PathBuf::from(name)
+}
+
+/// Writes next to `to` and renames it over, so a crash leaves either the old file or the new one.
+fn replace(to: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut name = to.as_os_str().to_owned();
+    name.push(".tmp");
+    let tmp = PathBuf::from(name);
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, to)
+}
+
+fn write_checkpoint<T>(tree: &QuadTree<T>, path: &Path, epoch: u64) -> io::Result<()>
+where
+    T: PartialOrd + Copy + Midpoint + FixedBytes,
+{
+    let mut bytes = epoch.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&tree.to_bytes());
+    replace(path, &bytes)
+}
+
+fn new_log(path: &Path, epoch: u64) -> io::Result<File> {
+    replace(&log_path(path), &epoch.to_le_bytes())?;
+    OpenOptions::new().append(true).open(log_path(path))
+}
+
+fn split_epoch(bytes: &[u8]) -> io::Result<(u64, &[u8])> {
+    if bytes.len() < 8 {
+        return Err(invalid("missing epoch"));
+    }
+    let epoch = u64::from_le_bytes(bytes[..8].try_into().expect("8 bytes"));
+    Ok((epoch, &bytes[8..]))
+}
+
+fn read_record<T: FixedBytes>(record: &[u8]) -> io::Result<Edit<T>> {
+    let coordinate = |at: usize| T::from_bytes(record[at..at + 8].try_into().expect("8 bytes"));
+    let point = (coordinate(1), coordinate(9));
+    match record[0] {
+        0 => Ok(Edit::Inserted(point)),
+        1 => Ok(Edit::Removed(point)),
+        _ => Err(invalid("unknown log record")),
+    }
+}
+
+fn invalid<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{log_path, DurableQuadTree};
+    use crate::QuadTree as Q;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn recover() {
+        let path = std::env::temp_dir().join(format!("quadtree-wal-{}", std::process::id()));
+        let mut durable = DurableQuadTree::create(Q::new((0u32, 100, 0, 100)), &path).unwrap();
+        for i in 0..50 {
+            assert!(durable.insert((i, i)).unwrap());
+        }
+        let logged = std::fs::metadata(log_path(&path)).unwrap().len();
+        assert!(durable.insert((5, 5)).unwrap());
+        assert_eq!(std::fs::metadata(log_path(&path)).unwrap().len(), logged);
+        assert!(durable.remove(&(3, 3)).unwrap());
+        assert!(!durable.remove(&(3, 3)).unwrap());
+        drop(durable);
+
+        // A crash half way through writing a record.
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(log_path(&path))
+            .unwrap();
+        log.write_all(&[0, 1, 2]).unwrap();
+        drop(log);
+
+        let mut durable = DurableQuadTree::<u32>::recover(&path).unwrap();
+        assert_eq!(durable.tree().size(), 49);
+        durable.checkpoint().unwrap();
+        durable.insert((99, 99)).unwrap();
+        drop(durable);
+
+        let durable = DurableQuadTree::<u32>::recover(&path).unwrap();
+        assert_eq!(durable.tree().size(), 50);
+        let mut points = durable.tree().search(&(0, 100, 0, 100));
+        points.sort();
+        let mut expected: Vec<_> = (0..50).filter(|&i| i != 3).map(|i| (i, i)).collect();
+        expected.push((99, 99));
+        assert_eq!(points, expected);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(log_path(&path)).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{Boundary, Edit, Midpoint, Observer, Point, QuadTree};
+
+/// Names a region being watched by a `Watcher`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WatchId(u64);
+
+/// Keeps track of inserts and removes inside some regions of a tree. It's an `Observer`, so pass
+/// it to `insert_observed` and `remove_observed`, then `drain` what happened every now and then.
+/// A change inside several watched regions shows up once for each of them.
+#[derive(Debug, Clone)]
+pub struct Watcher<T> {
+    next_id: u64,
+    regions: Vec<(WatchId, Boundary<T>)>,
+    events: Vec<(WatchId, Edit<T>)>,
+}
+
+impl<T> Default for Watcher<T> {
+    fn default() -> Self {
+        Watcher {
+            next_id: 0,
+            regions: vec![],
+            events: vec![],
+        }
+    }
+}
+
+impl<T> Watcher<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts reporting changes inside `boundary`.
+    pub fn watch(&mut self, boundary: Boundary<T>) -> WatchId {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        self.regions.push((id, boundary));
+        id
+    }
+
+    /// Stops reporting changes for `id`. Its changes that haven't been drained yet are dropped.
+    /// Returns whether it was being watched.
+    pub fn unwatch(&mut self, id: WatchId) -> bool {
+        let before = self.regions.len();
+        self.regions.retain(|(i, _)| *i != id);
+        self.events.retain(|(i, _)| *i != id);
+        self.regions.len() != before
+    }
+
+    /// Takes every change since the last call, oldest first.
+    pub fn drain(&mut self) -> std::vec::Drain<'_, (WatchId, Edit<T>)> {
+        self.events.drain(..)
+    }
+
+    fn record(&mut self, point: &Point<T>, edit: Edit<T>) {
+        for (id, boundary) in &self.regions {
+            if QuadTree::contains(boundary, point) {
+                self.events.push((*id, edit));
+            }
+        }
+    }
+}
+
+impl<T> Observer<T> for Watcher<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    fn inserted(&mut self, point: &Point<T>, _: &Boundary<T>, _: usize) {
+        self.record(point, Edit::Inserted(*point));
+    }
+
+    fn removed(&mut self, point: &Point<T>, _: &Boundary<T>, _: usize) {
+        self.record(point, Edit::Removed(*point));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Watcher;
+    use crate::{Edit, QuadTree};
+
+    #[test]
+    fn watch() {
+        let mut qt = QuadTree::new((0, 100, 0, 100));
+        let mut watcher = Watcher::new();
+        let left = watcher.watch((0, 50, 0, 100));
+        let corner = watcher.watch((0, 10, 0, 10));
+
+        qt.insert_observed((5, 5), &mut watcher);
+        qt.insert_observed((5, 5), &mut watcher);
+        qt.insert_observed((70, 5), &mut watcher);
+        qt.insert_observed((20, 80), &mut watcher);
+        qt.remove_observed(&(5, 5), &mut watcher);
+        assert_eq!(
+            watcher.drain().collect::<Vec<_>>(),
+            vec![
+                (left, Edit::Inserted((5, 5))),
+                (corner, Edit::Inserted((5, 5))),
+                (left, Edit::Inserted((20, 80))),
+                (left, Edit::Removed((5, 5))),
+                (corner, Edit::Removed((5, 5))),
+            ]
+        );
+        assert_eq!(watcher.drain().count(), 0);
+
+        assert!(watcher.unwatch(left));
+        assert!(!watcher.unwatch(left));
+        qt.insert_observed((1, 1), &mut watcher);
+        qt.insert_observed((30, 30), &mut watcher);
+        assert_eq!(
+            watcher.drain().collect::<Vec<_>>(),
+            vec![(corner, Edit::Inserted((1, 1)))]
+        );
+    }
+}
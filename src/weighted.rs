@@ -0,0 +1,312 @@
+use crate::geom::covers;
+use crate::{Boundary, BuildError, Config, Midpoint, Point, QuadTree};
+
+/// The `max_depth` that `new` and `with_node_capacity` use. Points too close together for the
+/// midpoints to tell apart would otherwise keep splitting leaves forever.
+const MAX_DEPTH: usize = 32;
+
+/// A quadtree where every point has a weight, e.g. how likely something is to spawn there. Every
+/// node keeps the sum of the weights below it, which makes summing over a region and picking
+/// points by weight quick.
+///
+/// Inserting a point that's already there replaces its weight, so `Config::duplicates` and
+/// `Config::tolerance` are ignored. The capacity, grid and max depth work like they do for
+/// `QuadTree`.
+#[derive(Debug, Clone)]
+pub enum WeightedQuadTree<T: PartialOrd + Copy + Midpoint> {
+    Leaf(Config<T>, Boundary<T>, f64, Vec<(Point<T>, f64)>),
+    Node(Config<T>, Boundary<T>, f64, [Box<WeightedQuadTree<T>>; 4]),
+}
+
+impl<T> WeightedQuadTree<T>
+where
+    T: PartialOrd + Copy + Midpoint,
+{
+    pub fn new(boundary: Boundary<T>) -> Self {
+        Self::with_node_capacity(64, boundary)
+    }
+
+    /// Panics if `capacity` is 0, as every insert would subdivide forever.
+    pub fn with_node_capacity(capacity: usize, boundary: Boundary<T>) -> Self {
+        Self::with_config(Self::config(capacity), boundary)
+    }
+
+    /// Same as `with_node_capacity`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_node_capacity(
+        capacity: usize,
+        boundary: Boundary<T>,
+    ) -> Result<Self, BuildError> {
+        Self::try_with_config(Self::config(capacity), boundary)
+    }
+
+    /// Panics if the capacity is 0.
+    pub fn with_config(config: Config<T>, boundary: Boundary<T>) -> Self {
+        assert!(config.capacity > 0, "capacity must be at least 1");
+        WeightedQuadTree::Leaf(config, boundary, 0.0, vec![])
+    }
+
+    /// Same as `with_config`, but fails instead of panicking on a capacity of 0.
+    pub fn try_with_config(config: Config<T>, boundary: Boundary<T>) -> Result<Self, BuildError> {
+        if config.capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        Ok(Self::with_config(config, boundary))
+    }
+
+    fn config(capacity: usize) -> Config<T> {
+        Config {
+            capacity,
+            max_depth: Some(MAX_DEPTH),
+            ..Config::default()
+        }
+    }
+
+    /// Adds `point` with `weight`. If the point is already there, its weight is replaced. False if
+    /// the point is outside the tree.
+    pub fn insert(&mut self, point: Point<T>, weight: f64) -> bool {
+        let point = match &self.get_config().grid {
+            Some(grid) => grid.snap_point(point),
+            None => point,
+        };
+        if !QuadTree::contains(&self.get_boundary(), &point) {
+            return false;
+        }
+        self.insert_contained(point, weight, 0);
+        true
+    }
+
+    /// Totals are summed again from the weights below rather than adjusted by the change, so
+    /// rounding errors can't leave weight on a node whose children have none.
+    fn insert_contained(&mut self, point: Point<T>, weight: f64, depth: usize) {
+        if let WeightedQuadTree::Leaf(config, boundary, total, points) = self {
+            if let Some((_, w)) = points.iter_mut().find(|(p, _)| *p == point) {
+                *w = weight;
+                *total = points.iter().map(|(_, w)| w).sum();
+                return;
+            }
+            let at_max_depth = config.max_depth.is_some_and(|max| depth >= max);
+            if points.len() < config.capacity || at_max_depth {
+                points.push((point, weight));
+                *total = points.iter().map(|(_, w)| w).sum();
+                return;
+            }
+
+            let leaf = |b| Box::new(WeightedQuadTree::Leaf(*config, b, 0.0, vec![]));
+            let [top_lef, bot_lef, top_rig, bot_rig] = QuadTree::quadrants(boundary);
+            let mut children = [leaf(top_lef), leaf(bot_lef), leaf(top_rig), leaf(bot_rig)];
+            for (p, w) in points.drain(..) {
+                children[QuadTree::quadrant_of(boundary, &p)].insert_contained(p, w, depth + 1);
+            }
+            *self = WeightedQuadTree::Node(*config, *boundary, 0.0, children);
+        }
+
+        match self {
+            WeightedQuadTree::Leaf(_, _, _, _) => panic!("We should never be a leaf at this point"),
+            WeightedQuadTree::Node(_, boundary, total, children) => {
+                children[QuadTree::quadrant_of(boundary, &point)].insert_contained(
+                    point,
+                    weight,
+                    depth + 1,
+                );
+                *total = children.iter().map(|c| c.total_weight()).sum();
+            }
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            WeightedQuadTree::Leaf(_, _, _, points) => points.len(),
+            WeightedQuadTree::Node(_, _, _, children) => children.iter().map(|c| c.size()).sum(),
+        }
+    }
+
+    /// The sum of all the weights.
+    pub fn total_weight(&self) -> f64 {
+        match self {
+            WeightedQuadTree::Leaf(_, _, total, _) => *total,
+            WeightedQuadTree::Node(_, _, total, _) => *total,
+        }
+    }
+
+    /// The sum of the weights of the points in `boundary`. Nodes entirely inside it aren't
+    /// descended into.
+    pub fn total_weight_in(&self, boundary: &Boundary<T>) -> f64 {
+        let own = self.get_boundary();
+        if !QuadTree::intersects(&own, boundary) {
+            return 0.0;
+        }
+        if covers(boundary, &own) {
+            return self.total_weight();
+        }
+        match self {
+            WeightedQuadTree::Leaf(_, _, _, points) => points
+                .iter()
+                .filter(|(p, _)| QuadTree::contains(boundary, p))
+                .map(|(_, w)| w)
+                .sum(),
+            WeightedQuadTree::Node(_, _, _, children) => {
+                children.iter().map(|c| c.total_weight_in(boundary)).sum()
+            }
+        }
+    }
+
+    /// Picks `n` points at random, each one as likely as its share of the total weight. Points can
+    /// come up more than once. Empty if there's no positive weight to pick by. Weights should not
+    /// be negative.
+    ///
+    /// `rng` should return uniformly random `u64`s, like for `QuadTree::sample`.
+    pub fn sample_weighted<R: FnMut() -> u64>(&self, n: usize, rng: &mut R) -> Vec<Point<T>> {
+        if self.total_weight() <= 0.0 || self.total_weight().is_nan() {
+            return vec![];
+        }
+        (0..n)
+            .map(|_| {
+                // The top 53 bits make a float in [0, 1).
+                let r = (rng() >> 11) as f64 / (1u64 << 53) as f64;
+                self.point_at_weight(r * self.total_weight())
+            })
+            .collect()
+    }
+
+    /// The point where the running sum of weights passes `at`. Anything rounding errors push past
+    /// the end lands on the last point with any weight.
+    fn point_at_weight(&self, mut at: f64) -> Point<T> {
+        match self {
+            WeightedQuadTree::Leaf(_, _, _, points) => {
+                let mut last = points[0].0;
+                for (p, w) in points {
+                    if *w > 0.0 {
+                        if at < *w {
+                            return *p;
+                        }
+                        last = *p;
+                    }
+                    at -= w;
+                }
+                last
+            }
+            WeightedQuadTree::Node(_, _, _, children) => {
+                let heavy = children.iter().filter(|c| c.total_weight() > 0.0);
+                let last = heavy
+                    .clone()
+                    .next_back()
+                    .expect("a node with weight has a heavy child");
+                for child in heavy {
+                    if at < child.total_weight() {
+                        return child.point_at_weight(at);
+                    }
+                    at -= child.total_weight();
+                }
+                last.point_at_weight(last.total_weight())
+            }
+        }
+    }
+
+    /// The points in `boundary` along with their weights.
+    pub fn search(&self, boundary: &Boundary<T>) -> Vec<(Point<T>, f64)> {
+        let mut found = vec![];
+        self.search_into(boundary, &mut found);
+        found
+    }
+
+    fn search_into(&self, boundary: &Boundary<T>, found: &mut Vec<(Point<T>, f64)>) {
+        if !QuadTree::intersects(&self.get_boundary(), boundary) {
+            return;
+        }
+        match self {
+            WeightedQuadTree::Leaf(_, _, _, points) => found.extend(
+                points
+                    .iter()
+                    .filter(|(p, _)| QuadTree::contains(boundary, p)),
+            ),
+            WeightedQuadTree::Node(_, _, _, children) => {
+                for child in children {
+                    child.search_into(boundary, found);
+                }
+            }
+        }
+    }
+
+    pub fn get_config(&self) -> &Config<T> {
+        match self {
+            WeightedQuadTree::Leaf(config, _, _, _) => config,
+            WeightedQuadTree::Node(config, _, _, _) => config,
+        }
+    }
+
+    fn get_boundary(&self) -> Boundary<T> {
+        match self {
+            WeightedQuadTree::Leaf(_, boundary, _, _) => *boundary,
+            WeightedQuadTree::Node(_, boundary, _, _) => *boundary,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WeightedQuadTree;
+    use crate::{BuildError, Config};
+
+    #[test]
+    fn weights() {
+        let mut qt = WeightedQuadTree::with_node_capacity(2, (0, 100, 0, 100));
+        for i in 0..100 {
+            qt.insert((i, i), 1.0);
+        }
+        qt.insert((90, 10), 300.0);
+        qt.insert((5, 5), 0.0);
+        assert_eq!(qt.size(), 101);
+        assert_eq!(qt.total_weight(), 399.0);
+        assert_eq!(qt.total_weight_in(&(0, 50, 0, 50)), 49.0);
+        assert_eq!(qt.total_weight_in(&(50, 100, 0, 50)), 300.0);
+
+        let mut x = 88_172_645_463_325_252u64;
+        let mut next = || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        let picks = qt.sample_weighted(4000, &mut next);
+        let heavy = picks.iter().filter(|p| **p == (90, 10)).count();
+        assert!((2800..3200).contains(&heavy), "{}", heavy);
+        assert!(!picks.contains(&(5, 5)));
+
+        let empty = WeightedQuadTree::<i32>::new((0, 1, 0, 1));
+        assert!(empty.sample_weighted(3, &mut next).is_empty());
+
+        // 0.1 + 0.2 - 0.1 - 0.2 isn't 0, but the split leaves nothing to pick.
+        let mut zero = WeightedQuadTree::with_node_capacity(2, (0, 8, 0, 8));
+        zero.insert((1, 1), 0.1);
+        zero.insert((6, 6), 0.2);
+        zero.insert((1, 1), 0.0);
+        zero.insert((6, 6), 0.0);
+        zero.insert((2, 6), 0.0);
+        assert_eq!(zero.total_weight(), 0.0);
+        assert!(zero.sample_weighted(3, &mut next).is_empty());
+
+        // Points closer than the midpoints can tell apart stop splitting at the max depth.
+        let config = Config {
+            capacity: 1,
+            max_depth: Some(4),
+            ..Config::default()
+        };
+        let mut dense = WeightedQuadTree::with_config(config, (0.0, 1.0, 0.0, 1.0));
+        for i in 0..50 {
+            dense.insert((0.5 + i as f64 * 1e-12, 0.5), i as f64);
+        }
+        assert_eq!(dense.size(), 50);
+        assert_eq!(dense.total_weight_in(&(0.5, 0.6, 0.5, 0.6)), 1225.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity must be at least 1")]
+    fn zero_capacity() {
+        assert_eq!(
+            WeightedQuadTree::<u32>::try_with_node_capacity(0, (0, 10, 0, 10)).unwrap_err(),
+            BuildError::ZeroCapacity
+        );
+        assert!(WeightedQuadTree::<u32>::try_with_node_capacity(1, (0, 10, 0, 10)).is_ok());
+        WeightedQuadTree::<u32>::with_node_capacity(0, (0, 10, 0, 10));
+    }
+}